@@ -0,0 +1,90 @@
+use std::thread;
+
+use console::{Key, Term};
+use flume::{unbounded, Receiver};
+use gametetris_rs::{
+    Action, AnsiTermStyle, CoopState, CoopTetris, GameFieldCoop, GameRunner, Gravity, PlayerSide,
+    RuleSet, TermRender,
+};
+
+// Rules given with `--rules <preset|file.json>`, None to keep the default game setup
+fn rules_from_args() -> Option<RuleSet> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--rules").skip(1);
+    let name = args.next()?;
+    Some(RuleSet::load(&name).unwrap_or_else(|err| panic!("{}", err)))
+}
+
+fn start_tetris_thread(inputs: Receiver<(PlayerSide, Action)>) -> Receiver<CoopState> {
+    let (tx, rx) = unbounded();
+    let tetris = match rules_from_args() {
+        Some(rules) => CoopTetris::with_rules(10, 20, rules),
+        None => {
+            let mut tetris = CoopTetris::new(10, 20);
+            // Setup ganme speed
+            tetris.set_gravity(Gravity::RowsPerSecond(3.3));
+            tetris.set_drop_gravity(Gravity::RowsPerSecond(100.0));
+            tetris
+        }
+    };
+
+    GameRunner::new(tetris)
+        .with_input(move || inputs.try_recv().ok())
+        .with_sink(move |state| tx.send(state).is_ok())
+        .spawn();
+    rx
+}
+
+fn start_read_key_thread() -> Receiver<(PlayerSide, Action)> {
+    let term = Term::stdout();
+    let (tx, rx) = unbounded();
+    thread::spawn(move || loop {
+        let key = term.read_key().unwrap();
+        if let Some(action) = key_to_action_player(&key) {
+            tx.send((PlayerSide::Player, action)).unwrap();
+        }
+        if let Some(action) = key_to_action_opponent(&key) {
+            tx.send((PlayerSide::Opponent, action)).unwrap();
+        }
+    });
+    rx
+}
+
+fn key_to_action_player(key: &Key) -> Option<Action> {
+    match key {
+        Key::ArrowLeft => Some(Action::MoveLeft),
+        Key::ArrowRight => Some(Action::MoveRight),
+        Key::ArrowDown => Some(Action::MoveDown),
+        Key::ArrowUp => Some(Action::RotateLeft),
+        Key::Char(' ') => Some(Action::Drop),
+        _ => None,
+    }
+}
+
+fn key_to_action_opponent(key: &Key) -> Option<Action> {
+    match key {
+        Key::Char('a') => Some(Action::MoveLeft),
+        Key::Char('d') => Some(Action::MoveRight),
+        Key::Char('s') => Some(Action::MoveDown),
+        Key::Char('w') => Some(Action::RotateLeft),
+        Key::Char('q') => Some(Action::Drop),
+        _ => None,
+    }
+}
+
+fn main() {
+    let term = Term::stdout();
+
+    let input_rx = start_read_key_thread();
+    let state_rx = start_tetris_thread(input_rx);
+
+    term.clear_screen().unwrap();
+    while let Ok(state) = state_rx.recv() {
+        // Draw tetris field on term
+        let field = GameFieldCoop::new(state, vec!["RIGHT".to_string()], vec!["LEFT".to_string()]);
+        let lines = field.render(&AnsiTermStyle);
+        term.move_cursor_to(0, 0).unwrap();
+        for line in lines {
+            term.write_line(&line).unwrap();
+        }
+    }
+}
@@ -1,62 +1,65 @@
-use std::{thread, time};
+use std::thread;
 
 use console::{Key, Term};
 use flume::{unbounded, Receiver};
 use gametetris_rs::{
-    Action, AnsiTermStyle, GameFieldPair, PlayerSide, StepResult, TermRender, TetrisPair,
-    TetrisPairState,
+    Action, AnsiTermStyle, GameFieldSeries, GameRunner, Gravity, MatchCommand, MatchInput,
+    MatchSeries, MatchSeriesState, PlayerSettings, PlayerSide, RuleSet, TermRender, TetrisPair,
 };
 
-fn start_tetris_thread(
-    player_actions: Receiver<Action>,
-    opponent_actions: Receiver<Action>,
-) -> Receiver<TetrisPairState> {
-    let (tx, rx) = unbounded();
-    thread::spawn(move || {
-        let mut tetris_pair = TetrisPair::new(10, 20);
-
-        // Setup ganme speed
-        let step_delay = time::Duration::from_millis(10);
-        tetris_pair.set_fall_speed(1, 30);
-        tetris_pair.set_drop_speed(1, 1);
-        tetris_pair.set_line_remove_speed(3, 5);
+const BEST_OF: usize = 3;
 
-        loop {
-            let start = time::Instant::now();
-            while let Ok(action) = player_actions.try_recv() {
-                tetris_pair.add_player_action(PlayerSide::Player, action);
-            }
-            while let Ok(action) = opponent_actions.try_recv() {
-                tetris_pair.add_player_action(PlayerSide::Opponent, action);
-            }
-
-            if tetris_pair.step() != (StepResult::None, StepResult::None) {
-                tx.send(tetris_pair.get_state()).unwrap();
-            }
+// Rules given with `--rules <preset|file.json>`, None to keep the default game setup
+fn rules_from_args() -> Option<RuleSet> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--rules").skip(1);
+    let name = args.next()?;
+    Some(RuleSet::load(&name).unwrap_or_else(|err| panic!("{}", err)))
+}
 
-            let elapsed = start.elapsed();
-            if elapsed < step_delay {
-                thread::sleep(step_delay - elapsed);
-            }
+fn start_tetris_thread(inputs: Receiver<MatchInput>) -> Receiver<MatchSeriesState> {
+    let (tx, rx) = unbounded();
+    let mut tetris_pair = match rules_from_args() {
+        Some(rules) => {
+            let settings = PlayerSettings::with_rules(10, 20, rules);
+            TetrisPair::with_settings(&settings, &settings)
         }
-    });
+        None => {
+            let mut tetris_pair = TetrisPair::new(10, 20);
+            // Setup ganme speed
+            tetris_pair.set_gravity(Gravity::RowsPerSecond(3.3));
+            tetris_pair.set_drop_gravity(Gravity::RowsPerSecond(100.0));
+            tetris_pair
+        }
+    };
+    // Both players get the same tetrominos
+    tetris_pair.set_shared_seed(Some(rand::random()));
+
+    let series = MatchSeries::new(tetris_pair, BEST_OF);
+    GameRunner::new(series)
+        .with_input(move || inputs.try_recv().ok())
+        .with_sink(move |state| tx.send(state).is_ok())
+        .spawn();
     rx
 }
 
-fn start_read_key_thread() -> (Receiver<Action>, Receiver<Action>) {
+fn start_read_key_thread() -> Receiver<MatchInput> {
     let term = Term::stdout();
-    let (tx_player, rx_player) = unbounded();
-    let (tx_opponent, rx_opponent) = unbounded();
+    let (tx, rx) = unbounded();
     thread::spawn(move || loop {
         let key = term.read_key().unwrap();
         if let Some(action) = key_to_action_player(&key) {
-            tx_player.send(action).unwrap();
+            tx.send(MatchInput::Action(PlayerSide::Player, action))
+                .unwrap();
         }
         if let Some(action) = key_to_action_opponent(&key) {
-            tx_opponent.send(action).unwrap();
+            tx.send(MatchInput::Action(PlayerSide::Opponent, action))
+                .unwrap();
+        }
+        if let Some((side, command)) = key_to_command(&key) {
+            tx.send(MatchInput::Command(side, command)).unwrap();
         }
     });
-    (rx_player, rx_opponent)
+    rx
 }
 
 fn key_to_action_player(key: &Key) -> Option<Action> {
@@ -66,6 +69,7 @@ fn key_to_action_player(key: &Key) -> Option<Action> {
         Key::ArrowDown => Some(Action::MoveDown),
         Key::ArrowUp => Some(Action::RotateLeft),
         Key::Char(' ') => Some(Action::Drop),
+        Key::Char('m') => Some(Action::Hold),
         _ => None,
     }
 }
@@ -77,6 +81,19 @@ fn key_to_action_opponent(key: &Key) -> Option<Action> {
         Key::Char('s') => Some(Action::MoveDown),
         Key::Char('w') => Some(Action::RotateLeft),
         Key::Char('q') => Some(Action::Drop),
+        Key::Char('z') => Some(Action::Hold),
+        _ => None,
+    }
+}
+
+fn key_to_command(key: &Key) -> Option<(PlayerSide, MatchCommand)> {
+    match key {
+        Key::Enter => Some((PlayerSide::Player, MatchCommand::Rematch)),
+        Key::Char('p') => Some((PlayerSide::Player, MatchCommand::Pause)),
+        Key::Char('r') => Some((PlayerSide::Player, MatchCommand::Resume)),
+        Key::Char('e') => Some((PlayerSide::Opponent, MatchCommand::Rematch)),
+        Key::Char('f') => Some((PlayerSide::Opponent, MatchCommand::Pause)),
+        Key::Char('g') => Some((PlayerSide::Opponent, MatchCommand::Resume)),
         _ => None,
     }
 }
@@ -84,16 +101,13 @@ fn key_to_action_opponent(key: &Key) -> Option<Action> {
 fn main() {
     let term = Term::stdout();
 
-    let (action_rx_player, action_rx_opponent) = start_read_key_thread();
-    let state_rx = start_tetris_thread(
-        action_rx_player,
-        action_rx_opponent,
-    );
+    let input_rx = start_read_key_thread();
+    let state_rx = start_tetris_thread(input_rx);
 
     term.clear_screen().unwrap();
     while let Ok(state) = state_rx.recv() {
         // Draw tetris field on term
-        let field = GameFieldPair::new(state, vec!["PLAYER".to_string()], vec!["OPPONENT".to_string()]);
+        let field = GameFieldSeries::new(state, "PLAYER", "OPPONENT");
         let lines = field.render(&AnsiTermStyle);
         term.move_cursor_to(0, 0).unwrap();
         for line in lines {
@@ -0,0 +1,99 @@
+use std::{thread, time::Duration};
+
+use console::{Key, Term};
+use flume::{unbounded, Receiver};
+use gametetris_rs::{
+    Action, AnsiTermStyle, Delays, GameFieldSingle, GameRunner, MatchCommand, ModeInput, Puzzle,
+    RuleSet, TermRender,
+};
+
+// Bundled pack, used when no file is given
+const BASICS: &str = include_str!("puzzles/basics.txt");
+
+// Puzzles from the file given as the first argument, text or JSON array
+fn load_pack() -> Vec<Puzzle> {
+    let Some(path) = std::env::args().nth(1) else {
+        return Puzzle::pack_from_text(BASICS).expect("bundled puzzles are valid");
+    };
+    let text = std::fs::read_to_string(&path).expect("can't read puzzle pack");
+    if path.ends_with(".json") {
+        serde_json::from_str(&text).expect("invalid puzzle pack")
+    } else {
+        Puzzle::pack_from_text(&text).expect("invalid puzzle pack")
+    }
+}
+
+fn start_read_key_thread() -> Receiver<ModeInput> {
+    let term = Term::stdout();
+    let (tx, rx) = unbounded();
+    thread::spawn(move || loop {
+        let key = term.read_key().unwrap();
+        if let Some(action) = key_to_action(&key) {
+            tx.send(ModeInput::Action(action)).unwrap();
+        }
+        // Enter restarts failed puzzle
+        if key == Key::Enter {
+            tx.send(ModeInput::Command(MatchCommand::Rematch)).unwrap();
+        }
+    });
+    rx
+}
+
+fn key_to_action(key: &Key) -> Option<Action> {
+    match key {
+        Key::ArrowLeft => Some(Action::MoveLeft),
+        Key::ArrowRight => Some(Action::MoveRight),
+        Key::ArrowDown => Some(Action::MoveDown),
+        Key::ArrowUp => Some(Action::RotateRight),
+        Key::Char('z') => Some(Action::RotateLeft),
+        Key::Char(' ') => Some(Action::Drop),
+        Key::Char('c') => Some(Action::Hold),
+        _ => None,
+    }
+}
+
+fn main() {
+    let term = Term::stdout();
+    let pack = load_pack();
+    let input_rx = start_read_key_thread();
+
+    // Puzzles are about placement, not speed
+    let mut rules = RuleSet::guideline();
+    rules.delays = Delays::ZERO;
+
+    for (n, puzzle) in pack.iter().enumerate() {
+        let mut game = puzzle.create_game(rules.clone()).expect("invalid puzzle");
+        game.set_countdown(0, Duration::ZERO);
+        game.restart();
+
+        let (tx, state_rx) = unbounded();
+        let inputs = input_rx.clone();
+        let runner = GameRunner::new(game)
+            .with_input(move || inputs.try_recv().ok())
+            .with_sink(move |state| tx.send(state).is_ok())
+            .spawn();
+
+        term.clear_screen().unwrap();
+        let title = format!("{}/{}: {}", n + 1, pack.len(), puzzle.name);
+        // Share the puzzle with fumen editors, they only support 10 columns
+        let fumen = puzzle
+            .to_fumen()
+            .map(|fumen| format!("Fumen: {}", fumen.encode()))
+            .unwrap_or_default();
+        while let Ok(state) = state_rx.recv() {
+            let solved = state.result.is_some_and(|result| result.is_completed());
+            let field = GameFieldSingle::new(state, vec![title.clone()]);
+            term.move_cursor_to(0, 0).unwrap();
+            for line in field.render(&AnsiTermStyle) {
+                term.write_line(&line).unwrap();
+            }
+            term.write_line(&fumen).unwrap();
+            if solved {
+                thread::sleep(Duration::from_secs(2));
+                break;
+            }
+        }
+        runner.stop_and_join();
+    }
+    term.write_line("All puzzles are solved!").unwrap();
+}
@@ -0,0 +1,119 @@
+use std::{thread, time::Duration};
+
+use console::{Key, Term};
+use flume::{unbounded, Receiver};
+use gametetris_rs::{
+    Action, AnsiTermStyle, GameFieldSingle, GameMode, GameRunner, MatchCommand, ModeInput,
+    ModeStats, RuleSet, SinglePlayer, SinglePlayerState, TermRender, Tetris,
+};
+
+// Value of the `--name value` argument
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+// Mode given with `--mode sprint|ultra|marathon|dig|endless`, sprint by default.
+// `--endless` continues marathon after 150 lines, `--rise <seconds>` makes dig garbage rise
+fn mode_from_args() -> GameMode {
+    match arg_value("--mode").as_deref() {
+        None | Some("sprint") => GameMode::sprint(),
+        Some("ultra") => GameMode::ultra(),
+        Some("marathon") => GameMode::marathon(std::env::args().any(|arg| arg == "--endless")),
+        Some("dig") => match arg_value("--rise") {
+            Some(seconds) => GameMode::dig_survival(Duration::from_secs_f64(
+                seconds.parse().expect("--rise expects seconds"),
+            )),
+            None => GameMode::dig(),
+        },
+        Some("endless") => GameMode::Endless,
+        Some(mode) => panic!(
+            "unknown mode {}, expected sprint, ultra, marathon, dig or endless",
+            mode
+        ),
+    }
+}
+
+// Rules given with `--rules <preset|file.json>`, guideline by default
+fn rules_from_args() -> RuleSet {
+    let Some(name) = arg_value("--rules") else {
+        return RuleSet::guideline();
+    };
+    RuleSet::load(&name).unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn start_tetris_thread(inputs: Receiver<ModeInput>) -> Receiver<SinglePlayerState> {
+    let (tx, rx) = unbounded();
+    let tetris = Tetris::with_rules(10, 20, rules_from_args());
+    let game = SinglePlayer::new(tetris, mode_from_args());
+    GameRunner::new(game)
+        .with_input(move || inputs.try_recv().ok())
+        .with_sink(move |state| tx.send(state).is_ok())
+        .spawn();
+    rx
+}
+
+fn start_read_key_thread() -> Receiver<ModeInput> {
+    let term = Term::stdout();
+    let (tx, rx) = unbounded();
+    thread::spawn(move || loop {
+        let key = term.read_key().unwrap();
+        if let Some(action) = key_to_action(&key) {
+            tx.send(ModeInput::Action(action)).unwrap();
+        }
+        if let Some(command) = key_to_command(&key) {
+            tx.send(ModeInput::Command(command)).unwrap();
+        }
+    });
+    rx
+}
+
+fn key_to_action(key: &Key) -> Option<Action> {
+    match key {
+        Key::ArrowLeft => Some(Action::MoveLeft),
+        Key::ArrowRight => Some(Action::MoveRight),
+        Key::ArrowDown => Some(Action::MoveDown),
+        Key::ArrowUp => Some(Action::RotateRight),
+        Key::Char('z') => Some(Action::RotateLeft),
+        Key::Char(' ') => Some(Action::Drop),
+        Key::Char('c') => Some(Action::Hold),
+        _ => None,
+    }
+}
+
+fn key_to_command(key: &Key) -> Option<MatchCommand> {
+    match key {
+        Key::Enter => Some(MatchCommand::Rematch),
+        Key::Char('p') => Some(MatchCommand::Pause),
+        Key::Char('r') => Some(MatchCommand::Resume),
+        _ => None,
+    }
+}
+
+fn main() {
+    let term = Term::stdout();
+
+    let input_rx = start_read_key_thread();
+    let state_rx = start_tetris_thread(input_rx);
+
+    term.clear_screen().unwrap();
+    let mut finished = false;
+    while let Ok(state) = state_rx.recv() {
+        // Stats screen replaces the well when the game is finished
+        if finished != state.result.is_some() {
+            finished = state.result.is_some();
+            term.clear_screen().unwrap();
+        }
+        let lines = if finished {
+            let mut lines = ModeStats::new(&state).render(&AnsiTermStyle);
+            lines.push(String::new());
+            lines.push("Press Enter to play again".to_string());
+            lines
+        } else {
+            GameFieldSingle::new(state, vec!["PLAYER".to_string()]).render(&AnsiTermStyle)
+        };
+        term.move_cursor_to(0, 0).unwrap();
+        for line in lines {
+            term.write_line(&line).unwrap();
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::{process::Command, thread};
+
+use console::{Key, Term};
+use flume::{unbounded, Receiver};
+use gametetris_rs::{
+    Action, AnsiTermStyle, Bot, BotDifficulty, BotPacing, GameFieldSeries, GameRunner, Gravity,
+    HeuristicBot, MatchCommand, MatchInput, MatchSeries, MatchSeriesState, PlayerSettings,
+    PlayerSide, RuleSet, TbpBot, TermRender, TetrisPair,
+};
+
+const BEST_OF: usize = 3;
+
+// Rules given with `--rules <preset|file.json>`, None to keep the default game setup
+fn rules_from_args() -> Option<RuleSet> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--rules").skip(1);
+    let name = args.next()?;
+    Some(RuleSet::load(&name).unwrap_or_else(|err| panic!("{}", err)))
+}
+
+// Bot difficulty given with `--level <preset|file.json>`, medium by default
+fn difficulty_from_args() -> BotDifficulty {
+    let mut args = std::env::args().skip_while(|arg| arg != "--level").skip(1);
+    let Some(name) = args.next() else {
+        return BotDifficulty::medium();
+    };
+    BotDifficulty::load(&name).unwrap_or_else(|err| panic!("{}", err))
+}
+
+// External bot given with `--tbp "<command> [args]"`, speaking the Tetris Bot Protocol.
+// None to play with the built-in bot
+fn tbp_bot_from_args(pacing: BotPacing) -> Option<TbpBot> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--tbp").skip(1);
+    let command_line = args.next()?;
+    let mut words = command_line.split_whitespace();
+    let mut command = Command::new(words.next().expect("empty bot command"));
+    command.args(words);
+    let bot = TbpBot::spawn(command).expect("can't start the bot");
+    Some(bot.with_pacing(pacing))
+}
+
+fn start_tetris_thread(inputs: Receiver<MatchInput>) -> Receiver<MatchSeriesState> {
+    let (tx, rx) = unbounded();
+    let mut tetris_pair = match rules_from_args() {
+        Some(rules) => {
+            let settings = PlayerSettings::with_rules(10, 20, rules);
+            TetrisPair::with_settings(&settings, &settings)
+        }
+        None => {
+            let mut tetris_pair = TetrisPair::new(10, 20);
+            tetris_pair.set_gravity(Gravity::RowsPerSecond(3.3));
+            tetris_pair.set_drop_gravity(Gravity::RowsPerSecond(100.0));
+            tetris_pair
+        }
+    };
+    // Both players get the same tetrominos
+    tetris_pair.set_shared_seed(Some(rand::random()));
+    let difficulty = difficulty_from_args();
+    let bot: Box<dyn Bot> = match tbp_bot_from_args(difficulty.pacing) {
+        Some(bot) => Box::new(bot),
+        None => Box::new(HeuristicBot::default().with_difficulty(&difficulty)),
+    };
+    tetris_pair.set_bot(PlayerSide::Opponent, Some(bot));
+
+    let series = MatchSeries::new(tetris_pair, BEST_OF);
+    GameRunner::new(series)
+        .with_input(move || inputs.try_recv().ok())
+        .with_sink(move |state| tx.send(state).is_ok())
+        .spawn();
+    rx
+}
+
+fn start_read_key_thread() -> Receiver<MatchInput> {
+    let term = Term::stdout();
+    let (tx, rx) = unbounded();
+    thread::spawn(move || loop {
+        let key = term.read_key().unwrap();
+        if let Some(action) = key_to_action(&key) {
+            tx.send(MatchInput::Action(PlayerSide::Player, action))
+                .unwrap();
+        }
+        if let Some(command) = key_to_command(&key) {
+            tx.send(MatchInput::Command(PlayerSide::Player, command))
+                .unwrap();
+            // The bot agrees to every rematch
+            if command == MatchCommand::Rematch {
+                tx.send(MatchInput::Command(PlayerSide::Opponent, command))
+                    .unwrap();
+            }
+        }
+    });
+    rx
+}
+
+fn key_to_action(key: &Key) -> Option<Action> {
+    match key {
+        Key::ArrowLeft => Some(Action::MoveLeft),
+        Key::ArrowRight => Some(Action::MoveRight),
+        Key::ArrowDown => Some(Action::MoveDown),
+        Key::ArrowUp => Some(Action::RotateRight),
+        Key::Char('z') => Some(Action::RotateLeft),
+        Key::Char(' ') => Some(Action::Drop),
+        Key::Char('c') => Some(Action::Hold),
+        _ => None,
+    }
+}
+
+fn key_to_command(key: &Key) -> Option<MatchCommand> {
+    match key {
+        Key::Enter => Some(MatchCommand::Rematch),
+        Key::Char('p') => Some(MatchCommand::Pause),
+        Key::Char('r') => Some(MatchCommand::Resume),
+        _ => None,
+    }
+}
+
+fn main() {
+    let term = Term::stdout();
+
+    let input_rx = start_read_key_thread();
+    let state_rx = start_tetris_thread(input_rx);
+
+    term.clear_screen().unwrap();
+    while let Ok(state) = state_rx.recv() {
+        let field = GameFieldSeries::new(state, "PLAYER", "BOT");
+        let lines = field.render(&AnsiTermStyle);
+        term.move_cursor_to(0, 0).unwrap();
+        for line in lines {
+            term.write_line(&line).unwrap();
+        }
+    }
+}
@@ -1,27 +1,38 @@
 use std::{sync::Arc, thread};
 
 use console::{Key, Term};
-use gametetris_rs::{Action, AnsiTermStyle, GameFieldPair, TermRender, TetrisPairState};
+use gametetris_rs::{
+    Action, AnsiTermStyle, GameFieldSeries, MatchCommand, MatchSeriesState, TermRender,
+};
 
 use zenoh::{
     prelude::{sync::SyncResolve, Config, KeyExpr},
     Session,
 };
 
-fn start_read_key_thread(session: Arc<Session>, action_keyexpr: KeyExpr) {
+fn start_read_key_thread(session: Arc<Session>, action_keyexpr: KeyExpr, control_keyexpr: KeyExpr) {
     let term = Term::stdout();
     let action_keyexpr = action_keyexpr.clone().into_owned();
+    let control_keyexpr = control_keyexpr.clone().into_owned();
     thread::spawn(move || {
         let publisher = session
             .declare_publisher(&action_keyexpr)
             .res_sync()
             .unwrap();
+        let control_publisher = session
+            .declare_publisher(&control_keyexpr)
+            .res_sync()
+            .unwrap();
         loop {
             let key = term.read_key().unwrap();
             if let Some(action) = key_to_action_player(&key) {
                 let value = serde_json::to_string(&action).unwrap();
                 publisher.put(value).res_sync().unwrap();
             }
+            if let Some(command) = key_to_command(&key) {
+                let value = serde_json::to_string(&command).unwrap();
+                control_publisher.put(value).res_sync().unwrap();
+            }
         }
     });
 }
@@ -33,6 +44,16 @@ fn key_to_action_player(key: &Key) -> Option<Action> {
         Key::ArrowDown => Some(Action::MoveDown),
         Key::ArrowUp => Some(Action::RotateLeft),
         Key::Char(' ') => Some(Action::Drop),
+        Key::Char('c') => Some(Action::Hold),
+        _ => None,
+    }
+}
+
+fn key_to_command(key: &Key) -> Option<MatchCommand> {
+    match key {
+        Key::Enter => Some(MatchCommand::Rematch),
+        Key::Char('p') => Some(MatchCommand::Pause),
+        Key::Char('r') => Some(MatchCommand::Resume),
         _ => None,
     }
 }
@@ -50,13 +71,13 @@ fn main() {
             servers.push(sample.key_expr);
         }
     }
-    if servers.len() == 0 {
+    if servers.is_empty() {
         println!("No servers found");
         return;
     }
     println!("Select server:");
-    for n in 0..servers.len() {
-        println!("{}: {}", n, servers[n]);
+    for (n, server) in servers.iter().enumerate() {
+        println!("{}: {}", n, server);
     }
     let n = loop {
         let mut line = String::new();
@@ -71,8 +92,9 @@ fn main() {
 
     let action_keyexpr = server_keyexpr.join("action").unwrap();
     let gamestate_keyexpr = server_keyexpr.join("gamestate").unwrap();
+    let control_keyexpr = server_keyexpr.join("control").unwrap();
 
-    start_read_key_thread(session.clone(), action_keyexpr);
+    start_read_key_thread(session.clone(), action_keyexpr, control_keyexpr);
 
     let subscriber = session
         .declare_subscriber(&gamestate_keyexpr)
@@ -80,12 +102,12 @@ fn main() {
         .unwrap();
 
     while let Ok(sample) = subscriber.recv() {
-        let mut state: TetrisPairState =
+        let mut state: MatchSeriesState =
             serde_json::from_str(sample.value.to_string().as_str()).unwrap();
 
         // Draw tetris field on term
         state.swap();
-        let field = GameFieldPair::new(state, vec!["PLAYER".to_string()], vec!["OPPONENT".to_string()]);
+        let field = GameFieldSeries::new(state, "PLAYER", "OPPONENT");
         let lines = field.render(&AnsiTermStyle);
         term.move_cursor_to(0, 0).unwrap();
         for line in lines {
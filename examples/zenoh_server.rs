@@ -1,10 +1,10 @@
-use std::{thread, time};
+use std::thread;
 
 use console::{Key, Term};
-use flume::{unbounded, Receiver};
+use flume::{unbounded, Receiver, Sender};
 use gametetris_rs::{
-    Action, AnsiTermStyle, GameFieldPair, PlayerSide, StepResult, TermRender, TetrisPair,
-    TetrisPairState,
+    Action, AnsiTermStyle, GameFieldSeries, GameRunner, Gravity, MatchCommand, MatchInput,
+    MatchSeries, MatchSeriesState, PlayerSettings, PlayerSide, RuleSet, TermRender, TetrisPair,
 };
 use human_hash::humanize;
 use zenoh::{
@@ -13,52 +13,54 @@ use zenoh::{
     sample::Sample,
 };
 
-fn start_tetris_thread(
-    player_actions: Receiver<Action>,
-    opponent_actions: Receiver<Action>,
-) -> Receiver<TetrisPairState> {
+const BEST_OF: usize = 3;
+
+// Rules given with `--rules <preset|file.json>`, None to keep the default game setup
+fn rules_from_args() -> Option<RuleSet> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--rules").skip(1);
+    let name = args.next()?;
+    Some(RuleSet::load(&name).unwrap_or_else(|err| panic!("{}", err)))
+}
+
+fn start_tetris_thread(inputs: Receiver<MatchInput>) -> Receiver<MatchSeriesState> {
     let (tx, rx) = unbounded();
-    thread::spawn(move || {
-        let mut tetris_pair = TetrisPair::new(10, 20);
-
-        // Setup ganme speed
-        let step_delay = time::Duration::from_millis(10);
-        tetris_pair.set_fall_speed(1, 30);
-        tetris_pair.set_drop_speed(1, 1);
-        tetris_pair.set_line_remove_speed(3, 5);
-
-        loop {
-            let start = time::Instant::now();
-            while let Ok(action) = player_actions.try_recv() {
-                tetris_pair.add_player_action(PlayerSide::Player, action);
-            }
-            while let Ok(action) = opponent_actions.try_recv() {
-                tetris_pair.add_player_action(PlayerSide::Opponent, action);
-            }
-
-            if tetris_pair.step() != (StepResult::None, StepResult::None) {
-                tx.send(tetris_pair.get_state()).unwrap();
-            }
-
-            let elapsed = start.elapsed();
-            if elapsed < step_delay {
-                thread::sleep(step_delay - elapsed);
-            }
+    let mut tetris_pair = match rules_from_args() {
+        Some(rules) => {
+            let settings = PlayerSettings::with_rules(10, 20, rules);
+            TetrisPair::with_settings(&settings, &settings)
         }
-    });
+        None => {
+            let mut tetris_pair = TetrisPair::new(10, 20);
+            // Setup ganme speed
+            tetris_pair.set_gravity(Gravity::RowsPerSecond(3.3));
+            tetris_pair.set_drop_gravity(Gravity::RowsPerSecond(100.0));
+            tetris_pair
+        }
+    };
+    // Both players get the same tetrominos
+    tetris_pair.set_shared_seed(Some(rand::random()));
+
+    let series = MatchSeries::new(tetris_pair, BEST_OF);
+    GameRunner::new(series)
+        .with_input(move || inputs.try_recv().ok())
+        .with_sink(move |state| tx.send(state).is_ok())
+        .spawn();
     rx
 }
 
-fn start_read_key_thread() -> Receiver<Action> {
+fn start_read_key_thread(tx: Sender<MatchInput>) {
     let term = Term::stdout();
-    let (tx_player, rx_player) = unbounded();
     thread::spawn(move || loop {
         let key = term.read_key().unwrap();
         if let Some(action) = key_to_action_player(&key) {
-            tx_player.send(action).unwrap();
+            tx.send(MatchInput::Action(PlayerSide::Player, action))
+                .unwrap();
+        }
+        if let Some(command) = key_to_command(&key) {
+            tx.send(MatchInput::Command(PlayerSide::Player, command))
+                .unwrap();
         }
     });
-    rx_player
 }
 
 fn key_to_action_player(key: &Key) -> Option<Action> {
@@ -68,6 +70,16 @@ fn key_to_action_player(key: &Key) -> Option<Action> {
         Key::ArrowDown => Some(Action::MoveDown),
         Key::ArrowUp => Some(Action::RotateLeft),
         Key::Char(' ') => Some(Action::Drop),
+        Key::Char('c') => Some(Action::Hold),
+        _ => None,
+    }
+}
+
+fn key_to_command(key: &Key) -> Option<MatchCommand> {
+    match key {
+        Key::Enter => Some(MatchCommand::Rematch),
+        Key::Char('p') => Some(MatchCommand::Pause),
+        Key::Char('r') => Some(MatchCommand::Resume),
         _ => None,
     }
 }
@@ -83,6 +95,7 @@ fn main() {
     let base_keyexpr = KeyExpr::new(base_keyexpr).unwrap();
     let gamestate_keyexpr = base_keyexpr.join("gamestate").unwrap();
     let action_keyexpr = base_keyexpr.join("action").unwrap();
+    let control_keyexpr = base_keyexpr.join("control").unwrap();
 
     let discovery_callback = {
         let base_keyexpr = base_keyexpr.clone();
@@ -103,11 +116,17 @@ fn main() {
         .res_sync()
         .unwrap();
 
-    let (action_tx_opponent, action_rx_opponent) = unbounded();
-    let action_callback = move |sample: Sample| {
-        let s = sample.value.to_string();
-        let action = serde_json::from_str(s.as_str()).unwrap();
-        action_tx_opponent.send(action).unwrap();
+    // Inputs come both from the local keyboard and from the remote client
+    let (input_tx, input_rx) = unbounded();
+    let action_callback = {
+        let input_tx = input_tx.clone();
+        move |sample: Sample| {
+            let s = sample.value.to_string();
+            let action = serde_json::from_str(s.as_str()).unwrap();
+            input_tx
+                .send(MatchInput::Action(PlayerSide::Opponent, action))
+                .unwrap();
+        }
     };
     let _subscriber = session
         .declare_subscriber(action_keyexpr)
@@ -115,11 +134,24 @@ fn main() {
         .res_sync()
         .unwrap();
 
-    let action_rx_player = start_read_key_thread();
-    let state_rx = start_tetris_thread(
-        action_rx_player,
-        action_rx_opponent,
-    );
+    let control_callback = {
+        let input_tx = input_tx.clone();
+        move |sample: Sample| {
+            let s = sample.value.to_string();
+            let command = serde_json::from_str(s.as_str()).unwrap();
+            input_tx
+                .send(MatchInput::Command(PlayerSide::Opponent, command))
+                .unwrap();
+        }
+    };
+    let _control_subscriber = session
+        .declare_subscriber(control_keyexpr)
+        .callback(control_callback)
+        .res_sync()
+        .unwrap();
+
+    start_read_key_thread(input_tx);
+    let state_rx = start_tetris_thread(input_rx);
 
     term.clear_screen().unwrap();
     while let Ok(state) = state_rx.recv() {
@@ -127,7 +159,7 @@ fn main() {
         publisher.put(value).res_sync().unwrap();
 
         // Draw tetris field on term
-        let field = GameFieldPair::new(state, vec!["PLAYER".to_string()], vec!["OPPONENT".to_string()]);
+        let field = GameFieldSeries::new(state, "PLAYER", "OPPONENT");
         let lines = field.render(&AnsiTermStyle);
         term.move_cursor_to(0, 0).unwrap();
         for line in lines {
@@ -0,0 +1,263 @@
+use crate::tetris::{CellType, Field};
+
+/// Board metrics of a field, all computed in a single pass over its cells.
+/// Ghost cells count as empty, any other cell as filled. Heights are counted from the bottom
+/// of the well, row and column indices are the ones of `Field`, from the top.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldAnalysis {
+    cols: usize,
+    rows: usize,
+    // Filled cells row by row
+    filled: Vec<bool>,
+    // Number of filled cells in each column and row
+    column_cells: Vec<usize>,
+    row_cells: Vec<usize>,
+    heights: Vec<usize>,
+    holes: Vec<(usize, usize)>,
+    covered: usize,
+    row_transitions: usize,
+    column_transitions: usize,
+    // Difference of filled cells on the light and dark squares of a checkerboard
+    parity: isize,
+}
+
+fn is_filled(cell_type: CellType) -> bool {
+    !matches!(cell_type, CellType::Empty | CellType::Ghost)
+}
+
+impl FieldAnalysis {
+    pub fn new(field: &Field) -> Self {
+        let cols = field.cols();
+        let rows = field.rows();
+        let mut analysis = FieldAnalysis {
+            cols,
+            rows,
+            filled: Vec::with_capacity(cols * rows),
+            column_cells: vec![0; cols],
+            row_cells: vec![0; rows],
+            heights: vec![0; cols],
+            holes: Vec::new(),
+            covered: 0,
+            row_transitions: 0,
+            column_transitions: 0,
+            parity: 0,
+        };
+        // Filled cells met in the column going down, they cover holes below
+        let mut above = vec![0; cols];
+        // Cells above the top of the well are empty, walls and floor are filled
+        let mut column_filled = vec![false; cols];
+        for y in 0..rows {
+            let mut row_filled = true;
+            for (x, was_filled) in column_filled.iter_mut().enumerate() {
+                let filled = is_filled(field.get_cell(x, y));
+                analysis.filled.push(filled);
+                if filled {
+                    if analysis.heights[x] == 0 {
+                        analysis.heights[x] = rows - y;
+                    }
+                    above[x] += 1;
+                    analysis.column_cells[x] += 1;
+                    analysis.row_cells[y] += 1;
+                    analysis.parity += if (x + y) % 2 == 0 { 1 } else { -1 };
+                } else if above[x] > 0 {
+                    analysis.holes.push((x, y));
+                    analysis.covered += above[x];
+                }
+                if filled != row_filled {
+                    analysis.row_transitions += 1;
+                }
+                if filled != *was_filled {
+                    analysis.column_transitions += 1;
+                }
+                row_filled = filled;
+                *was_filled = filled;
+            }
+            if !row_filled {
+                analysis.row_transitions += 1;
+            }
+        }
+        analysis.column_transitions += column_filled.iter().filter(|&&f| !f).count();
+        analysis
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    // Height of each column: the number of rows from the floor to its top filled cell
+    pub fn column_heights(&self) -> &[usize] {
+        &self.heights
+    }
+
+    pub fn max_height(&self) -> usize {
+        self.heights.iter().copied().max().unwrap_or(0)
+    }
+
+    pub fn aggregate_height(&self) -> usize {
+        self.heights.iter().sum()
+    }
+
+    pub fn is_filled(&self, x: usize, y: usize) -> bool {
+        x < self.cols && y < self.rows && self.filled[y * self.cols + x]
+    }
+
+    pub fn filled_cells(&self) -> usize {
+        self.column_cells.iter().sum()
+    }
+
+    // Number of filled cells in each row, from the top
+    pub fn row_cells(&self) -> &[usize] {
+        &self.row_cells
+    }
+
+    // Rows with all cells filled
+    pub fn full_rows(&self) -> usize {
+        self.row_cells
+            .iter()
+            .filter(|&&cells| cells == self.cols)
+            .count()
+    }
+
+    // Empty cells with a filled cell somewhere above them in the same column
+    pub fn holes(&self) -> usize {
+        self.holes.len()
+    }
+
+    // Positions (x, y) of the holes, from the top row down
+    pub fn hole_positions(&self) -> &[(usize, usize)] {
+        &self.holes
+    }
+
+    // Columns with at least one hole
+    pub fn columns_with_holes(&self) -> usize {
+        let mut columns = vec![false; self.cols];
+        for &(x, _) in &self.holes {
+            columns[x] = true;
+        }
+        columns.into_iter().filter(|&c| c).count()
+    }
+
+    // Filled cells above the holes, counted once for each hole they cover.
+    // That's how much has to be cleared to uncover all holes
+    pub fn covered_cells(&self) -> usize {
+        self.covered
+    }
+
+    // Depth of the well at each column: how much lower the column is than the lower
+    // of its neighbours. Walls are as high as the well
+    pub fn well_depths(&self) -> Vec<usize> {
+        (0..self.cols)
+            .map(|x| {
+                let left = if x == 0 {
+                    self.rows
+                } else {
+                    self.heights[x - 1]
+                };
+                let right = self.heights.get(x + 1).copied().unwrap_or(self.rows);
+                left.min(right).saturating_sub(self.heights[x])
+            })
+            .collect()
+    }
+
+    // Column and depth of the deepest well, the leftmost one if there are several
+    pub fn deepest_well(&self) -> Option<(usize, usize)> {
+        self.well_depths()
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, depth)| depth > 0)
+            .fold(
+                None,
+                |deepest: Option<(usize, usize)>, (x, depth)| match deepest {
+                    Some((_, deepest_depth)) if deepest_depth >= depth => deepest,
+                    _ => Some((x, depth)),
+                },
+            )
+    }
+
+    // Sum of 1 + 2 + ... + depth over all wells, deep wells weigh more than several shallow ones
+    pub fn cumulative_wells(&self) -> usize {
+        self.well_depths()
+            .into_iter()
+            .map(|depth| depth * (depth + 1) / 2)
+            .sum()
+    }
+
+    // Sum of height differences of neighbouring columns
+    pub fn bumpiness(&self) -> usize {
+        self.heights
+            .windows(2)
+            .map(|pair| pair[0].abs_diff(pair[1]))
+            .sum()
+    }
+
+    // Changes between filled and empty cells along the rows, walls are filled
+    pub fn row_transitions(&self) -> usize {
+        self.row_transitions
+    }
+
+    // Changes between filled and empty cells along the columns, the floor is filled
+    // and the space above the well is empty
+    pub fn column_transitions(&self) -> usize {
+        self.column_transitions
+    }
+
+    // Filled cells on the light squares of a checkerboard minus the ones on the dark squares.
+    // Each tetromino except T covers 2 of each, a T changes the parity by 2
+    pub fn parity(&self) -> isize {
+        self.parity
+    }
+
+    // Necessary condition for clearing the whole well within the bottom `lines` rows:
+    // the stack fits into them and the empty cells of the rows split into areas of
+    // a multiple of 4 cells, so that tetrominos can fill them.
+    // It doesn't guarantee that the given pieces can do it
+    pub fn perfect_clear_possible(&self, lines: usize) -> bool {
+        if lines == 0 || lines > self.rows || self.max_height() > lines {
+            return false;
+        }
+        let top = self.rows - lines;
+        let empty = lines * self.cols - self.row_cells[top..].iter().sum::<usize>();
+        if !empty.is_multiple_of(4) {
+            return false;
+        }
+        // Sizes of connected empty areas
+        let mut visited = vec![vec![false; self.cols]; lines];
+        for start_y in 0..lines {
+            for start_x in 0..self.cols {
+                if visited[start_y][start_x] || self.is_filled(start_x, top + start_y) {
+                    continue;
+                }
+                let mut size: usize = 0;
+                let mut stack = vec![(start_x, start_y)];
+                visited[start_y][start_x] = true;
+                while let Some((x, y)) = stack.pop() {
+                    size += 1;
+                    let neighbours = [
+                        (x.wrapping_sub(1), y),
+                        (x + 1, y),
+                        (x, y.wrapping_sub(1)),
+                        (x, y + 1),
+                    ];
+                    for (nx, ny) in neighbours {
+                        if nx < self.cols
+                            && ny < lines
+                            && !visited[ny][nx]
+                            && !self.is_filled(nx, top + ny)
+                        {
+                            visited[ny][nx] = true;
+                            stack.push((nx, ny));
+                        }
+                    }
+                }
+                if !size.is_multiple_of(4) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{clock::TICK, tetris::Action};
+
+// Auto-repeat timings of held keys, in ticks (see `TICK`)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct AutoRepeatSettings {
+    // Delayed auto shift: how long left/right key must be held before it starts repeating
+    pub das: u32,
+    // Auto repeat rate: delay between repeated moves. 0 moves tetromino to the wall at once
+    pub arr: u32,
+    // DAS cut delay: horizontal repeating is suspended for this time after new tetromino appears
+    pub dcd: u32,
+    // Delay between repeated moves down while soft drop key is held. 0 moves tetromino to the floor
+    pub soft_drop_arr: u32,
+}
+
+impl Default for AutoRepeatSettings {
+    fn default() -> Self {
+        AutoRepeatSettings {
+            das: 17,
+            arr: 3,
+            dcd: 0,
+            soft_drop_arr: 3,
+        }
+    }
+}
+
+// Events of the key bound to the action. Keys of actions other than moves are not repeated
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum KeyEvent {
+    Press(Action),
+    Release(Action),
+}
+
+// Time of key repeating: first repeat is immediate, then one every `rate`
+#[derive(Debug, Default, Clone, Copy)]
+struct Repeat {
+    // Time since the last repeat, None if there was no repeat yet
+    accumulated: Option<Duration>,
+}
+
+impl Repeat {
+    // Number of repeats for `elapsed` time, `max` if rate is zero
+    fn advance(&mut self, elapsed: Duration, rate: Duration, max: usize) -> usize {
+        let (mut repeats, accumulated) = match self.accumulated {
+            Some(accumulated) => (0, accumulated + elapsed),
+            None => (1, elapsed),
+        };
+        if rate.is_zero() {
+            self.accumulated = Some(Duration::ZERO);
+            return max;
+        }
+        repeats += (accumulated.as_nanos() / rate.as_nanos()) as usize;
+        self.accumulated = Some(Duration::from_nanos(
+            (accumulated.as_nanos() % rate.as_nanos()) as u64,
+        ));
+        repeats.min(max)
+    }
+}
+
+/// Turns press and release events of left, right and soft drop keys into repeated actions,
+/// so that holding a key doesn't depend on the keyboard repeat of the terminal.
+/// DAS keeps charging while there is no tetromino to move (entry delay, countdown),
+/// so a held key starts repeating as soon as the next tetromino appears.
+#[derive(Debug, Clone)]
+pub struct AutoRepeat {
+    settings: AutoRepeatSettings,
+    left: bool,
+    right: bool,
+    // Held horizontal direction, the last pressed key wins
+    direction: Option<Action>,
+    // Time the direction key is held, up to DAS
+    das_charge: Duration,
+    shift: Repeat,
+    soft_drop: bool,
+    soft_drop_repeat: Repeat,
+    // Time left before repeating continues after new tetromino appeared
+    dcd_left: Duration,
+}
+
+impl AutoRepeat {
+    pub fn new(settings: AutoRepeatSettings) -> Self {
+        AutoRepeat {
+            settings,
+            left: false,
+            right: false,
+            direction: None,
+            das_charge: Duration::ZERO,
+            shift: Repeat::default(),
+            soft_drop: false,
+            soft_drop_repeat: Repeat::default(),
+            dcd_left: Duration::ZERO,
+        }
+    }
+
+    pub fn settings(&self) -> AutoRepeatSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: AutoRepeatSettings) {
+        self.settings = settings;
+    }
+
+    // Forget all held keys
+    pub fn clear(&mut self) {
+        *self = AutoRepeat::new(self.settings);
+    }
+
+    // Key is pressed. The pressed action itself is performed by the caller
+    pub fn press(&mut self, action: Action) {
+        match action {
+            Action::MoveLeft => {
+                self.left = true;
+                self.start_shift(action);
+            }
+            Action::MoveRight => {
+                self.right = true;
+                self.start_shift(action);
+            }
+            Action::MoveDown => {
+                self.soft_drop = true;
+                self.soft_drop_repeat = Repeat::default();
+                // First move down is the press itself
+                self.soft_drop_repeat.accumulated = Some(Duration::ZERO);
+            }
+            _ => {}
+        }
+    }
+
+    // Key is released. If the opposite direction is still held, it starts charging again
+    pub fn release(&mut self, action: Action) {
+        match action {
+            Action::MoveLeft => {
+                self.left = false;
+                if self.direction == Some(Action::MoveLeft) {
+                    self.direction = None;
+                    if self.right {
+                        self.start_shift(Action::MoveRight);
+                    }
+                }
+            }
+            Action::MoveRight => {
+                self.right = false;
+                if self.direction == Some(Action::MoveRight) {
+                    self.direction = None;
+                    if self.left {
+                        self.start_shift(Action::MoveLeft);
+                    }
+                }
+            }
+            Action::MoveDown => self.soft_drop = false,
+            _ => {}
+        }
+    }
+
+    fn start_shift(&mut self, direction: Action) {
+        self.direction = Some(direction);
+        self.das_charge = Duration::ZERO;
+        self.shift = Repeat::default();
+    }
+
+    // New tetromino appeared. Charged DAS is kept, but repeating waits for DCD
+    pub fn on_spawn(&mut self) {
+        self.dcd_left = TICK * self.settings.dcd;
+        if self.das_charge >= TICK * self.settings.das {
+            self.shift = Repeat::default();
+        }
+    }
+
+    // Advance held keys by the elapsed time and return repeated actions.
+    // When `active` is false there is no tetromino to move: DAS is charged, but nothing is repeated.
+    // `max_shift` and `max_drop` limit repeats with zero rate, usually the well size
+    pub fn advance(
+        &mut self,
+        elapsed: Duration,
+        active: bool,
+        max_shift: usize,
+        max_drop: usize,
+    ) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let mut elapsed_active = elapsed;
+        if active {
+            elapsed_active = elapsed.saturating_sub(self.dcd_left);
+            self.dcd_left = self.dcd_left.saturating_sub(elapsed);
+        }
+
+        if let Some(direction) = self.direction {
+            let das = TICK * self.settings.das;
+            let charge = self.das_charge + elapsed;
+            // Time passed since DAS got charged
+            let repeat_time = (charge.saturating_sub(das)).min(elapsed_active);
+            self.das_charge = charge.min(das);
+            if active && charge >= das && self.dcd_left.is_zero() {
+                let repeats = self
+                    .shift
+                    .advance(repeat_time, TICK * self.settings.arr, max_shift);
+                actions.extend(std::iter::repeat_n(direction, repeats));
+            }
+        }
+
+        if self.soft_drop && active {
+            let repeats = self.soft_drop_repeat.advance(
+                elapsed,
+                TICK * self.settings.soft_drop_arr,
+                max_drop,
+            );
+            actions.extend(std::iter::repeat_n(Action::MoveDown, repeats));
+        }
+        actions
+    }
+}
@@ -0,0 +1,16 @@
+use std::io;
+
+use gametetris_rs::{BotDifficulty, HeuristicBot, TbpServer};
+
+// Heuristic bot speaking the Tetris Bot Protocol on stdin and stdout.
+// `--level <preset|file.json>` sets lookahead and mistakes of the bot, pacing is up to the game
+fn main() -> io::Result<()> {
+    let mut bot = HeuristicBot::default();
+    let mut args = std::env::args().skip_while(|arg| arg != "--level").skip(1);
+    if let Some(name) = args.next() {
+        let difficulty = BotDifficulty::load(&name).map_err(io::Error::other)?;
+        bot = bot.with_difficulty(&difficulty);
+    }
+    let mut server = TbpServer::new(Box::new(bot), "gametetris heuristic", "gametetris-rs");
+    server.run(io::stdin().lock(), io::stdout().lock())
+}
@@ -0,0 +1,137 @@
+use std::{fs, time::Duration};
+
+use gametetris_rs::{
+    Bot, BotDifficulty, HeuristicBot, PlayerSettings, RuleSet, Tournament, TournamentReport,
+};
+
+const USAGE: &str = "Usage: tournament [options]
+  --bots <name,name,...>    registered bots, default: greedy,heuristic,deep
+  --tbp <name=command>      external bot speaking the Tetris Bot Protocol, can be repeated
+  --games <n>               games for each pair of bots, default: 10
+  --seed <n>                seed of the first game, default: 1
+  --rules <preset|file>     game rules, default: guideline
+  --max-time <seconds>      game time after which the game is a draw, default: 300
+  --threads <n>             games played at once, default: number of CPUs
+  --json <file>             write standings and games as JSON
+  --csv <file>              write standings as CSV
+  --games-csv <file>        write games as CSV";
+
+// Bots which can be chosen by name. Difficulty presets are registered too
+fn registered_bot(name: &str) -> Option<fn(u64) -> Box<dyn Bot>> {
+    let factory: fn(u64) -> Box<dyn Bot> = match name {
+        "greedy" => |seed| Box::new(HeuristicBot::default().with_lookahead(0).with_seed(seed)),
+        "heuristic" => |seed| Box::new(HeuristicBot::default().with_seed(seed)),
+        "deep" => |seed| Box::new(HeuristicBot::default().with_lookahead(2).with_seed(seed)),
+        "beginner" => |seed| difficulty_bot(BotDifficulty::beginner(), seed),
+        "easy" => |seed| difficulty_bot(BotDifficulty::easy(), seed),
+        "medium" => |seed| difficulty_bot(BotDifficulty::medium(), seed),
+        "hard" => |seed| difficulty_bot(BotDifficulty::hard(), seed),
+        "expert" => |seed| difficulty_bot(BotDifficulty::expert(), seed),
+        _ => return None,
+    };
+    Some(factory)
+}
+
+fn difficulty_bot(difficulty: BotDifficulty, seed: u64) -> Box<dyn Bot> {
+    Box::new(
+        HeuristicBot::default()
+            .with_difficulty(&difficulty)
+            .with_seed(seed),
+    )
+}
+
+// Values of all occurrences of the option
+fn options(name: &str) -> Vec<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .filter(|pair| pair[0] == name)
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+fn option(name: &str) -> Option<String> {
+    options(name).pop()
+}
+
+fn number<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match option(name) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be a number\n{}", name, USAGE)),
+        None => default,
+    }
+}
+
+fn rules() -> RuleSet {
+    let name = option("--rules").unwrap_or_else(|| "guideline".to_string());
+    RuleSet::load(&name).unwrap_or_else(|err| panic!("{}\n{}", err, USAGE))
+}
+
+fn print_standings(report: &TournamentReport) {
+    println!(
+        "{:>4}  {:<20} {:>7} {:>6} {:>5} {:>5} {:>5} {:>6} {:>6}",
+        "rank", "bot", "rating", "games", "wins", "loss", "draw", "pps", "lpp"
+    );
+    for (rank, standing) in report.standings.iter().enumerate() {
+        println!(
+            "{:>4}  {:<20} {:>7.1} {:>6} {:>5} {:>5} {:>5} {:>6.2} {:>6.3}",
+            rank + 1,
+            standing.name,
+            standing.rating,
+            standing.games,
+            standing.wins,
+            standing.losses,
+            standing.draws,
+            standing.pieces_per_second,
+            standing.lines_per_piece,
+        );
+    }
+    let errors: usize = report.standings.iter().map(|s| s.errors).sum();
+    if errors > 0 {
+        println!("{} games were not played because of errors", errors / 2);
+    }
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let settings = PlayerSettings::with_rules(10, 20, rules());
+    let mut tournament = Tournament::new(settings)
+        .with_games(number("--games", 10), number("--seed", 1))
+        .with_max_time(Duration::from_secs(number("--max-time", 300)));
+    if let Some(threads) = option("--threads") {
+        tournament = tournament.with_threads(threads.parse().expect("--threads must be a number"));
+    }
+    let bots = option("--bots").unwrap_or_else(|| "greedy,heuristic,deep".to_string());
+    for name in bots.split(',').filter(|name| !name.is_empty()) {
+        let factory = registered_bot(name).unwrap_or_else(|| panic!("unknown bot {}", name));
+        tournament = tournament
+            .with_bot(name, factory)
+            .unwrap_or_else(|err| panic!("{}\n{}", err, USAGE));
+    }
+    for tbp in options("--tbp") {
+        let (name, command_line) = tbp
+            .split_once('=')
+            .unwrap_or_else(|| panic!("--tbp must be name=command\n{}", USAGE));
+        let mut words = command_line.split_whitespace();
+        let program = words.next().expect("empty bot command");
+        let args: Vec<String> = words.map(String::from).collect();
+        tournament = tournament
+            .with_tbp(name, program, &args)
+            .unwrap_or_else(|err| panic!("{}\n{}", err, USAGE));
+    }
+
+    let report = tournament.run();
+    print_standings(&report);
+    if let Some(path) = option("--json") {
+        fs::write(path, report.to_json().expect("report is serializable")).unwrap();
+    }
+    if let Some(path) = option("--csv") {
+        fs::write(path, report.standings_csv()).unwrap();
+    }
+    if let Some(path) = option("--games-csv") {
+        fs::write(path, report.games_csv()).unwrap();
+    }
+}
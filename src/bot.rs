@@ -0,0 +1,533 @@
+use std::{collections::VecDeque, fs, time::Duration};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::FieldAnalysis,
+    clock::TICK,
+    path::PathPlanner,
+    placement::{cleared_rows, Placement},
+    rules::{DropStyle, RotationSystem},
+    tetris::{Action, Field, Tetris, TetrominoType},
+};
+
+/// Player controlled by the program. It chooses where the current tetromino goes,
+/// the way to get there is found with `PathPlanner`.
+pub trait Bot: Send {
+    // Move for the current tetromino of the game, None to let it fall on its own
+    fn choose(&mut self, game: &Tetris) -> Option<BotMove>;
+
+    // How fast the moves are played
+    fn pacing(&self) -> BotPacing {
+        BotPacing::INSTANT
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct BotMove {
+    // Swap the current tetromino with the held one first
+    pub hold: bool,
+    // Where the tetromino after the optional hold is locked
+    pub placement: Placement,
+}
+
+/// Weights of board features scored by `HeuristicBot`, the default ones are El-Tetris weights
+/// tuned for the single player game
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct HeuristicWeights {
+    // Height of the middle of the locked tetromino above the floor
+    pub landing_height: f64,
+    // Cleared lines multiplied by the tetromino cells removed with them
+    pub eroded_cells: f64,
+    pub row_transitions: f64,
+    pub column_transitions: f64,
+    pub holes: f64,
+    pub cumulative_wells: f64,
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        HeuristicWeights {
+            landing_height: -4.500158825082766,
+            eroded_cells: 3.4181268101392694,
+            row_transitions: -3.2178882868487753,
+            column_transitions: -9.348695305445199,
+            holes: -7.899265427351652,
+            cumulative_wells: -3.3855972247263626,
+        }
+    }
+}
+
+/// Timing of bot inputs. A human needs time to see the new tetromino, decide where it goes
+/// and press the keys, the bot waits the same way
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct BotPacing {
+    // Cap of locked tetrominos per second, None for no cap
+    pub pieces_per_second: Option<f64>,
+    // Keys pressed per second while moving the tetromino, None to queue all at once
+    pub inputs_per_second: Option<f64>,
+    // Time before the first input for each tetromino, in ticks (see `TICK`)
+    pub think_time: u32,
+    // Random time up to this is added to the think time, in ticks
+    pub think_jitter: u32,
+}
+
+impl BotPacing {
+    // Moves are played as soon as the tetromino appears
+    pub const INSTANT: BotPacing = BotPacing {
+        pieces_per_second: None,
+        inputs_per_second: None,
+        think_time: 0,
+        think_jitter: 0,
+    };
+}
+
+/// Difficulty profile of `HeuristicBot`
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct BotDifficulty {
+    // Preview tetrominos placed after the current one when choosing the move
+    pub lookahead: usize,
+    // Chance of choosing one of the next few placements instead of the best one
+    pub mistake_chance: f64,
+    pub pacing: BotPacing,
+}
+
+impl BotDifficulty {
+    // Names of bundled profiles, from the easiest
+    pub const PRESETS: [&'static str; 5] = ["beginner", "easy", "medium", "hard", "expert"];
+
+    pub fn preset(name: &str) -> Option<BotDifficulty> {
+        match name.to_lowercase().as_str() {
+            "beginner" => Some(BotDifficulty::beginner()),
+            "easy" => Some(BotDifficulty::easy()),
+            "medium" => Some(BotDifficulty::medium()),
+            "hard" => Some(BotDifficulty::hard()),
+            "expert" => Some(BotDifficulty::expert()),
+            _ => None,
+        }
+    }
+
+    pub fn beginner() -> Self {
+        BotDifficulty {
+            lookahead: 0,
+            mistake_chance: 0.3,
+            pacing: BotPacing {
+                pieces_per_second: Some(0.5),
+                inputs_per_second: Some(5.0),
+                think_time: 50,
+                think_jitter: 50,
+            },
+        }
+    }
+
+    pub fn easy() -> Self {
+        BotDifficulty {
+            lookahead: 0,
+            mistake_chance: 0.15,
+            pacing: BotPacing {
+                pieces_per_second: Some(1.0),
+                inputs_per_second: Some(8.0),
+                think_time: 30,
+                think_jitter: 30,
+            },
+        }
+    }
+
+    pub fn medium() -> Self {
+        BotDifficulty {
+            lookahead: 1,
+            mistake_chance: 0.05,
+            pacing: BotPacing {
+                pieces_per_second: Some(1.5),
+                inputs_per_second: Some(12.0),
+                think_time: 20,
+                think_jitter: 15,
+            },
+        }
+    }
+
+    pub fn hard() -> Self {
+        BotDifficulty {
+            lookahead: 1,
+            mistake_chance: 0.01,
+            pacing: BotPacing {
+                pieces_per_second: Some(2.5),
+                inputs_per_second: Some(20.0),
+                think_time: 10,
+                think_jitter: 5,
+            },
+        }
+    }
+
+    // Full strength with a pace of a fast human player
+    pub fn expert() -> Self {
+        BotDifficulty {
+            lookahead: 2,
+            mistake_chance: 0.0,
+            pacing: BotPacing {
+                pieces_per_second: Some(4.0),
+                inputs_per_second: Some(40.0),
+                think_time: 5,
+                think_jitter: 0,
+            },
+        }
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<BotDifficulty> {
+        serde_json::from_str(json)
+    }
+
+    // Preset by its name, otherwise difficulty read from the JSON file at the path
+    pub fn load(name_or_path: &str) -> Result<BotDifficulty, String> {
+        if let Some(difficulty) = BotDifficulty::preset(name_or_path) {
+            return Ok(difficulty);
+        }
+        let json = fs::read_to_string(name_or_path).map_err(|err| {
+            format!(
+                "{} is neither a level ({}) nor a readable file: {}",
+                name_or_path,
+                BotDifficulty::PRESETS.join(", "),
+                err
+            )
+        })?;
+        BotDifficulty::from_json(&json)
+            .map_err(|err| format!("invalid level file {}: {}", name_or_path, err))
+    }
+}
+
+// Mistakes are made with one of this many placements after the best one
+const MISTAKE_RANGE: usize = 3;
+
+/// Bot scoring reachable placements by weighted board features.
+/// With lookahead it also places the preview tetrominos, following only the best
+/// `beam` placements of each one
+#[derive(Debug, Clone)]
+pub struct HeuristicBot {
+    weights: HeuristicWeights,
+    // Preview tetrominos placed after the current one
+    lookahead: usize,
+    beam: usize,
+    mistake_chance: f64,
+    pacing: BotPacing,
+    rng: StdRng,
+}
+
+impl Default for HeuristicBot {
+    fn default() -> Self {
+        HeuristicBot::new(HeuristicWeights::default())
+    }
+}
+
+impl HeuristicBot {
+    pub fn new(weights: HeuristicWeights) -> Self {
+        HeuristicBot {
+            weights,
+            lookahead: 1,
+            beam: 6,
+            mistake_chance: 0.0,
+            pacing: BotPacing::INSTANT,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    // Number of preview tetrominos placed after the current one. 0 looks at the current one only
+    pub fn with_lookahead(mut self, lookahead: usize) -> Self {
+        self.lookahead = lookahead;
+        self
+    }
+
+    // Number of best placements followed further by lookahead
+    pub fn with_beam(mut self, beam: usize) -> Self {
+        self.beam = beam.max(1);
+        self
+    }
+
+    // Lookahead, mistakes and pacing of the profile
+    pub fn with_difficulty(mut self, difficulty: &BotDifficulty) -> Self {
+        self.lookahead = difficulty.lookahead;
+        self.mistake_chance = difficulty.mistake_chance.clamp(0.0, 1.0);
+        self.pacing = difficulty.pacing;
+        self
+    }
+
+    // Seed of random mistakes, bots with the same seed make the same choices
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn weights(&self) -> &HeuristicWeights {
+        &self.weights
+    }
+
+    // Score of the placement on the field, higher is better, and the field after it
+    pub fn evaluate(&self, field: &Field, placement: &Placement) -> (f64, Field) {
+        let after = placement.apply(field);
+        let analysis = FieldAnalysis::new(&after);
+        let cells = placement.tetromino.cells();
+        let middle = cells.iter().map(|&(_, y)| y as f64).sum::<f64>() / cells.len() as f64;
+        let landing_height = field.rows() as f64 - middle;
+        let cleared = cleared_rows(field, &placement.tetromino);
+        let eroded_cells =
+            cleared.len() * cells.iter().filter(|(_, y)| cleared.contains(y)).count();
+        let weights = &self.weights;
+        let score = weights.landing_height * landing_height
+            + weights.eroded_cells * eroded_cells as f64
+            + weights.row_transitions * analysis.row_transitions() as f64
+            + weights.column_transitions * analysis.column_transitions() as f64
+            + weights.holes * analysis.holes() as f64
+            + weights.cumulative_wells * analysis.cumulative_wells() as f64;
+        (score, after)
+    }
+
+    // Placements of the first tetromino of the queue with their scores including the lookahead,
+    // from the best. With lookahead only the best `beam` placements are kept
+    fn rank(
+        &self,
+        field: &Field,
+        placements: Vec<Placement>,
+        queue: &[TetrominoType],
+        system: RotationSystem,
+        depth: usize,
+    ) -> Vec<(Placement, f64)> {
+        let mut scored: Vec<(Placement, f64, Field)> = placements
+            .into_iter()
+            .map(|placement| {
+                let (score, after) = self.evaluate(field, &placement);
+                (placement, score, after)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let Some((&next, rest)) = queue.split_first().filter(|_| depth > 0) else {
+            return scored
+                .into_iter()
+                .map(|(placement, score, _)| (placement, score))
+                .collect();
+        };
+        scored.truncate(self.beam);
+        let mut ranked: Vec<(Placement, f64)> = scored
+            .into_iter()
+            .map(|(placement, score, after)| {
+                let placements = Placement::find_all(&after, next, system);
+                // Next tetromino can't spawn, the game would be over
+                let future = self
+                    .rank(&after, placements, rest, system, depth - 1)
+                    .first()
+                    .map_or(f64::NEG_INFINITY, |&(_, future)| future);
+                (placement, score + future)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+}
+
+impl Bot for HeuristicBot {
+    fn choose(&mut self, game: &Tetris) -> Option<BotMove> {
+        game.get_current().as_ref()?;
+        let field = game.get_field();
+        let system = game.rules().rotation;
+        let queue: Vec<TetrominoType> = game
+            .get_next_queue()
+            .iter()
+            .take(game.preview_count())
+            .copied()
+            .collect();
+        let mut moves: Vec<(BotMove, f64)> = self
+            .rank(field, game.placements(), &queue, system, self.lookahead)
+            .into_iter()
+            .map(|(placement, score)| {
+                let hold = false;
+                (BotMove { hold, placement }, score)
+            })
+            .collect();
+        if game.can_hold() {
+            // The first hold takes the next tetromino out of the queue
+            let held = match game.get_hold() {
+                Some(held) => Some((held, &queue[..])),
+                None => queue.split_first().map(|(&next, rest)| (next, rest)),
+            };
+            if let Some((held, queue)) = held {
+                let placements = Placement::find_all(field, held, system);
+                let ranked = self.rank(field, placements, queue, system, self.lookahead);
+                moves.extend(ranked.into_iter().map(|(placement, score)| {
+                    let hold = true;
+                    (BotMove { hold, placement }, score)
+                }));
+                moves.sort_by(|a, b| b.1.total_cmp(&a.1));
+            }
+        }
+        let choice = if moves.len() > 1 && self.rng.gen_bool(self.mistake_chance) {
+            self.rng.gen_range(1..moves.len().min(MISTAKE_RANGE + 1))
+        } else {
+            0
+        };
+        moves.get(choice).map(|&(bot_move, _)| bot_move)
+    }
+
+    fn pacing(&self) -> BotPacing {
+        self.pacing
+    }
+}
+
+// Feeds moves of the bot to its game as actions with the bot's pacing, see `TetrisPair::set_bot`
+pub(crate) struct BotController {
+    bot: Box<dyn Bot>,
+    pacing: BotPacing,
+    rng: StdRng,
+    // Game time seen by the bot, without pauses and countdowns
+    time: Duration,
+    // Actions not accepted by the game yet
+    pending: VecDeque<Action>,
+    // Locked tetrominos and whether hold was possible when the last move was chosen.
+    // A new move is chosen when it changes
+    chosen_at: Option<(usize, bool)>,
+    // Placement of the current tetromino, None while the bot thinks
+    target: Option<Placement>,
+    // Hold was pressed for the target
+    held: bool,
+    // Drop was pressed for the current tetromino
+    dropping: bool,
+    // Time of the next input
+    next_input: Duration,
+    // Earliest time the bot starts with the next tetromino, keeps the pieces per second cap
+    next_piece: Duration,
+}
+
+impl BotController {
+    // Think time jitter is random, but the same with the same seed
+    pub fn new(bot: Box<dyn Bot>, seed: u64) -> Self {
+        BotController {
+            pacing: bot.pacing(),
+            bot,
+            rng: StdRng::seed_from_u64(seed),
+            time: Duration::ZERO,
+            pending: VecDeque::new(),
+            chosen_at: None,
+            target: None,
+            held: false,
+            dropping: false,
+            next_input: Duration::ZERO,
+            next_piece: Duration::ZERO,
+        }
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn reset(&mut self) {
+        self.time = Duration::ZERO;
+        self.pending.clear();
+        self.chosen_at = None;
+        self.target = None;
+        self.held = false;
+        self.dropping = false;
+        self.next_input = Duration::ZERO;
+        self.next_piece = Duration::ZERO;
+    }
+
+    // Choose the move for a new tetromino and add its actions to the game when it's time
+    pub fn update(&mut self, game: &mut Tetris, elapsed: Duration) {
+        if game.is_game_over() || game.is_paused() || game.countdown().is_some() {
+            return;
+        }
+        self.time += elapsed;
+        if self.pending.is_empty() && game.get_current().is_some() {
+            let chosen_at = (game.score().pieces, game.can_hold());
+            if self.chosen_at != Some(chosen_at) {
+                self.chosen_at = Some(chosen_at);
+                self.start_tetromino(game);
+            }
+            if self.time >= self.next_input {
+                self.input(game);
+            }
+        }
+        while let Some(&action) = self.pending.front() {
+            if !game.add_action(action) {
+                break;
+            }
+            self.pending.pop_front();
+        }
+    }
+
+    // New tetromino appeared, or the held one was swapped in
+    fn start_tetromino(&mut self, game: &Tetris) {
+        // The target was chosen before the hold
+        let held = self.target.filter(|target| {
+            self.held
+                && game.get_current().is_some_and(|current| {
+                    current.tetromino_type() == target.tetromino.tetromino_type()
+                })
+        });
+        self.held = false;
+        self.dropping = false;
+        if held.is_some() {
+            self.target = held;
+            return;
+        }
+        self.target = None;
+        let pacing = &self.pacing;
+        let think_time = pacing.think_time + self.rng.gen_range(0..=pacing.think_jitter);
+        let start = (self.time + TICK * think_time).max(self.next_piece);
+        self.next_input = start;
+        if let Some(pieces_per_second) = pacing.pieces_per_second.filter(|pps| *pps > 0.0) {
+            self.next_piece = start + Duration::from_secs_f64(1.0 / pieces_per_second);
+        }
+    }
+
+    // Choose the move when the bot is done thinking, then press the next key.
+    // The path is planned again before each key, the tetromino falls in between
+    fn input(&mut self, game: &Tetris) {
+        let target = match self.target {
+            Some(target) => target,
+            None => {
+                let Some(bot_move) = self.bot.choose(game) else {
+                    // Let the tetromino fall on its own
+                    self.next_input = Duration::MAX;
+                    return;
+                };
+                self.target = Some(bot_move.placement);
+                // Path is planned after the hold, from where the held tetromino appears
+                if bot_move.hold {
+                    self.held = true;
+                    self.pending.push_back(Action::Hold);
+                    self.schedule_input();
+                    return;
+                }
+                bot_move.placement
+            }
+        };
+        let Some(current) = *game.get_current() else {
+            return;
+        };
+        let mut planner = PathPlanner::new(game.rules());
+        if let Some(inputs_per_second) = self.pacing.inputs_per_second {
+            planner = planner.with_input_rate(inputs_per_second, game.gravity());
+        }
+        let path = planner
+            .find_placement(game.get_field(), current, &target)
+            .unwrap_or_else(|| vec![Action::Drop]);
+        // Fast drop keeps falling after the key is pressed once
+        if !(self.dropping && path == [Action::Drop]) {
+            let keys = match self.pacing.inputs_per_second {
+                Some(_) => &path[..path.len().min(1)],
+                None => &path[..],
+            };
+            self.dropping =
+                keys.last() == Some(&Action::Drop) && game.rules().drop == DropStyle::Fast;
+            self.pending.extend(keys);
+        }
+        self.schedule_input();
+    }
+
+    fn schedule_input(&mut self) {
+        self.next_input = match self.pacing.inputs_per_second {
+            Some(inputs_per_second) if inputs_per_second > 0.0 => {
+                self.time + Duration::from_secs_f64(1.0 / inputs_per_second)
+            }
+            _ => self.time,
+        };
+    }
+}
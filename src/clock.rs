@@ -0,0 +1,102 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Default fixed time step of the game loop. Settings expressed in ticks
+/// (i.e. `Tetris::set_fall_speed(lines, steps)`) are converted to time using it
+pub const TICK: Duration = Duration::from_millis(10);
+
+// Frame rate used to convert G to rows per second
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+/// Source of time for the game loop.
+/// `now` returns time passed since some fixed moment, i.e. creation of the clock
+pub trait Clock: Send {
+    fn now(&self) -> Duration;
+    // Wait until `now` advances by the duration
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Real time clock based on `std::time::Instant`
+pub struct MonotonicClock {
+    start: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        MonotonicClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Clock which is advanced manually. Clones share the same time, so the test can keep
+/// one copy and advance time seen by the game holding another one
+#[derive(Clone, Default)]
+pub struct FakeClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    pub fn set(&self, now: Duration) {
+        *self.now.lock().unwrap() = now;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+    // Time is advanced by the owner of the clock, just let it run
+    fn sleep(&self, _duration: Duration) {
+        std::thread::yield_now();
+    }
+}
+
+/// Speed of falling tetromino
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Gravity {
+    RowsPerSecond(f64),
+    // Rows per frame at 60 frames per second, as usual in tetris games.
+    // I.e. 1/60G is one row per second and 20G is instant fall in 20 rows well
+    G(f64),
+}
+
+impl Gravity {
+    // Convert speed given in (lines, steps) format with steps of `TICK` length
+    pub fn from_ticks(lines: usize, steps: usize) -> Self {
+        Gravity::RowsPerSecond(lines as f64 / (steps as f64 * TICK.as_secs_f64()))
+    }
+
+    pub fn rows_per_second(&self) -> f64 {
+        match self {
+            Gravity::RowsPerSecond(rows) => *rows,
+            Gravity::G(g) => g * FRAMES_PER_SECOND,
+        }
+    }
+}
@@ -0,0 +1,715 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    clock::{Gravity, TICK},
+    frequency_regulator::RateRegulator,
+    piece_sequence::PieceSequence,
+    rotation::spawn_x,
+    rules::{Delays, DropStyle, GravityCurve, RuleSet},
+    scoring::{Score, ScoreCounter},
+    state::CoopState,
+    tetris::{
+        drop_queued_actions, hold_field, preview_field, Action, CellType, DropReason,
+        DroppedActions, Field, Lock, Obstacles, PieceState, Rotation, StepResult, Tetromino,
+        TetrominoType,
+    },
+    tetris_pair::PlayerSide,
+};
+
+// One of two players sharing the well
+struct CoopPlayer {
+    // Current tetromino controlled by this player
+    current: Option<Tetromino>,
+    // Drop and lock delay state of the current tetromino
+    piece: PieceState,
+    // Source of next tetrominos
+    sequence: PieceSequence,
+    // Next tetrominos, first one is placed next, and their preview
+    next: VecDeque<TetrominoType>,
+    preview: Field,
+    // Held tetromino and whether hold was used by the current tetromino
+    hold: Option<TetrominoType>,
+    hold_used: bool,
+    // User actions queue
+    actions: VecDeque<Action>,
+    // Rotations and hold carried over to the next tetromino with IRS and IHS
+    carried: Vec<Action>,
+    dropped: DroppedActions,
+    // Game speed
+    fall_speed: RateRegulator,
+    // Drop speed
+    drop_speed: RateRegulator,
+    // Time left before the next tetromino appears
+    spawn_delay: Option<Duration>,
+    // First column of the player's half of the well
+    offset: isize,
+    // Last lock, reported by `advance` as `StepResult::Locked`
+    locked: Option<Lock>,
+    // Next queue is given by `CoopTetris::set_fixed_queue`, no tetrominos are generated
+    fixed_queue: bool,
+}
+
+impl CoopPlayer {
+    fn new(offset: isize, rules: &RuleSet) -> Self {
+        let mut player = CoopPlayer {
+            current: None,
+            piece: PieceState::default(),
+            sequence: PieceSequence::with_randomizer(rules.randomizer, rand::random()),
+            next: VecDeque::new(),
+            preview: Field::new(4, 4),
+            hold: None,
+            hold_used: false,
+            actions: VecDeque::new(),
+            carried: Vec::new(),
+            dropped: DroppedActions::default(),
+            fall_speed: RateRegulator::new(
+                rules.gravity.gravity(rules.start_level).rows_per_second(),
+            ),
+            drop_speed: RateRegulator::new(rules.drop_gravity.rows_per_second()),
+            spawn_delay: None,
+            offset,
+            locked: None,
+            fixed_queue: false,
+        };
+        player.fill_next_queue(rules.preview_count);
+        player
+    }
+
+    // Add random tetromino types to the next queue until it's long enough for preview
+    fn fill_next_queue(&mut self, preview_count: usize) {
+        while !self.fixed_queue && self.next.len() < preview_count.max(1) {
+            self.next.push_back(self.sequence.next_tetromino_type());
+        }
+        self.preview = preview_field(&self.next, preview_count);
+    }
+
+    // Make the tetromino current, with fresh drop and lock state
+    fn start_tetromino(&mut self, tetromino: Tetromino) {
+        self.current = Some(tetromino);
+        self.piece = PieceState::new(&tetromino);
+    }
+}
+
+enum Spawn {
+    Placed,
+    // Spawn position is occupied by the other player's tetromino, try again later
+    Blocked,
+    // Spawn position is occupied by fixed cells
+    GameOver,
+}
+
+// Fixed cells of the shared well and the partner's tetromino
+struct CoopObstacles<'a> {
+    well: &'a Field,
+    partner: Option<Tetromino>,
+}
+
+impl Obstacles for CoopObstacles<'_> {
+    fn blocks(&self, tetromino: &Tetromino) -> bool {
+        if tetromino.intersects(self.well) {
+            return true;
+        }
+        let Some(partner) = self.partner else {
+            return false;
+        };
+        let partner_cells = partner.cells();
+        tetromino
+            .cells()
+            .iter()
+            .any(|cell| partner_cells.contains(cell))
+    }
+}
+
+/// Cooperative game: two players share one wide well.
+/// Each player controls own tetromino with own queue of next tetrominos and own hold.
+/// Active tetrominos can't pass through each other, otherwise tetrominos move, rotate
+/// and lock the same way as in `Tetris` with the same `RuleSet`.
+/// Player plays in the right half of the well, opponent (the partner) in the left one.
+pub struct CoopTetris {
+    // Game field size
+    cols: usize,
+    rows: usize,
+    // Game over flag
+    game_over: bool,
+    // Game field
+    well: Field,
+    player: CoopPlayer,
+    opponent: CoopPlayer,
+    // Game rules. Setters below change the corresponding rule
+    rules: RuleSet,
+    // Time left before blasted lines collapse. Both players wait for it
+    clear_delay: Option<Duration>,
+    // Score, lines and level of both players together
+    score: ScoreCounter,
+}
+
+impl CoopTetris {
+    // Create game with well of `cols_per_player` * 2 columns
+    pub fn new(cols_per_player: usize, rows: usize) -> Self {
+        Self::with_rules(cols_per_player, rows, RuleSet::default())
+    }
+
+    pub fn with_rules(cols_per_player: usize, rows: usize, rules: RuleSet) -> Self {
+        let cols = cols_per_player * 2;
+        CoopTetris {
+            cols,
+            rows,
+            game_over: false,
+            well: Field::new(cols, rows),
+            player: CoopPlayer::new(cols_per_player as isize, &rules),
+            opponent: CoopPlayer::new(0, &rules),
+            clear_delay: None,
+            score: ScoreCounter::new(rules.scoring, rules.level_up, rules.start_level),
+            rules,
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn lines(&self) -> usize {
+        self.score.score().lines
+    }
+
+    pub fn score(&self) -> Score {
+        self.score.score()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    // Set falling speed of the tetrominos. It doesn't change with level anymore
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.rules.gravity = GravityCurve::Fixed(gravity);
+        self.update_gravity();
+    }
+
+    pub fn set_drop_gravity(&mut self, gravity: Gravity) {
+        self.rules.drop_gravity = gravity;
+        self.player.drop_speed.set(gravity.rows_per_second());
+        self.opponent.drop_speed.set(gravity.rows_per_second());
+    }
+
+    pub fn set_delays(&mut self, delays: Delays) {
+        self.rules.delays = delays;
+    }
+
+    // Blasted lines collapse all at once after `Delays::clear_animation`.
+    // The rate is converted to the animation time of one line
+    #[deprecated(note = "use `set_delays` with `Delays::clear_animation`")]
+    pub fn set_line_remove_rate(&mut self, lines_per_second: f64) {
+        self.rules.delays.clear_animation = (1.0 / (lines_per_second * TICK.as_secs_f64())) as u32;
+    }
+
+    // Speeds in (lines, steps) format, see `Tetris::set_fall_speed`
+
+    pub fn set_fall_speed(&mut self, lines: usize, steps: usize) {
+        self.set_gravity(Gravity::from_ticks(lines, steps));
+    }
+
+    pub fn set_drop_speed(&mut self, lines: usize, steps: usize) {
+        self.set_drop_gravity(Gravity::from_ticks(lines, steps));
+    }
+
+    #[deprecated(note = "use `set_delays` with `Delays::clear_animation`")]
+    pub fn set_line_remove_speed(&mut self, lines: usize, steps: usize) {
+        self.rules.delays.clear_animation = (steps / lines.max(1)) as u32;
+    }
+
+    // Add user action to the player's queue. Returns false if the action is dropped,
+    // see `InputPolicy::max_queue`
+    pub fn add_player_action(&mut self, player: PlayerSide, action: Action) -> bool {
+        let max_queue = self.rules.input_policy.max_queue;
+        let player = self.get_player_mut(player);
+        if max_queue.is_some_and(|max_queue| player.actions.len() >= max_queue) {
+            player.dropped.add(DropReason::QueueFull);
+            return false;
+        }
+        player.actions.push_back(action);
+        true
+    }
+
+    // The player gets the given tetrominos in order instead of the generated ones, see
+    // `Tetris::set_fixed_queue`. With None tetrominos are generated again
+    pub fn set_fixed_queue(&mut self, player: PlayerSide, tetrominos: Option<Vec<TetrominoType>>) {
+        let preview_count = self.rules.preview_count;
+        let player = self.get_player_mut(player);
+        player.fixed_queue = tetrominos.is_some();
+        player.next = tetrominos.unwrap_or_default().into();
+        player.fill_next_queue(preview_count);
+    }
+
+    // Number of the player's actions dropped since the game start
+    pub fn dropped_actions(&self, player: PlayerSide) -> DroppedActions {
+        self.get_player(player).dropped
+    }
+
+    fn get_player(&self, player: PlayerSide) -> &CoopPlayer {
+        match player {
+            PlayerSide::Player => &self.player,
+            PlayerSide::Opponent => &self.opponent,
+        }
+    }
+
+    fn get_player_mut(&mut self, player: PlayerSide) -> &mut CoopPlayer {
+        match player {
+            PlayerSide::Player => &mut self.player,
+            PlayerSide::Opponent => &mut self.opponent,
+        }
+    }
+
+    pub fn get_current(&self, player: PlayerSide) -> &Option<Tetromino> {
+        &self.get_player(player).current
+    }
+
+    pub fn get_hold(&self, player: PlayerSide) -> Option<TetrominoType> {
+        self.get_player(player).hold
+    }
+
+    // Advance the game by one `TICK`
+    pub fn step(&mut self) -> (Vec<StepResult>, Vec<StepResult>) {
+        self.advance(TICK)
+    }
+
+    // Advance the game by the time elapsed since the previous call, the player moves first.
+    // Each player's step works as `Tetris::advance`: all queued actions are applied,
+    // then gravity moves the tetromino down. Line removal is reported to both players.
+    // Returns results of both players in the order they happened, empty if nothing happened
+    pub fn advance(&mut self, elapsed: Duration) -> (Vec<StepResult>, Vec<StepResult>) {
+        if self.game_over {
+            return (vec![StepResult::GameOver], vec![StepResult::GameOver]);
+        }
+
+        let mut results = (Vec::new(), Vec::new());
+        // Time left after clear animation goes on to the players
+        let mut elapsed = elapsed;
+
+        // Blasted lines are shown for clear animation time, then all of them collapse at once
+        if let Some(delay) = self.clear_delay {
+            if delay > elapsed {
+                self.clear_delay = Some(delay - elapsed);
+                return results;
+            }
+            elapsed -= delay;
+            self.remove_blasted_lines(&mut results);
+        }
+
+        for side in [PlayerSide::Player, PlayerSide::Opponent] {
+            if self.game_over {
+                break;
+            }
+            let player_results = self.step_player(side, elapsed);
+            match side {
+                PlayerSide::Player => results.0.extend(player_results),
+                PlayerSide::Opponent => results.1.extend(player_results),
+            }
+            // Without clear animation the lines collapse before the partner moves
+            if self.clear_delay.is_some_and(|delay| delay.is_zero()) {
+                self.remove_blasted_lines(&mut results);
+            }
+        }
+
+        if self.game_over {
+            for results in [&mut results.0, &mut results.1] {
+                if results.last() != Some(&StepResult::GameOver) {
+                    results.push(StepResult::GameOver);
+                }
+            }
+        }
+        results
+    }
+
+    fn step_player(&mut self, side: PlayerSide, elapsed: Duration) -> Vec<StepResult> {
+        let mut results = Vec::new();
+        // Time left after entry delay goes on to the new tetromino
+        let mut elapsed = elapsed;
+
+        if self.get_player(side).current.is_none() {
+            let player = self.get_player_mut(side);
+            if let Some(delay) = player.spawn_delay {
+                if delay > elapsed {
+                    player.spawn_delay = Some(delay - elapsed);
+                    return results;
+                }
+                elapsed -= delay;
+                player.spawn_delay = None;
+            }
+            if !matches!(self.spawn(side), Spawn::Placed) {
+                return results;
+            }
+        }
+
+        while let Some(action) = self.get_player_mut(side).actions.pop_front() {
+            let succeed = self.perform(side, action);
+            results.push(StepResult::ActionPerformed(action, succeed));
+            // Move down is special case. If it fails, fix current tetromino and blast full lines.
+            // With hard drop the tetromino is locked by drop instead, so soft drop can slide it
+            if !succeed
+                && action == Action::MoveDown
+                && self.get_player(side).current.is_some()
+                && self.rules.drop != DropStyle::Hard
+            {
+                self.lock(side);
+            }
+            // Hard drop locks the tetromino too
+            self.report_lock(side, &mut results);
+            if self.game_over {
+                return results;
+            }
+        }
+
+        // Lock delay counts down while the tetromino is on the ground
+        let (player, well) = self.player_and_obstacles(side);
+        if let Some(current) = player.current {
+            if player.piece.lock_delay_expired(&current, elapsed, &well) {
+                results.push(StepResult::ActionPerformed(Action::MoveDown, false));
+                self.lock(side);
+                self.report_lock(side, &mut results);
+                return results;
+            }
+        }
+
+        let player = self.get_player_mut(side);
+        if player.current.is_some() {
+            let falls = if player.piece.drop {
+                player.drop_speed.advance(elapsed)
+            } else {
+                player.fall_speed.advance(elapsed)
+            };
+            for _ in 0..falls {
+                if self.control(side, |piece, current, well| piece.move_down(current, well)) {
+                    results.push(StepResult::ActionPerformed(Action::MoveDown, true));
+                    continue;
+                }
+                // Without lock delay the tetromino locks as soon as it can't fall
+                if self.rules.lock_delay.delay == 0 {
+                    results.push(StepResult::ActionPerformed(Action::MoveDown, false));
+                    self.lock(side);
+                    self.report_lock(side, &mut results);
+                }
+                break;
+            }
+        }
+
+        let lock_delay = self.rules.lock_delay;
+        let (player, well) = self.player_and_obstacles(side);
+        if let Some(current) = player.current {
+            player.piece.start_lock_delay(&current, lock_delay, &well);
+        }
+        results
+    }
+
+    // Add the lock which happened, if any, to the step results
+    fn report_lock(&mut self, side: PlayerSide, results: &mut Vec<StepResult>) {
+        if let Some(lock) = self.get_player_mut(side).locked.take() {
+            results.push(StepResult::Locked(lock));
+        }
+    }
+
+    // Perform user action. Returns false if it's impossible
+    fn perform(&mut self, side: PlayerSide, action: Action) -> bool {
+        let system = self.rules.rotation;
+        let succeed = match action {
+            Action::MoveLeft => self.control(side, |piece, current, well| {
+                piece.shift(current, -1, 0, Rotation::R0, well)
+            }),
+            Action::MoveRight => self.control(side, |piece, current, well| {
+                piece.shift(current, 1, 0, Rotation::R0, well)
+            }),
+            Action::MoveDown => {
+                let moved =
+                    self.control(side, |piece, current, well| piece.move_down(current, well));
+                if moved {
+                    self.score.soft_drop(1);
+                }
+                moved
+            }
+            Action::RotateLeft => self.control(side, |piece, current, well| {
+                piece.rotate(current, system, Rotation::R270, well)
+            }),
+            Action::RotateRight => self.control(side, |piece, current, well| {
+                piece.rotate(current, system, Rotation::R90, well)
+            }),
+            Action::Drop => self.drop(side),
+            Action::Hold => self.hold(side),
+            // There is no opponent to send garbage in cooperative game
+            Action::BottomRefill => false,
+        };
+        if succeed
+            && matches!(
+                action,
+                Action::MoveLeft | Action::MoveRight | Action::RotateLeft | Action::RotateRight
+            )
+        {
+            let lock_delay = self.rules.lock_delay;
+            self.get_player_mut(side).piece.reset_lock_delay(lock_delay);
+        }
+        succeed
+    }
+
+    // Move the player's current tetromino with the shared `PieceState` code,
+    // the partner's tetromino is in the way as the fixed cells are
+    fn control(
+        &mut self,
+        side: PlayerSide,
+        change: impl FnOnce(&mut PieceState, &mut Tetromino, &CoopObstacles) -> bool,
+    ) -> bool {
+        let (player, well) = self.player_and_obstacles(side);
+        let Some(current) = &mut player.current else {
+            return false;
+        };
+        change(&mut player.piece, current, &well)
+    }
+
+    // Drop current tetromino the way the rules say, see `Tetris::drop`
+    fn drop(&mut self, side: PlayerSide) -> bool {
+        match self.rules.drop {
+            DropStyle::Fast => {
+                let player = self.get_player_mut(side);
+                player.piece.drop = player.current.is_some();
+                player.piece.drop
+            }
+            DropStyle::Hard | DropStyle::Sonic => {
+                if self.get_player(side).current.is_none() {
+                    return false;
+                }
+                let mut rows = 0;
+                while self.control(side, |piece, current, well| piece.move_down(current, well)) {
+                    rows += 1;
+                }
+                self.score.hard_drop(rows);
+                if self.rules.drop == DropStyle::Hard {
+                    self.lock(side);
+                }
+                true
+            }
+        }
+    }
+
+    // Swap current tetromino with the held one, see `Tetris::hold`
+    fn hold(&mut self, side: PlayerSide) -> bool {
+        let rules_hold = self.rules.hold;
+        let preview_count = self.rules.preview_count;
+        let tetromino = {
+            let player = self.get_player(side);
+            if !rules_hold || player.hold_used {
+                return false;
+            }
+            let Some(tetromino_type) = player.hold.or(player.next.front().copied()) else {
+                return false;
+            };
+            self.new_tetromino(side, tetromino_type)
+        };
+        if self.obstacles(side).blocks(&tetromino) {
+            return false;
+        }
+        let player = self.get_player_mut(side);
+        let Some(current) = player.current else {
+            return false;
+        };
+        if player.hold.is_none() {
+            player.next.pop_front();
+            player.fill_next_queue(preview_count);
+        }
+        player.hold = Some(current.tetromino_type());
+        player.start_tetromino(tetromino);
+        player.hold_used = true;
+        true
+    }
+
+    // The well and the partner's tetromino, the player's tetromino can't pass them
+    fn obstacles(&self, side: PlayerSide) -> CoopObstacles<'_> {
+        CoopObstacles {
+            well: &self.well,
+            partner: self.get_player(side.other()).current,
+        }
+    }
+
+    // The player to control and what its tetromino can't pass
+    fn player_and_obstacles(&mut self, side: PlayerSide) -> (&mut CoopPlayer, CoopObstacles<'_>) {
+        let (player, partner) = match side {
+            PlayerSide::Player => (&mut self.player, &self.opponent),
+            PlayerSide::Opponent => (&mut self.opponent, &self.player),
+        };
+        let obstacles = CoopObstacles {
+            well: &self.well,
+            partner: partner.current,
+        };
+        (player, obstacles)
+    }
+
+    // Tetromino of given type at the spawn position in the player's half of the well
+    fn new_tetromino(&self, side: PlayerSide, tetromino_type: TetrominoType) -> Tetromino {
+        let x = spawn_x(self.rules.rotation, tetromino_type, self.cols / 2);
+        Tetromino::new(
+            tetromino_type,
+            Rotation::R0,
+            self.get_player(side).offset + x,
+            0,
+        )
+    }
+
+    // Place next tetromino of the player. The game is over if fixed cells are in the way
+    fn spawn(&mut self, side: PlayerSide) -> Spawn {
+        let Some(&tetromino_type) = self.get_player(side).next.front() else {
+            self.game_over = true;
+            return Spawn::GameOver;
+        };
+        let tetromino = self.new_tetromino(side, tetromino_type);
+        if tetromino.intersects(&self.well) {
+            self.game_over = true;
+            return Spawn::GameOver;
+        }
+        if self.obstacles(side).blocks(&tetromino) {
+            return Spawn::Blocked;
+        }
+        let preview_count = self.rules.preview_count;
+        let player = self.get_player_mut(side);
+        player.start_tetromino(tetromino);
+        player.hold_used = false;
+        player.next.pop_front();
+        player.fill_next_queue(preview_count);
+        if self.score.spawn() {
+            self.update_gravity();
+        }
+
+        // Apply hold and rotations carried over from the previous tetromino
+        let carried = std::mem::take(&mut self.get_player_mut(side).carried);
+        if carried.contains(&Action::Hold) {
+            self.hold(side);
+        }
+        for action in carried {
+            if matches!(action, Action::RotateLeft | Action::RotateRight) {
+                self.perform(side, action);
+            }
+        }
+        Spawn::Placed
+    }
+
+    // Wait `delay` before the player's next tetromino appears, without delay it's placed at once
+    fn start_spawn_delay(&mut self, side: PlayerSide, delay: Duration) {
+        if delay.is_zero() {
+            self.spawn(side);
+        } else {
+            self.get_player_mut(side).spawn_delay = Some(delay);
+        }
+    }
+
+    // Fix the player's tetromino and blast full lines, see `Tetris::lock`.
+    // Blasted lines collapse after clear animation, the next tetromino appears after
+    // line clear entry delay then, otherwise after entry delay
+    fn lock(&mut self, side: PlayerSide) {
+        let policy = self.rules.input_policy;
+        let player = self.get_player_mut(side);
+        let Some(current) = player.current.take() else {
+            return;
+        };
+        let piece = player.piece;
+        {
+            let CoopPlayer {
+                actions,
+                carried,
+                dropped,
+                ..
+            } = player;
+            drop_queued_actions(actions, policy, carried, dropped);
+        }
+        let tspin = piece.tspin(&current, &self.well);
+        current.draw(&mut self.well);
+        let blasted = self.well.blast_full_lines();
+        let lines = self.well.blasted_lines();
+        let lock = Lock {
+            tetromino: current,
+            lines,
+            tspin,
+            perfect_clear: blasted && self.well.is_cleared(),
+        };
+        if self.score.lock(&lock) {
+            self.update_gravity();
+        }
+        self.get_player_mut(side).locked = Some(lock);
+        if blasted {
+            self.clear_delay = Some(self.rules.delays.clear_animation());
+            self.get_player_mut(side).spawn_delay = Some(self.rules.delays.line_clear_are());
+        } else {
+            self.start_spawn_delay(side, self.rules.delays.are());
+        }
+    }
+
+    // Collapse all blasted lines and report them to both players. Active tetrominos above
+    // a removed line go down together with the fixed cells, tetromino can't overlap
+    // blasted line as it's full. Tetrominos waiting only for the collapse appear then
+    fn remove_blasted_lines(&mut self, results: &mut (Vec<StepResult>, Vec<StepResult>)) {
+        self.clear_delay = None;
+        let mut lines = 0;
+        while let Some(line) = self.well.remove_top_blasted_line() {
+            for player in [&mut self.player, &mut self.opponent] {
+                if let Some(current) = &mut player.current {
+                    if current.y() < line as isize {
+                        *current = current.moved(0, 1, Rotation::R0);
+                    }
+                }
+            }
+            lines += 1;
+        }
+        results.0.push(StepResult::LinesRemoved(lines));
+        results.1.push(StepResult::LinesRemoved(lines));
+        for side in [PlayerSide::Player, PlayerSide::Opponent] {
+            let player = self.get_player_mut(side);
+            if player.current.is_none() && player.spawn_delay.is_some_and(|delay| delay.is_zero()) {
+                player.spawn_delay = None;
+                self.spawn(side);
+            }
+        }
+    }
+
+    // Falling speed of the current level
+    fn update_gravity(&mut self) {
+        let gravity = self.rules.gravity.gravity(self.score.score().level);
+        self.player.fall_speed.set(gravity.rows_per_second());
+        self.opponent.fall_speed.set(gravity.rows_per_second());
+    }
+
+    pub fn get_state(&self) -> CoopState {
+        let mut well = self.well.clone();
+        // Draw where the tetrominos land under them
+        if self.rules.ghost {
+            for side in [PlayerSide::Player, PlayerSide::Opponent] {
+                if let Some(current) = self.get_player(side).current {
+                    let obstacles = self.obstacles(side);
+                    let mut ghost = current;
+                    while !obstacles.blocks(&ghost.moved(0, 1, Rotation::R0)) {
+                        ghost = ghost.moved(0, 1, Rotation::R0);
+                    }
+                    ghost.draw_as(&mut well, CellType::Ghost);
+                }
+            }
+        }
+        for player in [&self.player, &self.opponent] {
+            if let Some(current) = &player.current {
+                current.draw(&mut well);
+            }
+        }
+        // Hold boxes are shown only if hold is allowed
+        let hold = |player: &CoopPlayer| self.rules.hold.then(|| hold_field(player.hold));
+        CoopState {
+            well,
+            player_preview: self.player.preview.clone(),
+            opponent_preview: self.opponent.preview.clone(),
+            player_hold: hold(&self.player),
+            opponent_hold: hold(&self.opponent),
+            score: self.score(),
+            game_over: self.game_over,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub struct FrequencyRegulator {
     events: usize,
     steps: usize,
@@ -37,7 +39,7 @@ impl FrequencyRegulator {
     /// Returns the number of events to generate this step
     pub fn step(&mut self) -> usize {
         let events_to_generate_this_step =
-            (self.events * (self.current_step + 1) + self.steps - 1) / self.steps - self.events_generated;
+            (self.events * (self.current_step + 1)).div_ceil(self.steps) - self.events_generated;
 
         self.events_generated += events_to_generate_this_step;
 
@@ -50,3 +52,37 @@ impl FrequencyRegulator {
         events_to_generate_this_step
     }
 }
+
+/// Time based counterpart of `FrequencyRegulator`: calculates how many events to generate
+/// for the elapsed time to reach the desired number of events per second.
+/// Fractional part of the events is accumulated between calls
+pub struct RateRegulator {
+    rate: f64,
+    accumulated: f64,
+}
+
+impl RateRegulator {
+    pub fn new(events_per_second: f64) -> Self {
+        RateRegulator {
+            rate: events_per_second,
+            accumulated: 0.0,
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn set(&mut self, events_per_second: f64) {
+        self.rate = events_per_second;
+        self.accumulated = 0.0;
+    }
+
+    /// Returns the number of events to generate for the elapsed time
+    pub fn advance(&mut self, elapsed: Duration) -> usize {
+        self.accumulated += self.rate * elapsed.as_secs_f64();
+        let events = self.accumulated.floor();
+        self.accumulated -= events;
+        events as usize
+    }
+}
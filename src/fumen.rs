@@ -0,0 +1,767 @@
+use crate::tetris::{CellType, Field, Rotation, StepResult, Tetris, Tetromino, TetrominoType};
+
+// Fumen (https://fumen.zui.jp) is the format the community uses to share boards.
+// Version 115 is supported: the well is 10 cells wide with 23 visible rows and one
+// hidden garbage row under them. Each page stores the difference of its field
+// from the field of the previous page, a tetromino and a comment.
+
+pub const FUMEN_COLS: usize = 10;
+pub const FUMEN_ROWS: usize = 23;
+
+const PREFIXES: [&str; 4] = ["v115@", "m115@", "d115@", "D115@"];
+const ENCODE_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// Visible rows and the garbage row
+const FIELD_BLOCKS: u32 = ((FUMEN_ROWS + 1) * FUMEN_COLS) as u32;
+// Comments are printable ASCII characters packed 4 into a value
+const COMMENT_BASE: u32 = 96;
+const MAX_COMMENT_LENGTH: usize = 4095;
+
+/// Page of fumen: the field shown on it, the tetromino placed on the field and the comment.
+/// If `lock` is set, the tetromino is locked and full lines are cleared before the next page,
+/// then the garbage row rises if `rise` is set and the field is mirrored if `mirror` is set.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FumenPage {
+    // 10x23 field without the tetromino
+    pub field: Field,
+    // Hidden row under the field
+    pub garbage: Vec<CellType>,
+    pub tetromino: Option<Tetromino>,
+    // Comment stays the same on the following pages until it's changed
+    pub comment: Option<String>,
+    pub lock: bool,
+    pub rise: bool,
+    pub mirror: bool,
+}
+
+impl FumenPage {
+    // Page with the given field placed at the bottom of the fumen field. The field must be
+    // 10 cells wide, rows above the 23 visible ones must be empty
+    pub fn new(field: &Field) -> Result<FumenPage, String> {
+        if field.cols() != FUMEN_COLS {
+            return Err(format!(
+                "fumen field is {} cells wide, not {}",
+                FUMEN_COLS,
+                field.cols()
+            ));
+        }
+        let mut page_field = Field::new(FUMEN_COLS, FUMEN_ROWS);
+        for y in 0..field.rows() {
+            let page_y = (y + FUMEN_ROWS).checked_sub(field.rows());
+            for x in 0..FUMEN_COLS {
+                let cell_type = field.get_cell(x, y);
+                match page_y {
+                    Some(page_y) => page_field.set_cell(x, page_y, cell_type),
+                    None if is_filled(cell_type) => {
+                        return Err(format!("fumen field is {} rows high", FUMEN_ROWS));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Ok(FumenPage {
+            field: page_field,
+            garbage: vec![CellType::Empty; FUMEN_COLS],
+            tetromino: None,
+            comment: None,
+            lock: true,
+            rise: false,
+            mirror: false,
+        })
+    }
+
+    // Field of the page cut or extended at the top to the given number of rows
+    pub fn field_with_rows(&self, rows: usize) -> Field {
+        let mut field = Field::new(FUMEN_COLS, rows);
+        for y in 0..FUMEN_ROWS {
+            if let Some(field_y) = (y + rows).checked_sub(FUMEN_ROWS) {
+                for x in 0..FUMEN_COLS {
+                    field.set_cell(x, field_y, self.field.get_cell(x, y));
+                }
+            }
+        }
+        field
+    }
+}
+
+fn is_filled(cell_type: CellType) -> bool {
+    !matches!(cell_type, CellType::Empty | CellType::Ghost)
+}
+
+/// Sequence of fumen pages, converted from and to the `v115@...` string
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fumen {
+    pub pages: Vec<FumenPage>,
+}
+
+impl Fumen {
+    // Fumen with the single page showing the field
+    pub fn from_field(field: &Field) -> Result<Fumen, String> {
+        Ok(Fumen {
+            pages: vec![FumenPage::new(field)?],
+        })
+    }
+
+    // Decode all pages. The data may be given with the version prefix
+    // or as the whole URL, i.e. `https://fumen.zui.jp/?v115@vhAAgH`
+    pub fn decode(fumen: &str) -> Result<Fumen, String> {
+        decode(fumen).map(|pages| Fumen { pages })
+    }
+
+    pub fn encode(&self) -> String {
+        encode(&self.pages)
+    }
+}
+
+fn decode(fumen: &str) -> Result<Vec<FumenPage>, String> {
+    let data = PREFIXES
+        .iter()
+        .find_map(|prefix| fumen.find(prefix).map(|i| &fumen[i + prefix.len()..]))
+        .ok_or("only fumen v115 is supported")?;
+    let mut reader = Reader::new(data.trim())?;
+    let mut pages = Vec::new();
+    let mut blocks = Blocks::default();
+    let mut repeat = 0;
+    let mut comment = None;
+    while !reader.is_empty() {
+        if repeat > 0 {
+            repeat -= 1;
+        } else if !blocks.apply_diff(&mut reader)? {
+            repeat = reader.poll(1)?;
+        }
+
+        let action = Action::decode(reader.poll(3)?);
+        if action.comment {
+            let length = reader.poll(2)? as usize;
+            let mut escaped = String::new();
+            for _ in 0..length.div_ceil(4) {
+                let mut value = reader.poll(5)?;
+                for _ in 0..4 {
+                    escaped.push(char::from(b' ' + (value % COMMENT_BASE) as u8));
+                    value /= COMMENT_BASE;
+                }
+            }
+            let text = unescape(&escaped.chars().take(length).collect::<String>());
+            comment = Some(text).filter(|text| !text.is_empty());
+        }
+
+        let tetromino = action.piece.map(|piece| piece.to_tetromino()).transpose()?;
+        pages.push(FumenPage {
+            field: blocks.field(),
+            garbage: blocks.garbage(),
+            tetromino,
+            comment: comment.clone(),
+            lock: action.lock,
+            rise: action.rise,
+            mirror: action.mirror,
+        });
+        blocks.finish_page(&action);
+    }
+    if pages.is_empty() {
+        return Err("fumen has no pages".to_string());
+    }
+    Ok(pages)
+}
+
+fn encode(pages: &[FumenPage]) -> String {
+    let mut writer = Writer::default();
+    let mut prev = Blocks::default();
+    // Position of the repeat counter of unchanged fields
+    let mut repeat: Option<usize> = None;
+    let mut comment = "";
+    for page in pages {
+        let current = Blocks::from_page(page);
+        let diff = current.diff(&prev);
+        match repeat {
+            Some(position) if diff.is_none() && writer.get(position) < 63 => {
+                writer.set(position, writer.get(position) + 1);
+            }
+            _ => {
+                let unchanged = diff.is_none();
+                writer.write_runs(&diff.unwrap_or_else(|| vec![(8, FIELD_BLOCKS)]));
+                repeat = unchanged.then(|| {
+                    writer.push(0, 1);
+                    writer.len() - 1
+                });
+            }
+        }
+
+        let page_comment = page.comment.as_deref().unwrap_or("");
+        let action = Action {
+            piece: page.tetromino.and_then(Piece::from_tetromino),
+            rise: page.rise,
+            mirror: page.mirror,
+            // Community tools set guideline colors on every page
+            colorize: true,
+            comment: page_comment != comment,
+            lock: page.lock,
+        };
+        writer.push(action.encode(), 3);
+        if action.comment {
+            comment = page_comment;
+            let escaped: Vec<u8> = escape(comment).bytes().take(MAX_COMMENT_LENGTH).collect();
+            writer.push(escaped.len() as u32, 2);
+            for chunk in escaped.chunks(4) {
+                let value = chunk
+                    .iter()
+                    .rev()
+                    .fold(0, |value, &c| value * COMMENT_BASE + u32::from(c - b' '));
+                writer.push(value, 5);
+            }
+        }
+
+        prev = current;
+        prev.finish_page(&action);
+    }
+    format!("v115@{}", writer.finish())
+}
+
+/// Records the game as fumen, one page for each locked tetromino and the last page with
+/// the final well. Pass the results of each step of the game to `record`
+#[derive(Debug, Clone)]
+pub struct FumenRecorder {
+    pages: Vec<FumenPage>,
+    // Well before the next lock
+    well: Field,
+}
+
+impl FumenRecorder {
+    pub fn new(tetris: &Tetris) -> Self {
+        FumenRecorder {
+            pages: Vec::new(),
+            well: tetris.get_field().clone(),
+        }
+    }
+
+    pub fn record(&mut self, tetris: &Tetris, results: &[StepResult]) -> Result<(), String> {
+        for result in results {
+            if let StepResult::Locked(lock) = result {
+                let rows = self.well.rows() as isize;
+                let mut page = FumenPage::new(&self.well)?;
+                page.tetromino = Some(lock.tetromino.moved(
+                    0,
+                    FUMEN_ROWS as isize - rows,
+                    Rotation::R0,
+                ));
+                self.pages.push(page);
+            }
+        }
+        self.well = tetris.get_field().clone();
+        Ok(())
+    }
+
+    pub fn pages(&self) -> &[FumenPage] {
+        &self.pages
+    }
+
+    pub fn to_fumen(&self) -> Result<Fumen, String> {
+        let mut pages = self.pages.clone();
+        // The game may stop while cleared lines are still shown
+        let mut well = self.well.clone();
+        well.remove_blasted_lines();
+        pages.push(FumenPage::new(&well)?);
+        Ok(Fumen { pages })
+    }
+}
+
+// Values of fumen data, 6 bits each
+struct Reader {
+    values: Vec<u32>,
+    position: usize,
+}
+
+impl Reader {
+    // Line breaks of long data are marked with `?`
+    fn new(data: &str) -> Result<Self, String> {
+        let values = data
+            .bytes()
+            .filter(|&c| c != b'?')
+            .map(|c| {
+                ENCODE_TABLE
+                    .iter()
+                    .position(|&e| e == c)
+                    .map(|value| value as u32)
+                    .ok_or_else(|| format!("invalid fumen character `{}`", char::from(c)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Reader {
+            values,
+            position: 0,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.values.len()
+    }
+
+    // Number stored in `count` values, least significant first
+    fn poll(&mut self, count: usize) -> Result<u32, String> {
+        let values = self
+            .values
+            .get(self.position..self.position + count)
+            .ok_or("fumen data is truncated")?;
+        self.position += count;
+        Ok(values
+            .iter()
+            .rev()
+            .fold(0, |number, value| number * 64 + value))
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    values: Vec<u32>,
+}
+
+impl Writer {
+    fn push(&mut self, mut number: u32, count: usize) {
+        for _ in 0..count {
+            self.values.push(number % 64);
+            number /= 64;
+        }
+    }
+
+    // Field difference as runs of (difference + 8, number of blocks)
+    fn write_runs(&mut self, runs: &[(u32, u32)]) {
+        for &(diff, count) in runs {
+            self.push(diff * FIELD_BLOCKS + count - 1, 2);
+        }
+    }
+
+    fn get(&self, position: usize) -> u32 {
+        self.values[position]
+    }
+
+    fn set(&mut self, position: usize, value: u32) {
+        self.values[position] = value;
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    // Data with `?` after each 47 characters, like fumen does for long data
+    fn finish(&self) -> String {
+        let mut data = String::new();
+        for (i, &value) in self.values.iter().enumerate() {
+            if i > 0 && i % 47 == 0 {
+                data.push('?');
+            }
+            data.push(char::from(ENCODE_TABLE[value as usize]));
+        }
+        data
+    }
+}
+
+// Fumen field as block numbers, rows from the top and the garbage row last
+#[derive(Clone, PartialEq)]
+struct Blocks {
+    rows: [[u8; FUMEN_COLS]; FUMEN_ROWS + 1],
+}
+
+impl Default for Blocks {
+    fn default() -> Self {
+        Blocks {
+            rows: [[0; FUMEN_COLS]; FUMEN_ROWS + 1],
+        }
+    }
+}
+
+impl Blocks {
+    fn from_page(page: &FumenPage) -> Self {
+        let mut blocks = Blocks::default();
+        for x in 0..FUMEN_COLS {
+            for y in 0..FUMEN_ROWS {
+                blocks.rows[y][x] = cell_to_block(page.field.get_cell(x, y));
+            }
+            let garbage = page.garbage.get(x).copied().unwrap_or(CellType::Empty);
+            blocks.rows[FUMEN_ROWS][x] = cell_to_block(garbage);
+        }
+        blocks
+    }
+
+    fn field(&self) -> Field {
+        let mut field = Field::new(FUMEN_COLS, FUMEN_ROWS);
+        for (y, row) in self.rows[..FUMEN_ROWS].iter().enumerate() {
+            for (x, &block) in row.iter().enumerate() {
+                if block != 0 {
+                    field.set_cell(x, y, block_to_cell(block));
+                }
+            }
+        }
+        field
+    }
+
+    fn garbage(&self) -> Vec<CellType> {
+        self.rows[FUMEN_ROWS]
+            .iter()
+            .map(|&b| block_to_cell(b))
+            .collect()
+    }
+
+    // Read the difference from the previous page. Returns false if the field is unchanged
+    fn apply_diff(&mut self, reader: &mut Reader) -> Result<bool, String> {
+        let mut index = 0;
+        let mut changed = true;
+        while index < FIELD_BLOCKS {
+            let value = reader.poll(2)?;
+            let diff = value / FIELD_BLOCKS;
+            let count = value % FIELD_BLOCKS + 1;
+            if diff == 8 && count == FIELD_BLOCKS {
+                changed = false;
+            }
+            for _ in 0..count {
+                let Some(block) = self.block_mut(index) else {
+                    return Err("fumen field is too long".to_string());
+                };
+                let value = *block as i32 + diff as i32 - 8;
+                if !(0..=8).contains(&value) {
+                    return Err(format!("invalid fumen block {}", value));
+                }
+                *block = value as u8;
+                index += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    // Runs of the difference from the previous field, None if the field is unchanged
+    fn diff(&self, prev: &Blocks) -> Option<Vec<(u32, u32)>> {
+        if self == prev {
+            return None;
+        }
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for (row, prev_row) in self.rows.iter().zip(prev.rows.iter()) {
+            for (&block, &prev_block) in row.iter().zip(prev_row.iter()) {
+                let diff = (block as i32 - prev_block as i32 + 8) as u32;
+                match runs.last_mut() {
+                    Some((last, count)) if *last == diff => *count += 1,
+                    _ => runs.push((diff, 1)),
+                }
+            }
+        }
+        Some(runs)
+    }
+
+    fn block_mut(&mut self, index: u32) -> Option<&mut u8> {
+        let index = index as usize;
+        self.rows
+            .get_mut(index / FUMEN_COLS)
+            .map(|row| &mut row[index % FUMEN_COLS])
+    }
+
+    // Field of the next page: lock the tetromino, clear full lines, rise and mirror
+    fn finish_page(&mut self, action: &Action) {
+        if !action.lock {
+            return;
+        }
+        if let Some(piece) = &action.piece {
+            for (x, y) in piece.cells() {
+                if let Some(row) = self.rows[..FUMEN_ROWS].get_mut(y as usize) {
+                    if let Some(block) = row.get_mut(x as usize) {
+                        *block = piece.block;
+                    }
+                }
+            }
+        }
+        let mut rows: Vec<[u8; FUMEN_COLS]> = self.rows[..FUMEN_ROWS]
+            .iter()
+            .filter(|row| row.contains(&0))
+            .copied()
+            .collect();
+        while rows.len() < FUMEN_ROWS {
+            rows.insert(0, [0; FUMEN_COLS]);
+        }
+        if action.rise {
+            rows.remove(0);
+            rows.push(self.rows[FUMEN_ROWS]);
+            self.rows[FUMEN_ROWS] = [0; FUMEN_COLS];
+        }
+        if action.mirror {
+            for row in &mut rows {
+                row.reverse();
+            }
+        }
+        self.rows[..FUMEN_ROWS].copy_from_slice(&rows);
+    }
+}
+
+fn cell_to_block(cell_type: CellType) -> u8 {
+    match cell_type {
+        CellType::Empty | CellType::Ghost => 0,
+        CellType::I => 1,
+        CellType::L => 2,
+        CellType::O => 3,
+        CellType::Z => 4,
+        CellType::T => 5,
+        CellType::J => 6,
+        CellType::S => 7,
+        CellType::Garbage | CellType::Blasted => 8,
+    }
+}
+
+fn block_to_cell(block: u8) -> CellType {
+    match block {
+        1 => CellType::I,
+        2 => CellType::L,
+        3 => CellType::O,
+        4 => CellType::Z,
+        5 => CellType::T,
+        6 => CellType::J,
+        7 => CellType::S,
+        8 => CellType::Garbage,
+        _ => CellType::Empty,
+    }
+}
+
+// Tetromino as fumen stores it: block number, rotation number and the center of rotation
+// in the row counted from the top
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    block: u8,
+    rotation: u32,
+    x: isize,
+    y: isize,
+}
+
+impl Piece {
+    fn tetromino_type(&self) -> Option<TetrominoType> {
+        match self.block {
+            1 => Some(TetrominoType::I),
+            2 => Some(TetrominoType::L),
+            3 => Some(TetrominoType::O),
+            4 => Some(TetrominoType::Z),
+            5 => Some(TetrominoType::T),
+            6 => Some(TetrominoType::J),
+            7 => Some(TetrominoType::S),
+            _ => None,
+        }
+    }
+
+    // Cells relative to the center, in fumen coordinates with y going up
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let spawn: [(isize, isize); 4] = match self.block {
+            1 => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+            2 => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+            3 => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            4 => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+            5 => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+            6 => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+            _ => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+        };
+        spawn
+            .iter()
+            .map(|&(x, y)| match self.rotation {
+                // Reverse
+                0 => (-x, -y),
+                // Right
+                1 => (y, -x),
+                // Spawn
+                2 => (x, y),
+                // Left
+                _ => (-y, x),
+            })
+            .collect()
+    }
+
+    // Cells in the rows counted from the top
+    fn cells(&self) -> Vec<(isize, isize)> {
+        self.offsets()
+            .into_iter()
+            .map(|(x, y)| (self.x + x, self.y - y))
+            .collect()
+    }
+
+    fn to_tetromino(self) -> Result<Tetromino, String> {
+        let tetromino_type = self.tetromino_type().ok_or("invalid fumen piece")?;
+        let cells = self.cells();
+        let outside = cells.iter().any(|&(x, y)| {
+            !(0..FUMEN_COLS as isize).contains(&x) || !(0..FUMEN_ROWS as isize).contains(&y)
+        });
+        if outside {
+            return Err("fumen piece is outside of the field".to_string());
+        }
+        // Rotation of the same shape may differ, keep the fumen one if it fits
+        let x = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let y = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let tetromino = Tetromino::new(tetromino_type, rotation_from_number(self.rotation), x, y);
+        let mut sorted = cells.clone();
+        sorted.sort();
+        let mut tetromino_cells = tetromino.cells();
+        tetromino_cells.sort();
+        if tetromino_cells == sorted {
+            return Ok(tetromino);
+        }
+        Tetromino::from_cells(tetromino_type, &cells).ok_or("invalid fumen piece".to_string())
+    }
+
+    fn from_tetromino(tetromino: Tetromino) -> Option<Piece> {
+        let block = cell_to_block(tetromino.tetromino_type().get_cell_type());
+        let mut cells = tetromino.cells();
+        cells.sort();
+        let preferred = rotation_to_number(tetromino.rotation());
+        // Center is one of the cells, find the one giving the same cells
+        [preferred, 0, 1, 2, 3].into_iter().find_map(|rotation| {
+            cells.iter().find_map(|&(x, y)| {
+                let piece = Piece {
+                    block,
+                    rotation,
+                    x,
+                    y,
+                };
+                let mut piece_cells = piece.cells();
+                piece_cells.sort();
+                (piece_cells == cells).then_some(piece)
+            })
+        })
+    }
+
+    // Fumen stores some pieces by another cell than the center
+    fn stored_offset(&self) -> (isize, isize) {
+        match (self.block, self.rotation) {
+            // O left, reverse and spawn
+            (3, 3) => (-1, -1),
+            (3, 0) => (-1, 0),
+            (3, 2) => (0, -1),
+            // I reverse and left
+            (1, 0) => (-1, 0),
+            (1, 3) => (0, -1),
+            // S spawn and right
+            (7, 2) => (0, -1),
+            (7, 1) => (1, 0),
+            // Z spawn and left
+            (4, 2) => (0, -1),
+            (4, 3) => (-1, 0),
+            _ => (0, 0),
+        }
+    }
+}
+
+fn rotation_from_number(number: u32) -> Rotation {
+    match number {
+        0 => Rotation::R180,
+        1 => Rotation::R90,
+        2 => Rotation::R0,
+        _ => Rotation::R270,
+    }
+}
+
+fn rotation_to_number(rotation: Rotation) -> u32 {
+    match rotation {
+        Rotation::R180 => 0,
+        Rotation::R90 => 1,
+        Rotation::R0 => 2,
+        Rotation::R270 => 3,
+    }
+}
+
+// Page flags and the piece, stored in 3 values
+struct Action {
+    piece: Option<Piece>,
+    rise: bool,
+    mirror: bool,
+    colorize: bool,
+    comment: bool,
+    lock: bool,
+}
+
+impl Action {
+    fn decode(mut value: u32) -> Action {
+        let block = (value % 8) as u8;
+        value /= 8;
+        let rotation = value % 4;
+        value /= 4;
+        let position = (value % FIELD_BLOCKS) as isize;
+        value /= FIELD_BLOCKS;
+        let mut flag = || {
+            let set = value % 2 == 1;
+            value /= 2;
+            set
+        };
+        let rise = flag();
+        let mirror = flag();
+        let colorize = flag();
+        let comment = flag();
+        let lock = !flag();
+        let piece = (block != 0).then(|| {
+            let mut piece = Piece {
+                block,
+                rotation,
+                x: position % FUMEN_COLS as isize,
+                y: position / FUMEN_COLS as isize,
+            };
+            let (dx, dy) = piece.stored_offset();
+            piece.x -= dx;
+            piece.y -= dy;
+            piece
+        });
+        Action {
+            piece,
+            rise,
+            mirror,
+            colorize,
+            comment,
+            lock,
+        }
+    }
+
+    fn encode(&self) -> u32 {
+        let flags = [
+            !self.lock,
+            self.comment,
+            self.colorize,
+            self.mirror,
+            self.rise,
+        ];
+        let value = flags
+            .iter()
+            .fold(0, |value, &flag| value * 2 + u32::from(flag));
+        let (block, rotation, position) = match &self.piece {
+            Some(piece) => {
+                let (dx, dy) = piece.stored_offset();
+                let position = (piece.y + dy) * FUMEN_COLS as isize + piece.x + dx;
+                (piece.block, piece.rotation, position as u32)
+            }
+            // Empty piece is stored at the top left corner
+            None => (0, 0, 0),
+        };
+        ((value * FIELD_BLOCKS + position) * 4 + rotation) * 8 + u32::from(block)
+    }
+}
+
+// Comments are escaped like JavaScript `escape` does
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for unit in text.encode_utf16() {
+        match char::from_u32(u32::from(unit)) {
+            Some(c) if c.is_ascii_alphanumeric() || "@*_+-./".contains(c) => escaped.push(c),
+            _ if unit < 256 => escaped.push_str(&format!("%{:02X}", unit)),
+            _ => escaped.push_str(&format!("%u{:04X}", unit)),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut units = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let code = match rest.strip_prefix("%u") {
+            Some(hex) => hex.get(..4).map(|hex| (hex, 6)),
+            None if c == '%' => rest.get(1..3).map(|hex| (hex, 3)),
+            None => None,
+        }
+        .and_then(|(hex, length)| Some((u16::from_str_radix(hex, 16).ok()?, length)));
+        match code {
+            Some((unit, length)) => {
+                units.push(unit);
+                rest = &rest[length..];
+            }
+            None => {
+                let mut buffer = [0; 2];
+                units.extend_from_slice(c.encode_utf16(&mut buffer));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
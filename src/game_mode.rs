@@ -0,0 +1,400 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auto_repeat::KeyEvent,
+    clock::TICK,
+    match_series::MatchCommand,
+    puzzle::PuzzleGoal,
+    scoring::Score,
+    state::SinglePlayerState,
+    tetris::{Action, StepResult, Tetris},
+};
+
+// Goal of the single player game
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum GameMode {
+    // Play until game over
+    Endless,
+    // Clear the number of lines as fast as possible
+    Sprint {
+        lines: usize,
+    },
+    // Score as much as possible before the time runs out
+    Ultra {
+        time_limit: Duration,
+    },
+    // Clear the lines while the level grows up to `max_level`. Endless game continues
+    // after the lines are cleared, without the level cap, until game over
+    Marathon {
+        lines: usize,
+        max_level: usize,
+        endless: bool,
+    },
+    // Dig through the rows of garbage the well starts with. In survival variant
+    // a new garbage row rises from the bottom every `rise_every`
+    Dig {
+        rows: usize,
+        rise_every: Option<Duration>,
+    },
+    // Reach the goal before the fixed queue of tetrominos is used up, see `Puzzle`
+    Puzzle {
+        goal: PuzzleGoal,
+    },
+}
+
+impl GameMode {
+    // Sprint to 40 lines
+    pub fn sprint() -> Self {
+        GameMode::Sprint { lines: 40 }
+    }
+
+    // Two minutes of Ultra
+    pub fn ultra() -> Self {
+        GameMode::Ultra {
+            time_limit: Duration::from_secs(120),
+        }
+    }
+
+    // 150 lines across 15 levels
+    pub fn marathon(endless: bool) -> Self {
+        GameMode::Marathon {
+            lines: 150,
+            max_level: 15,
+            endless,
+        }
+    }
+
+    // Race through 10 rows of garbage
+    pub fn dig() -> Self {
+        GameMode::Dig {
+            rows: 10,
+            rise_every: None,
+        }
+    }
+
+    // Dig while garbage rises every `rise_every`
+    pub fn dig_survival(rise_every: Duration) -> Self {
+        GameMode::Dig {
+            rows: 10,
+            rise_every: Some(rise_every),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Endless => "Endless",
+            GameMode::Sprint { .. } => "Sprint",
+            GameMode::Ultra { .. } => "Ultra",
+            GameMode::Marathon { endless: false, .. } => "Marathon",
+            GameMode::Marathon { endless: true, .. } => "Endless marathon",
+            GameMode::Dig {
+                rise_every: None, ..
+            } => "Dig",
+            GameMode::Dig {
+                rise_every: Some(_),
+                ..
+            } => "Dig survival",
+            GameMode::Puzzle { .. } => "Puzzle",
+        }
+    }
+}
+
+// Summary of the finished single player game
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ModeResult {
+    Endless {
+        score: Score,
+        time: Duration,
+    },
+    // Time it took to clear the lines. Not completed if the game was over before
+    Sprint {
+        completed: bool,
+        time: Duration,
+        lines: usize,
+        pieces: usize,
+    },
+    // Score at the time limit. Not completed if the game was over before
+    Ultra {
+        completed: bool,
+        score: Score,
+        time: Duration,
+    },
+    // Game is cleared when all lines of the marathon are cleared.
+    // Endless marathon is also cleared if the game was over after that
+    Marathon {
+        cleared: bool,
+        score: Score,
+        time: Duration,
+    },
+    // Time it took to clear all garbage. Not completed if the game was over before
+    Dig {
+        completed: bool,
+        time: Duration,
+        pieces: usize,
+        garbage_left: usize,
+    },
+    // Not solved if the tetrominos were used up or the game was over before the goal
+    Puzzle {
+        solved: bool,
+        pieces: usize,
+        time: Duration,
+    },
+}
+
+impl ModeResult {
+    // Goal of the mode was reached
+    pub fn is_completed(&self) -> bool {
+        match self {
+            ModeResult::Endless { .. } => false,
+            ModeResult::Sprint { completed, .. }
+            | ModeResult::Ultra { completed, .. }
+            | ModeResult::Dig { completed, .. } => *completed,
+            ModeResult::Puzzle { solved, .. } => *solved,
+            ModeResult::Marathon { cleared, .. } => *cleared,
+        }
+    }
+}
+
+// Input of the single player game
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum ModeInput {
+    Action(Action),
+    // Key press or release for games with built-in key repeat
+    Key(KeyEvent),
+    // `MatchCommand::Rematch` restarts the game
+    Command(MatchCommand),
+}
+
+/// Single player `Tetris` with the goal of the `GameMode`.
+/// Game time runs only while the tetrominos fall, without countdown and pauses.
+/// When the goal is reached or the game is over, the game stops and the result is kept
+/// until the game is restarted.
+pub struct SinglePlayer {
+    tetris: Tetris,
+    mode: GameMode,
+    // Game time, without countdown and pauses
+    time: Duration,
+    result: Option<ModeResult>,
+    // Time since the last garbage row rose in dig survival
+    garbage_timer: Duration,
+    // Goal of the puzzle is reached
+    goal_reached: bool,
+    // Countdown before start, in (counts, time per count) format
+    countdown: (usize, Duration),
+}
+
+impl SinglePlayer {
+    pub fn new(tetris: Tetris, mode: GameMode) -> Self {
+        let mut single = SinglePlayer {
+            tetris,
+            mode,
+            time: Duration::ZERO,
+            result: None,
+            garbage_timer: Duration::ZERO,
+            goal_reached: false,
+            countdown: (3, Duration::from_secs(1)),
+        };
+        single.restart();
+        single
+    }
+
+    pub fn tetris(&self) -> &Tetris {
+        &self.tetris
+    }
+
+    pub fn tetris_mut(&mut self) -> &mut Tetris {
+        &mut self.tetris
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    // Result of the game, None while it's in progress
+    pub fn result(&self) -> Option<ModeResult> {
+        self.result
+    }
+
+    // Set countdown used when the game starts. Applies from the next restart
+    pub fn set_countdown(&mut self, counts: usize, time_per_count: Duration) {
+        self.countdown = (counts, time_per_count);
+    }
+
+    // Start a new game of the same mode
+    pub fn restart(&mut self) {
+        // Level cap of the marathon
+        let max_level = match self.mode {
+            GameMode::Marathon { max_level, .. } => Some(max_level),
+            _ => None,
+        };
+        self.tetris.set_max_level(max_level);
+        self.tetris.reset();
+        let (counts, time_per_count) = self.countdown;
+        self.tetris.start_countdown(counts, time_per_count);
+        if let GameMode::Dig { rows, .. } = self.mode {
+            for _ in 0..rows {
+                self.tetris.bottom_refill();
+            }
+        }
+        self.time = Duration::ZERO;
+        self.result = None;
+        self.garbage_timer = Duration::ZERO;
+        self.goal_reached = false;
+    }
+
+    // Returns false if the action is dropped. Actions are dropped after the game is finished
+    pub fn add_action(&mut self, action: Action) -> bool {
+        self.result.is_none() && self.tetris.add_action(action)
+    }
+
+    pub fn key_event(&mut self, event: KeyEvent) {
+        match event {
+            KeyEvent::Press(_) if self.result.is_some() => {}
+            _ => self.tetris.key_event(event),
+        }
+    }
+
+    pub fn command(&mut self, command: MatchCommand) {
+        match command {
+            MatchCommand::Rematch => {
+                if self.result.is_some() {
+                    self.restart();
+                }
+            }
+            MatchCommand::Pause => {
+                if self.result.is_none() {
+                    self.tetris.pause();
+                }
+            }
+            MatchCommand::Resume => {
+                if self.tetris.is_paused() {
+                    self.tetris.resume();
+                    let (counts, time_per_count) = self.countdown;
+                    self.tetris.start_countdown(counts, time_per_count);
+                }
+            }
+        }
+    }
+
+    // Advance the game by one `TICK`
+    pub fn step(&mut self) -> Vec<StepResult> {
+        self.advance(TICK)
+    }
+
+    // Advance the game by the time elapsed since the previous call.
+    // Nothing happens after the game is finished
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<StepResult> {
+        if self.result.is_some() {
+            return Vec::new();
+        }
+        let running = !self.tetris.is_paused() && self.tetris.countdown().is_none();
+        if running {
+            self.time += elapsed;
+            match self.mode {
+                GameMode::Ultra { time_limit } => self.time = self.time.min(time_limit),
+                GameMode::Dig {
+                    rise_every: Some(rise_every),
+                    ..
+                } if !rise_every.is_zero() => {
+                    // Garbage is queued, so it rises between tetromino moves
+                    self.garbage_timer += elapsed;
+                    while self.garbage_timer >= rise_every {
+                        self.garbage_timer -= rise_every;
+                        self.tetris.add_action(Action::BottomRefill);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let results = self.tetris.advance(elapsed);
+        if let GameMode::Puzzle { goal } = self.mode {
+            let total_lines = self.tetris.score().lines;
+            self.goal_reached |= results.iter().any(|result| match result {
+                StepResult::Locked(lock) => goal.is_reached(lock, total_lines),
+                _ => false,
+            });
+        }
+        // Endless marathon continues without the level cap after the lines are cleared
+        if let GameMode::Marathon {
+            lines,
+            endless: true,
+            ..
+        } = self.mode
+        {
+            if self.tetris.score().lines >= lines {
+                self.tetris.set_max_level(None);
+            }
+        }
+        self.result = self.check_result();
+        results
+    }
+
+    // Result if the game is finished now
+    fn check_result(&self) -> Option<ModeResult> {
+        let game_over = self.tetris.is_game_over();
+        let score = self.tetris.score();
+        match self.mode {
+            GameMode::Endless => game_over.then_some(ModeResult::Endless {
+                score,
+                time: self.time,
+            }),
+            GameMode::Sprint { lines } => {
+                let completed = score.lines >= lines;
+                (completed || game_over).then_some(ModeResult::Sprint {
+                    completed,
+                    time: self.time,
+                    lines: score.lines,
+                    pieces: score.pieces,
+                })
+            }
+            GameMode::Ultra { time_limit } => {
+                let completed = self.time >= time_limit;
+                (completed || game_over).then_some(ModeResult::Ultra {
+                    completed,
+                    score,
+                    time: self.time,
+                })
+            }
+            GameMode::Dig { .. } => {
+                let garbage_left = self.tetris.get_field().garbage_lines();
+                let completed = garbage_left == 0;
+                (completed || game_over).then_some(ModeResult::Dig {
+                    completed,
+                    time: self.time,
+                    pieces: score.pieces,
+                    garbage_left,
+                })
+            }
+            GameMode::Puzzle { .. } => {
+                (self.goal_reached || game_over).then_some(ModeResult::Puzzle {
+                    solved: self.goal_reached,
+                    pieces: score.pieces,
+                    time: self.time,
+                })
+            }
+            GameMode::Marathon { lines, endless, .. } => {
+                let cleared = score.lines >= lines;
+                ((cleared && !endless) || game_over).then_some(ModeResult::Marathon {
+                    cleared,
+                    score,
+                    time: self.time,
+                })
+            }
+        }
+    }
+
+    pub fn get_state(&self) -> SinglePlayerState {
+        SinglePlayerState {
+            tetris: self.tetris.get_state(),
+            mode: self.mode,
+            time: self.time,
+            result: self.result,
+        }
+    }
+}
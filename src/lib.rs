@@ -1,17 +1,109 @@
+mod analysis;
+mod auto_repeat;
+mod bot;
+mod clock;
+mod coop;
 mod frequency_regulator;
+mod fumen;
+mod game_mode;
+mod match_series;
+mod path;
+mod piece_sequence;
+mod placement;
+mod puzzle;
+mod rotation;
+mod rules;
+mod runner;
+mod scoring;
 mod state;
+mod tbp;
 mod term_render;
 mod tetris;
 mod tetris_pair;
+mod tournament;
 
+pub use analysis::FieldAnalysis;
+pub use auto_repeat::AutoRepeat;
+pub use auto_repeat::AutoRepeatSettings;
+pub use auto_repeat::KeyEvent;
+pub use bot::Bot;
+pub use bot::BotDifficulty;
+pub use bot::BotMove;
+pub use bot::BotPacing;
+pub use bot::HeuristicBot;
+pub use bot::HeuristicWeights;
+pub use clock::Clock;
+pub use clock::FakeClock;
+pub use clock::Gravity;
+pub use clock::MonotonicClock;
+pub use clock::TICK;
+pub use coop::CoopTetris;
+pub use fumen::Fumen;
+pub use fumen::FumenPage;
+pub use fumen::FumenRecorder;
+pub use fumen::FUMEN_COLS;
+pub use fumen::FUMEN_ROWS;
+pub use game_mode::GameMode;
+pub use game_mode::ModeInput;
+pub use game_mode::ModeResult;
+pub use game_mode::SinglePlayer;
+pub use match_series::MatchCommand;
+pub use match_series::MatchInput;
+pub use match_series::MatchSeries;
+pub use path::PathPlanner;
+pub use piece_sequence::PieceSequence;
+pub use placement::Placement;
+pub use puzzle::Puzzle;
+pub use puzzle::PuzzleGoal;
+pub use rules::Delays;
+pub use rules::DropStyle;
+pub use rules::GravityCurve;
+pub use rules::InputPolicy;
+pub use rules::LevelUp;
+pub use rules::LockDelay;
+pub use rules::LockReset;
+pub use rules::Randomizer;
+pub use rules::RotationSystem;
+pub use rules::RuleSet;
+pub use runner::Game;
+pub use runner::GameRunner;
+pub use runner::InputSource;
+pub use runner::RunnerHandle;
+pub use runner::StateSink;
+pub use scoring::Score;
+pub use scoring::Scoring;
+pub use state::CoopState;
+pub use state::MatchPhase;
+pub use state::MatchSeriesState;
+pub use state::RoundOutcome;
+pub use state::RoundResult;
+pub use state::SinglePlayerState;
 pub use state::TetrisPairState;
 pub use state::TetrisState;
+pub use tbp::TbpBot;
+pub use tbp::TbpBotMessage;
+pub use tbp::TbpFrontendMessage;
+pub use tbp::TbpLocation;
+pub use tbp::TbpMove;
+pub use tbp::TbpOrientation;
+pub use tbp::TbpServer;
+pub use tbp::TbpSpin;
+pub use tbp::TbpStart;
+pub use tbp::TBP_COLS;
+pub use tbp::TBP_ROWS;
+pub use term_render::format_time;
 pub use term_render::pad_block_right;
 pub use term_render::render_block;
 pub use term_render::AnsiTermStyle;
+pub use term_render::GameFieldCoop;
 pub use term_render::GameFieldLeft;
 pub use term_render::GameFieldPair;
 pub use term_render::GameFieldRight;
+pub use term_render::GameFieldSeries;
+pub use term_render::GameFieldSingle;
+pub use term_render::MatchHeader;
+pub use term_render::ModeHeader;
+pub use term_render::ModeStats;
 pub use term_render::PlainTermStyle;
 pub use term_render::PreviewField;
 pub use term_render::TermCell;
@@ -19,8 +111,21 @@ pub use term_render::TermRender;
 pub use term_render::TermStyle;
 pub use term_render::WellField;
 pub use tetris::Action;
+pub use tetris::CellType;
+pub use tetris::DropReason;
+pub use tetris::DroppedActions;
 pub use tetris::Field;
+pub use tetris::Lock;
+pub use tetris::Rotation;
 pub use tetris::StepResult;
+pub use tetris::TSpin;
 pub use tetris::Tetris;
+pub use tetris::Tetromino;
+pub use tetris::TetrominoType;
+pub use tetris_pair::PlayerSettings;
 pub use tetris_pair::PlayerSide;
 pub use tetris_pair::TetrisPair;
+pub use tournament::BotStanding;
+pub use tournament::GameRecord;
+pub use tournament::Tournament;
+pub use tournament::TournamentReport;
@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auto_repeat::KeyEvent,
+    clock::TICK,
+    state::{MatchPhase, MatchSeriesState, RoundOutcome, RoundResult},
+    tetris::{Action, StepResult},
+    tetris_pair::{PlayerSide, TetrisPair},
+};
+
+// Commands which are not game actions, but control the match flow
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MatchCommand {
+    // Ask for a new series after the current one is over.
+    // The new series starts when both players asked for it
+    Rematch,
+    // Pause the round. Any player can pause
+    Pause,
+    // Withdraw the pause request. The round continues when both players agreed
+    Resume,
+}
+
+// Input of the series from one of the players
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MatchInput {
+    Action(PlayerSide, Action),
+    // Key press or release for games with built-in key repeat
+    Key(PlayerSide, KeyEvent),
+    Command(PlayerSide, MatchCommand),
+}
+
+/// Best-of-N series of rounds played on one `TetrisPair`.
+/// When a round ends, the series waits `round_break` time so that players can see
+/// the final boards, then clears both wells and starts the next round.
+/// When one of the players wins the majority of rounds, the series stops
+/// until both players request a rematch.
+pub struct MatchSeries {
+    pair: TetrisPair,
+    best_of: usize,
+    round: usize,
+    player_wins: usize,
+    opponent_wins: usize,
+    phase: MatchPhase,
+    // Results of finished rounds of the current series
+    rounds: Vec<RoundResult>,
+    // Time to wait between rounds
+    round_break: Duration,
+    // Time left before the next round starts
+    round_break_left: Duration,
+    rematch_player: bool,
+    rematch_opponent: bool,
+    // Phase or rematch requests changed since the change was last taken
+    changed: bool,
+}
+
+impl MatchSeries {
+    pub fn new(mut pair: TetrisPair, best_of: usize) -> Self {
+        assert!(best_of > 0, "series must have at least one round");
+        pair.start_countdown();
+        MatchSeries {
+            pair,
+            best_of,
+            round: 1,
+            player_wins: 0,
+            opponent_wins: 0,
+            phase: MatchPhase::Playing,
+            rounds: Vec::new(),
+            round_break: Duration::from_secs(3),
+            round_break_left: Duration::ZERO,
+            rematch_player: false,
+            rematch_opponent: false,
+            changed: false,
+        }
+    }
+
+    pub fn set_round_break(&mut self, round_break: Duration) {
+        self.round_break = round_break;
+    }
+
+    pub fn pair(&self) -> &TetrisPair {
+        &self.pair
+    }
+
+    pub fn pair_mut(&mut self) -> &mut TetrisPair {
+        &mut self.pair
+    }
+
+    pub fn best_of(&self) -> usize {
+        self.best_of
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn phase(&self) -> MatchPhase {
+        self.phase
+    }
+
+    pub fn wins(&self, player: PlayerSide) -> usize {
+        match player {
+            PlayerSide::Player => self.player_wins,
+            PlayerSide::Opponent => self.opponent_wins,
+        }
+    }
+
+    // Number of round wins needed to win the series
+    pub fn wins_needed(&self) -> usize {
+        self.best_of / 2 + 1
+    }
+
+    pub fn winner(&self) -> Option<PlayerSide> {
+        if self.player_wins >= self.wins_needed() {
+            Some(PlayerSide::Player)
+        } else if self.opponent_wins >= self.wins_needed() {
+            Some(PlayerSide::Opponent)
+        } else {
+            None
+        }
+    }
+
+    pub fn rounds(&self) -> &[RoundResult] {
+        &self.rounds
+    }
+
+    pub fn last_round(&self) -> Option<RoundOutcome> {
+        self.rounds.last().map(|round| round.outcome)
+    }
+
+    pub fn is_match_over(&self) -> bool {
+        self.phase == MatchPhase::MatchOver
+    }
+
+    // Advance the series by one `TICK`
+    pub fn step(&mut self) -> (Vec<StepResult>, Vec<StepResult>) {
+        self.advance(TICK)
+    }
+
+    // Advance the series by the time elapsed since the previous call
+    pub fn advance(&mut self, elapsed: Duration) -> (Vec<StepResult>, Vec<StepResult>) {
+        match self.phase {
+            MatchPhase::Playing => {
+                let result = self.pair.advance(elapsed);
+                if self.pair.is_game_over() {
+                    self.finish_round();
+                }
+                result
+            }
+            MatchPhase::RoundOver => {
+                if self.round_break_left > elapsed {
+                    self.round_break_left -= elapsed;
+                } else {
+                    self.start_round();
+                }
+                (Vec::new(), Vec::new())
+            }
+            MatchPhase::MatchOver => (Vec::new(), Vec::new()),
+        }
+    }
+
+    // Returns true once after the phase or rematch requests changed.
+    // Nothing happens in the wells between rounds, so `advance` reports no results then
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    // Returns false if the action is dropped. Actions are dropped between rounds
+    pub fn add_player_action(&mut self, player: PlayerSide, action: Action) -> bool {
+        self.phase == MatchPhase::Playing && self.pair.add_player_action(player, action)
+    }
+
+    pub fn player_key_event(&mut self, player: PlayerSide, event: KeyEvent) {
+        match event {
+            KeyEvent::Press(_) if self.phase != MatchPhase::Playing => {}
+            _ => self.pair.player_key_event(player, event),
+        }
+    }
+
+    pub fn command(&mut self, player: PlayerSide, command: MatchCommand) {
+        match command {
+            MatchCommand::Rematch => self.request_rematch(player),
+            MatchCommand::Pause => {
+                if self.phase == MatchPhase::Playing {
+                    self.pair.pause(player);
+                }
+            }
+            MatchCommand::Resume => self.pair.resume(player),
+        }
+    }
+
+    // Rematch is accepted only after the series is over
+    pub fn request_rematch(&mut self, player: PlayerSide) {
+        if self.phase != MatchPhase::MatchOver {
+            return;
+        }
+        match player {
+            PlayerSide::Player => self.rematch_player = true,
+            PlayerSide::Opponent => self.rematch_opponent = true,
+        }
+        self.changed = true;
+        if self.rematch_player && self.rematch_opponent {
+            self.player_wins = 0;
+            self.opponent_wins = 0;
+            self.round = 1;
+            self.rounds.clear();
+            self.rematch_player = false;
+            self.rematch_opponent = false;
+            self.pair.reset();
+            self.pair.start_countdown();
+            self.phase = MatchPhase::Playing;
+        }
+    }
+
+    fn finish_round(&mut self) {
+        let player_lost = self.pair.is_player_game_over(PlayerSide::Player);
+        let opponent_lost = self.pair.is_player_game_over(PlayerSide::Opponent);
+        let outcome = match (player_lost, opponent_lost) {
+            (true, false) => RoundOutcome::Winner(PlayerSide::Opponent),
+            (false, true) => RoundOutcome::Winner(PlayerSide::Player),
+            _ => RoundOutcome::Draw,
+        };
+        match outcome {
+            RoundOutcome::Winner(PlayerSide::Player) => self.player_wins += 1,
+            RoundOutcome::Winner(PlayerSide::Opponent) => self.opponent_wins += 1,
+            RoundOutcome::Draw => {}
+        }
+        self.rounds.push(RoundResult {
+            outcome,
+            seed: self.pair.shared_seed(),
+        });
+        self.changed = true;
+        if self.winner().is_some() {
+            self.phase = MatchPhase::MatchOver;
+        } else {
+            self.phase = MatchPhase::RoundOver;
+            self.round_break_left = self.round_break;
+        }
+    }
+
+    fn start_round(&mut self) {
+        // Draw is replayed, so the round number doesn't change
+        if self.last_round() != Some(RoundOutcome::Draw) {
+            self.round += 1;
+        }
+        self.pair.reset();
+        self.pair.start_countdown();
+        self.phase = MatchPhase::Playing;
+        self.changed = true;
+    }
+
+    pub fn get_state(&self) -> MatchSeriesState {
+        MatchSeriesState {
+            pair: self.pair.get_state(),
+            best_of: self.best_of,
+            round: self.round,
+            player_wins: self.player_wins,
+            opponent_wins: self.opponent_wins,
+            phase: self.phase,
+            rounds: self.rounds.clone(),
+            rematch_player: self.rematch_player,
+            rematch_opponent: self.rematch_opponent,
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use crate::{
+    clock::Gravity,
+    placement::{rotate, Placement},
+    rules::{DropStyle, RotationSystem, RuleSet},
+    tetris::{Action, Field, Rotation, TSpin, Tetromino},
+};
+
+/// Finds the shortest sequence of actions moving a tetromino into the target placement and
+/// locking it there, with the moves, kicks and drop of the rules.
+/// By default all actions are assumed to be performed before the tetromino falls, as it happens
+/// when they are queued at once. Set `fall_every` for inputs made one by one while it falls.
+/// Fall timing of the game isn't known to the planner, so such paths are approximate:
+/// plan again from the current position before each input.
+#[derive(Debug, Clone, Copy)]
+pub struct PathPlanner {
+    system: RotationSystem,
+    drop: DropStyle,
+    // Tetromino locks as soon as it can't fall
+    instant_lock: bool,
+    // Number of actions between falls by one row, 0 if it falls to the ground after each
+    fall_every: Option<usize>,
+}
+
+impl PathPlanner {
+    pub fn new(rules: &RuleSet) -> Self {
+        PathPlanner {
+            system: rules.rotation,
+            drop: rules.drop,
+            instant_lock: rules.lock_delay.delay == 0,
+            fall_every: None,
+        }
+    }
+
+    // Tetromino falls by one row after each `actions` actions, or to the ground after each
+    // action if `actions` is 0. None if it doesn't fall while the actions are performed
+    pub fn with_fall_every(mut self, actions: Option<usize>) -> Self {
+        self.fall_every = actions;
+        self
+    }
+
+    // Fall timing for inputs made at the given rate under the gravity. Falling by a row
+    // or more for each input is taken as falling to the ground
+    pub fn with_input_rate(self, inputs_per_second: f64, gravity: Gravity) -> Self {
+        let rows_per_second = gravity.rows_per_second();
+        if rows_per_second <= 0.0 {
+            return self.with_fall_every(None);
+        }
+        self.with_fall_every(Some((inputs_per_second / rows_per_second).floor() as usize))
+    }
+
+    // Actions moving the tetromino from `start` to `target` and locking it there,
+    // None if it can't get there or can't lock there because it would fall.
+    // Any rotation filling the same cells as the target will do
+    pub fn find(&self, field: &Field, start: Tetromino, target: Tetromino) -> Option<Vec<Action>> {
+        self.search(field, start, target, false)
+    }
+
+    // Actions locking the tetromino in the placement. If the placement is a T-spin,
+    // the last move before locking is a rotation
+    pub fn find_placement(
+        &self,
+        field: &Field,
+        start: Tetromino,
+        placement: &Placement,
+    ) -> Option<Vec<Action>> {
+        let spin = placement.tspin != TSpin::None;
+        self.search(field, start, placement.tetromino, spin)
+    }
+
+    fn search(
+        &self,
+        field: &Field,
+        start: Tetromino,
+        target: Tetromino,
+        spin: bool,
+    ) -> Option<Vec<Action>> {
+        if start.intersects(field)
+            || target.intersects(field)
+            || !target.moved(0, 1, Rotation::R0).intersects(field)
+        {
+            return None;
+        }
+        let mut target_cells = target.cells();
+        target_cells.sort();
+        let is_target = |tetromino: &Tetromino| {
+            let mut cells = tetromino.cells();
+            cells.sort();
+            cells == target_cells
+        };
+        let start = State {
+            tetromino: self.fall_to_ground(field, start),
+            actions: 0,
+            rotated: false,
+        };
+        // Previous state and the action leading from it
+        let mut parents: HashMap<State, Option<(State, Action)>> = HashMap::new();
+        parents.insert(start, None);
+        let mut queue = VecDeque::from([(start, 0)]);
+        // Last state and the actions finishing the path from it
+        let mut best: Option<(State, Vec<Action>, usize)> = None;
+        while let Some((state, depth)) = queue.pop_front() {
+            // Finishing takes at least one more action
+            if best.as_ref().is_some_and(|(_, _, cost)| depth + 1 >= *cost) {
+                break;
+            }
+            if let Some(finish) = self.finish(field, &state, &is_target, spin) {
+                let cost = depth + finish.len();
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, _, best_cost)| cost < *best_cost)
+                {
+                    best = Some((state, finish, cost));
+                }
+            }
+            for action in self.moves() {
+                let Some(next) = self.perform(field, &state, action) else {
+                    continue;
+                };
+                match next {
+                    Step::Moved(next) => {
+                        if let Entry::Vacant(entry) = parents.entry(next) {
+                            entry.insert(Some((state, action)));
+                            queue.push_back((next, depth + 1));
+                        }
+                    }
+                    // Gravity locked the tetromino in the target
+                    Step::Locked(next) if is_target(&next.tetromino) && (!spin || next.rotated) => {
+                        let cost = depth + 1;
+                        if best
+                            .as_ref()
+                            .is_none_or(|(_, _, best_cost)| cost < *best_cost)
+                        {
+                            best = Some((state, vec![action], cost));
+                        }
+                    }
+                    Step::Locked(_) => {}
+                }
+            }
+        }
+
+        let (mut state, mut actions, _) = best?;
+        actions.reverse();
+        while let Some(Some((parent, action))) = parents.get(&state) {
+            actions.push(*action);
+            state = *parent;
+        }
+        actions.reverse();
+        Some(actions)
+    }
+
+    // Actions tried from each state, in the order of preference
+    fn moves(&self) -> Vec<Action> {
+        let mut moves = vec![
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::RotateRight,
+            Action::RotateLeft,
+            Action::MoveDown,
+        ];
+        // Sonic drop moves the tetromino to the ground and lets it move on
+        if self.drop == DropStyle::Sonic {
+            moves.push(Action::Drop);
+        }
+        moves
+    }
+
+    fn perform(&self, field: &Field, state: &State, action: Action) -> Option<Step> {
+        let tetromino = &state.tetromino;
+        let moved = match action {
+            Action::MoveLeft => tetromino.moved(-1, 0, Rotation::R0),
+            Action::MoveRight => tetromino.moved(1, 0, Rotation::R0),
+            Action::MoveDown => tetromino.moved(0, 1, Rotation::R0),
+            Action::RotateRight => rotate(field, tetromino, Rotation::R90, self.system)?,
+            Action::RotateLeft => rotate(field, tetromino, Rotation::R270, self.system)?,
+            Action::Drop => drop_to_ground(field, *tetromino),
+            _ => return None,
+        };
+        if moved.intersects(field) || moved == *tetromino {
+            return None;
+        }
+        let rotated = matches!(action, Action::RotateLeft | Action::RotateRight);
+        let moved = State {
+            tetromino: moved,
+            actions: state.actions + 1,
+            rotated,
+        };
+        let falls = match self.fall_every {
+            None => false,
+            Some(0) => true,
+            Some(fall_every) => moved.actions >= fall_every,
+        };
+        if !falls {
+            return Some(Step::Moved(State {
+                actions: if self.fall_every.is_some() {
+                    moved.actions
+                } else {
+                    0
+                },
+                ..moved
+            }));
+        }
+        let fallen = self.fall(field, moved.tetromino);
+        if fallen != moved.tetromino {
+            // Falling isn't a rotation, T-spin is lost
+            Some(Step::Moved(State {
+                tetromino: fallen,
+                actions: 0,
+                rotated: false,
+            }))
+        } else if self.instant_lock {
+            Some(Step::Locked(moved))
+        } else {
+            Some(Step::Moved(State {
+                actions: 0,
+                ..moved
+            }))
+        }
+    }
+
+    // Position after the tetromino falls once
+    fn fall(&self, field: &Field, tetromino: Tetromino) -> Tetromino {
+        match self.fall_every {
+            Some(0) => drop_to_ground(field, tetromino),
+            _ => {
+                let fallen = tetromino.moved(0, 1, Rotation::R0);
+                if fallen.intersects(field) {
+                    tetromino
+                } else {
+                    fallen
+                }
+            }
+        }
+    }
+
+    // Actions locking the tetromino in the target from the given state, if it's possible
+    fn finish(
+        &self,
+        field: &Field,
+        state: &State,
+        is_target: &impl Fn(&Tetromino) -> bool,
+        spin: bool,
+    ) -> Option<Vec<Action>> {
+        if is_target(&state.tetromino) {
+            if spin && !state.rotated {
+                return None;
+            }
+            // Failed move down locks the tetromino, except with hard drop rules
+            return Some(match self.drop {
+                DropStyle::Hard => vec![Action::Drop],
+                _ => vec![Action::MoveDown],
+            });
+        }
+        if spin || !is_target(&drop_to_ground(field, state.tetromino)) {
+            return None;
+        }
+        Some(match self.drop {
+            DropStyle::Sonic => vec![Action::Drop, Action::MoveDown],
+            _ => vec![Action::Drop],
+        })
+    }
+
+    fn fall_to_ground(&self, field: &Field, tetromino: Tetromino) -> Tetromino {
+        match self.fall_every {
+            Some(0) => drop_to_ground(field, tetromino),
+            _ => tetromino,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    tetromino: Tetromino,
+    // Actions since the last fall
+    actions: usize,
+    // Last move was a rotation
+    rotated: bool,
+}
+
+enum Step {
+    Moved(State),
+    // Tetromino couldn't fall and locked
+    Locked(State),
+}
+
+fn drop_to_ground(field: &Field, tetromino: Tetromino) -> Tetromino {
+    let mut dropped = tetromino;
+    while !dropped.moved(0, 1, Rotation::R0).intersects(field) {
+        dropped = dropped.moved(0, 1, Rotation::R0);
+    }
+    dropped
+}
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{rules::Randomizer, tetris::TetrominoType};
+
+const ALL_TYPES: [TetrominoType; 7] = [
+    TetrominoType::I,
+    TetrominoType::J,
+    TetrominoType::L,
+    TetrominoType::O,
+    TetrominoType::S,
+    TetrominoType::T,
+    TetrominoType::Z,
+];
+
+/// Reproducible sequence of tetromino types.
+/// Two sequences created from the same seed produce identical tetrominos, so
+/// players can be given the same pieces while each of them consumes the sequence at own pace.
+pub struct PieceSequence {
+    seed: u64,
+    rng: StdRng,
+    randomizer: Randomizer,
+    // Rest of the current bag for `Randomizer::Bag7`
+    bag: Vec<TetrominoType>,
+    // Last tetrominos for `Randomizer::Tgm`, empty before the first one
+    history: VecDeque<TetrominoType>,
+}
+
+impl PieceSequence {
+    // Create sequence with random seed
+    pub fn new() -> Self {
+        Self::from_seed(rand::random())
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        Self::with_randomizer(Randomizer::Random, seed)
+    }
+
+    pub fn with_randomizer(randomizer: Randomizer, seed: u64) -> Self {
+        PieceSequence {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            randomizer,
+            bag: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn randomizer(&self) -> Randomizer {
+        self.randomizer
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_tetromino_type(&mut self) -> TetrominoType {
+        match self.randomizer {
+            Randomizer::Random => self.random_type(),
+            Randomizer::Bag7 => {
+                if self.bag.is_empty() {
+                    self.bag = ALL_TYPES.to_vec();
+                    self.bag.shuffle(&mut self.rng);
+                }
+                self.bag.pop().unwrap()
+            }
+            Randomizer::Tgm => self.next_tgm_type(),
+        }
+    }
+
+    fn random_type(&mut self) -> TetrominoType {
+        ALL_TYPES[self.rng.gen_range(0..7)]
+    }
+
+    fn next_tgm_type(&mut self) -> TetrominoType {
+        let tetromino_type = if self.history.is_empty() {
+            // History starts filled with Z, so first tetromino is also never S, Z or O
+            self.history.extend([TetrominoType::Z; 4]);
+            [
+                TetrominoType::I,
+                TetrominoType::J,
+                TetrominoType::L,
+                TetrominoType::T,
+            ][self.rng.gen_range(0..4)]
+        } else {
+            let mut tetromino_type = self.random_type();
+            for _ in 1..4 {
+                if !self.history.contains(&tetromino_type) {
+                    break;
+                }
+                tetromino_type = self.random_type();
+            }
+            tetromino_type
+        };
+        self.history.pop_front();
+        self.history.push_back(tetromino_type);
+        tetromino_type
+    }
+}
+
+impl Default for PieceSequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Derive seed for the next game from the seed of the previous one,
+// so that the whole series of games can be replayed from the first seed
+pub fn next_seed(seed: u64) -> u64 {
+    StdRng::seed_from_u64(seed).gen()
+}
@@ -0,0 +1,171 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rotation::{rotation_offsets, spawn_x},
+    rules::RotationSystem,
+    tetris::{CellType, Field, Rotation, TSpin, Tetromino, TetrominoType},
+};
+
+/// Final resting place of a tetromino: it can't move down from there and locks.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Placement {
+    pub tetromino: Tetromino,
+    // Best T-spin the tetromino can lock with, if it can be rotated into the place
+    pub tspin: TSpin,
+    // Lines the tetromino clears
+    pub lines: usize,
+}
+
+impl Placement {
+    // All placements reachable from the spawn position with moves left, right and down
+    // and rotations with kicks of the rotation system. Placements which leave the same board
+    // are listed once, the one found with fewer moves first. Empty if the tetromino can't spawn
+    pub fn find_all(
+        field: &Field,
+        tetromino_type: TetrominoType,
+        system: RotationSystem,
+    ) -> Vec<Placement> {
+        let x = spawn_x(system, tetromino_type, field.cols());
+        Placement::find_from(
+            field,
+            Tetromino::new(tetromino_type, Rotation::R0, x, 0),
+            system,
+        )
+    }
+
+    // All placements reachable from the given position, see `find_all`
+    pub fn find_from(field: &Field, start: Tetromino, system: RotationSystem) -> Vec<Placement> {
+        if start.intersects(field) {
+            return Vec::new();
+        }
+        // Positions are never negative, so they index the visited states directly
+        let cols = field.cols();
+        let rows = field.rows();
+        let index = |t: &Tetromino| {
+            (rotation_index(t.rotation()) * rows + t.y() as usize) * cols + t.x() as usize
+        };
+        // Whether the state was reached and whether it was reached by a rotation
+        let mut visited = vec![None; 4 * rows * cols];
+        let mut queue = VecDeque::new();
+        visited[index(&start)] = Some(false);
+        queue.push_back(start);
+
+        // States in the order of distance from the start
+        let mut order = Vec::new();
+        while let Some(tetromino) = queue.pop_front() {
+            order.push(tetromino);
+            let moves = [(-1, 0), (1, 0), (0, 1)]
+                .into_iter()
+                .map(|(x, y)| (tetromino.moved(x, y, Rotation::R0), false));
+            let rotations = [Rotation::R90, Rotation::R270]
+                .into_iter()
+                .filter_map(|rotation| rotate(field, &tetromino, rotation, system))
+                .map(|rotated| (rotated, true));
+            for (next, rotated) in moves.chain(rotations) {
+                if next.intersects(field) {
+                    continue;
+                }
+                match &mut visited[index(&next)] {
+                    Some(by_rotation) => *by_rotation |= rotated,
+                    state @ None => {
+                        *state = Some(rotated);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        let mut placements: Vec<Placement> = Vec::new();
+        let mut boards: HashMap<Vec<(isize, isize)>, usize> = HashMap::new();
+        for tetromino in order {
+            if !tetromino.moved(0, 1, Rotation::R0).intersects(field) {
+                continue;
+            }
+            let tspin = match visited[index(&tetromino)] {
+                Some(true) => tetromino.tspin(field),
+                _ => TSpin::None,
+            };
+            let mut cells = tetromino.cells();
+            cells.sort();
+            match boards.get(&cells) {
+                Some(&i) => {
+                    if tspin_rank(tspin) > tspin_rank(placements[i].tspin) {
+                        placements[i].tspin = tspin;
+                        placements[i].tetromino = tetromino;
+                    }
+                }
+                None => {
+                    boards.insert(cells, placements.len());
+                    placements.push(Placement {
+                        tetromino,
+                        tspin,
+                        lines: cleared_lines(field, &tetromino),
+                    });
+                }
+            }
+        }
+        placements
+    }
+
+    // Board after the tetromino is locked and the lines are cleared
+    pub fn apply(&self, field: &Field) -> Field {
+        let mut field = field.clone();
+        self.tetromino.draw(&mut field);
+        if field.blast_full_lines() {
+            field.remove_blasted_lines();
+        }
+        field
+    }
+}
+
+fn rotation_index(rotation: Rotation) -> usize {
+    match rotation {
+        Rotation::R0 => 0,
+        Rotation::R90 => 1,
+        Rotation::R180 => 2,
+        Rotation::R270 => 3,
+    }
+}
+
+fn tspin_rank(tspin: TSpin) -> usize {
+    match tspin {
+        TSpin::None => 0,
+        TSpin::Mini => 1,
+        TSpin::Full => 2,
+    }
+}
+
+// Rotated tetromino at the first kick position which fits, like `Tetris` rotates it
+pub(crate) fn rotate(
+    field: &Field,
+    tetromino: &Tetromino,
+    rotation: Rotation,
+    system: RotationSystem,
+) -> Option<Tetromino> {
+    let to = tetromino.rotation() + rotation;
+    rotation_offsets(system, tetromino.tetromino_type(), tetromino.rotation(), to)
+        .into_iter()
+        .map(|(x, y)| tetromino.moved(x, y, rotation))
+        .find(|rotated| !rotated.intersects(field))
+}
+
+fn cleared_lines(field: &Field, tetromino: &Tetromino) -> usize {
+    cleared_rows(field, tetromino).len()
+}
+
+// Rows the tetromino fills up when it's locked
+pub(crate) fn cleared_rows(field: &Field, tetromino: &Tetromino) -> Vec<isize> {
+    let cells = tetromino.cells();
+    let mut rows: Vec<isize> = cells.iter().map(|&(_, y)| y).collect();
+    rows.sort();
+    rows.dedup();
+    rows.into_iter()
+        .filter(|&y| {
+            (0..field.cols()).all(|x| {
+                cells.contains(&(x as isize, y)) || field.get_cell(x, y as usize) != CellType::Empty
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fumen::{Fumen, FumenPage},
+    game_mode::{GameMode, SinglePlayer},
+    rules::RuleSet,
+    tetris::{Field, Lock, TSpin, Tetris, TetrominoType},
+};
+
+// What must be done to solve the puzzle
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum PuzzleGoal {
+    // Clear at least this number of lines in total
+    ClearLines(usize),
+    // Leave the well empty after a line clear
+    PerfectClear,
+    // T-spin clearing this number of lines, i.e. 2 for T-spin double
+    TSpin(usize),
+}
+
+impl PuzzleGoal {
+    // The goal is reached by the lock, or by the total number of cleared lines
+    pub fn is_reached(&self, lock: &Lock, total_lines: usize) -> bool {
+        match self {
+            PuzzleGoal::ClearLines(lines) => total_lines >= *lines,
+            PuzzleGoal::PerfectClear => lock.perfect_clear,
+            PuzzleGoal::TSpin(lines) => lock.tspin == TSpin::Full && lock.lines == *lines,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            PuzzleGoal::ClearLines(lines) => format!("clear {} lines", lines),
+            PuzzleGoal::PerfectClear => "perfect clear".to_string(),
+            PuzzleGoal::TSpin(1) => "T-spin single".to_string(),
+            PuzzleGoal::TSpin(2) => "T-spin double".to_string(),
+            PuzzleGoal::TSpin(3) => "T-spin triple".to_string(),
+            PuzzleGoal::TSpin(lines) => format!("T-spin clearing {} lines", lines),
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["clear-lines", lines] => lines
+                .parse()
+                .map(PuzzleGoal::ClearLines)
+                .map_err(|_| format!("invalid number of lines: {}", lines)),
+            ["perfect-clear"] => Ok(PuzzleGoal::PerfectClear),
+            ["tspin-single"] => Ok(PuzzleGoal::TSpin(1)),
+            ["tspin-double"] => Ok(PuzzleGoal::TSpin(2)),
+            ["tspin-triple"] => Ok(PuzzleGoal::TSpin(3)),
+            _ => Err(format!("unknown goal: {}", s)),
+        }
+    }
+}
+
+/// Puzzle: the well with some cells filled, the fixed sequence of tetrominos and the goal.
+/// The puzzle is solved if the goal is reached before the tetrominos are used up.
+///
+/// Puzzles are stored as JSON or in the text format:
+///
+/// ```text
+/// name: First T-spin
+/// goal: tspin-double
+/// pieces: T
+/// rows: 20
+/// board:
+/// GGGGG..GGG
+/// GGGG...GGG
+/// GGGGG.GGGG
+/// ```
+///
+/// `rows` is the height of the well, 20 if omitted; the board is placed at its bottom.
+/// The board goes last, its first row may follow `board:` on the same line.
+/// Goal is one of `clear-lines N`, `perfect-clear`, `tspin-single`, `tspin-double`, `tspin-triple`.
+/// The board is in ASCII notation of `Field::from_ascii`: `.` is an empty cell, `G` or `#` is garbage
+/// and `IJLOSTZ` are tetromino cells.
+/// Instead of the board, `fumen: v115@...` takes the board and, unless given, the pieces from fumen.
+/// Lines starting with `;` are comments. Puzzles of a pack are separated with `---` line.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub name: String,
+    // Height of the well
+    pub rows: usize,
+    // Rows of the board from top to bottom, placed at the bottom of the well
+    pub board: Vec<String>,
+    pub pieces: Vec<TetrominoType>,
+    pub goal: PuzzleGoal,
+}
+
+const DEFAULT_ROWS: usize = 20;
+
+impl Puzzle {
+    pub fn from_json(json: &str) -> serde_json::Result<Puzzle> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("puzzles are always serializable")
+    }
+
+    // Parse one puzzle in the text format
+    pub fn from_text(text: &str) -> Result<Puzzle, String> {
+        let mut name = None;
+        let mut rows = DEFAULT_ROWS;
+        let mut pieces = None;
+        let mut goal = None;
+        let mut board = Vec::new();
+        let mut fumen = None;
+        let mut in_board = false;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            if in_board {
+                board.push(line.to_string());
+                continue;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("expected `key: value`, got: {}", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "rows" => {
+                    rows = value
+                        .parse()
+                        .map_err(|_| format!("invalid number of rows: {}", value))?
+                }
+                "pieces" => {
+                    pieces = Some(
+                        value
+                            .split_whitespace()
+                            .map(|piece| {
+                                let mut chars = piece.chars();
+                                match (
+                                    chars.next().and_then(TetrominoType::from_char),
+                                    chars.next(),
+                                ) {
+                                    (Some(tetromino_type), None) => Ok(tetromino_type),
+                                    _ => Err(format!("unknown tetromino: {}", piece)),
+                                }
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                    )
+                }
+                "goal" => goal = Some(PuzzleGoal::parse(value)?),
+                "fumen" => fumen = Some(value.to_string()),
+                // Rows follow until the end, the first one may be on the same line
+                "board" => {
+                    in_board = true;
+                    if !value.is_empty() {
+                        board.push(value.to_string());
+                    }
+                }
+                key => return Err(format!("unknown key: {}", key)),
+            }
+        }
+        let goal = goal.ok_or("goal is missing")?;
+        let puzzle = match fumen {
+            Some(_) if !board.is_empty() => {
+                return Err("both board and fumen are given".to_string())
+            }
+            Some(fumen) => {
+                let from_fumen = Puzzle::decode_fumen(&fumen, goal)?;
+                Puzzle {
+                    name: name.unwrap_or(from_fumen.name),
+                    rows: rows.max(from_fumen.board.len()),
+                    board: from_fumen.board,
+                    pieces: pieces.unwrap_or(from_fumen.pieces),
+                    goal,
+                }
+            }
+            None => Puzzle {
+                name: name.unwrap_or_default(),
+                rows,
+                board,
+                pieces: pieces.ok_or("pieces are missing")?,
+                goal,
+            },
+        };
+        if puzzle.pieces.is_empty() {
+            return Err("pieces are missing".to_string());
+        }
+        puzzle.field()?;
+        Ok(puzzle)
+    }
+
+    // Parse puzzles separated with `---` lines
+    pub fn pack_from_text(text: &str) -> Result<Vec<Puzzle>, String> {
+        let mut puzzles = Vec::new();
+        let mut current = String::new();
+        for line in text.lines() {
+            if line.trim() == "---" {
+                puzzles.push(Puzzle::from_text(&current)?);
+                current.clear();
+            } else {
+                current.push_str(line);
+                current.push('\n');
+            }
+        }
+        if !current.trim().is_empty() {
+            puzzles.push(Puzzle::from_text(&current)?);
+        }
+        Ok(puzzles)
+    }
+
+    // Puzzle from the first page of fumen. Pieces are taken from the quiz comment
+    // `#Q=[hold](current)next;name` if there is one, otherwise from the pages in their order.
+    // A held piece goes first, the player holds it to get the same position
+    pub fn from_fumen(fumen: &str, goal: PuzzleGoal) -> Result<Puzzle, String> {
+        let puzzle = Puzzle::decode_fumen(fumen, goal)?;
+        if puzzle.pieces.is_empty() {
+            return Err("fumen has no pieces".to_string());
+        }
+        Ok(puzzle)
+    }
+
+    // Puzzle from fumen, possibly without pieces
+    fn decode_fumen(fumen: &str, goal: PuzzleGoal) -> Result<Puzzle, String> {
+        let fumen = Fumen::decode(fumen)?;
+        let first = &fumen.pages[0];
+        let comment = first.comment.clone().unwrap_or_default();
+        let (name, pieces) = match comment.strip_prefix("#Q=") {
+            Some(quiz) => {
+                let (queue, name) = quiz.split_once(';').unwrap_or((quiz, ""));
+                let pieces = queue
+                    .chars()
+                    .filter(|c| !"[]()".contains(*c))
+                    .map(|c| {
+                        TetrominoType::from_char(c)
+                            .ok_or_else(|| format!("unknown tetromino in quiz: {}", c))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                (name.to_string(), pieces)
+            }
+            None => {
+                let pieces = fumen
+                    .pages
+                    .iter()
+                    .filter_map(|page| page.tetromino.map(|t| t.tetromino_type()))
+                    .collect();
+                (comment, pieces)
+            }
+        };
+        // Empty rows above the board are left out, the bottom row is kept to give the width
+        let ascii = first.field.to_string();
+        let rows: Vec<&str> = ascii.lines().collect();
+        let top = rows
+            .iter()
+            .position(|row| row.chars().any(|c| c != '.'))
+            .unwrap_or(rows.len() - 1);
+        let board: Vec<String> = rows[top..].iter().map(|row| row.to_string()).collect();
+        Ok(Puzzle {
+            name,
+            rows: DEFAULT_ROWS.max(board.len()),
+            board,
+            pieces,
+            goal,
+        })
+    }
+
+    // Fumen with the board, the pieces and the name in the quiz comment
+    pub fn to_fumen(&self) -> Result<Fumen, String> {
+        let mut page = FumenPage::new(&self.field()?)?;
+        let pieces: String = self
+            .pieces
+            .iter()
+            .map(|t| t.get_cell_type().to_char())
+            .collect();
+        let (current, next) = pieces.split_at(pieces.len().min(1));
+        page.comment = Some(format!("#Q=[]({}){};{}", current, next, self.name));
+        Ok(Fumen { pages: vec![page] })
+    }
+
+    // Width of the well, given by the board
+    pub fn cols(&self) -> usize {
+        self.board.first().map_or(0, |row| row.chars().count())
+    }
+
+    // The well with the board at its bottom
+    pub fn field(&self) -> Result<Field, String> {
+        let cols = self.cols();
+        if cols == 0 {
+            return Err("board is empty".to_string());
+        }
+        if self.board.len() > self.rows {
+            return Err("board is higher than the well".to_string());
+        }
+        let board = Field::from_ascii(&self.board.join("\n"))?;
+        let mut field = Field::new(cols, self.rows);
+        let top = self.rows - board.rows();
+        for y in 0..board.rows() {
+            for x in 0..cols {
+                field.set_cell(x, top + y, board.get_cell(x, y));
+            }
+        }
+        Ok(field)
+    }
+
+    // Tetris with the board and the pieces of the puzzle. Restarting the game restores them
+    pub fn create_tetris(&self, rules: RuleSet) -> Result<Tetris, String> {
+        let field = self.field()?;
+        let mut tetris = Tetris::with_rules(field.cols(), field.rows(), rules);
+        tetris.set_start_field(Some(field))?;
+        tetris.set_fixed_queue(Some(self.pieces.clone()));
+        Ok(tetris)
+    }
+
+    pub fn create_game(&self, rules: RuleSet) -> Result<SinglePlayer, String> {
+        let tetris = self.create_tetris(rules)?;
+        Ok(SinglePlayer::new(
+            tetris,
+            GameMode::Puzzle { goal: self.goal },
+        ))
+    }
+}
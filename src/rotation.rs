@@ -0,0 +1,97 @@
+use crate::{
+    rules::RotationSystem,
+    tetris::{Rotation, TetrominoType},
+};
+
+// Tetromino position is the top left corner of its rotated cells, while SRS rotates
+// tetromino inside a fixed box. Offset of the cells in the SRS box for each rotation
+fn srs_offset(tetromino_type: TetrominoType, rotation: Rotation) -> (isize, isize) {
+    match tetromino_type {
+        TetrominoType::I => match rotation {
+            Rotation::R0 => (0, 1),
+            Rotation::R90 => (2, 0),
+            Rotation::R180 => (0, 2),
+            Rotation::R270 => (1, 0),
+        },
+        TetrominoType::O => (1, 0),
+        _ => match rotation {
+            Rotation::R0 => (0, 0),
+            Rotation::R90 => (1, 0),
+            Rotation::R180 => (0, 1),
+            Rotation::R270 => (0, 0),
+        },
+    }
+}
+
+// SRS kicks of J, L, S, T and Z from rotation R0, R90, R180, R270 clockwise,
+// with y pointing up as in the SRS description
+const SRS_KICKS_JLSTZ: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+];
+
+const SRS_KICKS_I: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+];
+
+fn rotation_index(rotation: Rotation) -> usize {
+    match rotation {
+        Rotation::R0 => 0,
+        Rotation::R90 => 1,
+        Rotation::R180 => 2,
+        Rotation::R270 => 3,
+    }
+}
+
+// Position offsets to try, in order, when tetromino rotates from `from` to `to`.
+// The first one which doesn't intersect anything is taken
+pub(crate) fn rotation_offsets(
+    system: RotationSystem,
+    tetromino_type: TetrominoType,
+    from: Rotation,
+    to: Rotation,
+) -> Vec<(isize, isize)> {
+    match system {
+        RotationSystem::Classic => vec![(0, 0)],
+        RotationSystem::Tgm => match tetromino_type {
+            TetrominoType::I => vec![(0, 0)],
+            _ => vec![(0, 0), (1, 0), (-1, 0)],
+        },
+        RotationSystem::Srs => {
+            let (from_x, from_y) = srs_offset(tetromino_type, from);
+            let (to_x, to_y) = srs_offset(tetromino_type, to);
+            let table = match tetromino_type {
+                TetrominoType::O => return vec![(0, 0)],
+                TetrominoType::I => &SRS_KICKS_I,
+                _ => &SRS_KICKS_JLSTZ,
+            };
+            // Counterclockwise kicks are the clockwise ones of the reverse rotation, negated
+            let kicks: Vec<(isize, isize)> = if to == from.rotate_right() {
+                table[rotation_index(from)].to_vec()
+            } else {
+                table[rotation_index(to)]
+                    .iter()
+                    .map(|(x, y)| (-x, -y))
+                    .collect()
+            };
+            kicks
+                .into_iter()
+                .map(|(x, y)| (to_x - from_x + x, to_y - from_y - y))
+                .collect()
+        }
+    }
+}
+
+// Column of the tetromino position when it appears in the well
+pub(crate) fn spawn_x(system: RotationSystem, tetromino_type: TetrominoType, cols: usize) -> isize {
+    let x = cols as isize / 2 - 2;
+    match system {
+        RotationSystem::Srs => x + srs_offset(tetromino_type, Rotation::R0).0,
+        _ => x,
+    }
+}
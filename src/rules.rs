@@ -0,0 +1,436 @@
+use std::{fs, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auto_repeat::AutoRepeatSettings,
+    clock::{Gravity, TICK},
+    scoring::Scoring,
+};
+
+// Delays after the tetromino is locked, in ticks (see `TICK`)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Delays {
+    // Entry delay (ARE): time before the next tetromino appears when no lines were cleared
+    pub are: u32,
+    // Time before the next tetromino appears after cleared lines collapsed
+    pub line_clear_are: u32,
+    // Time the blasted lines are shown before they collapse
+    pub clear_animation: u32,
+}
+
+impl Delays {
+    // No delays at all: lines are removed and next tetromino appears right after lock
+    pub const ZERO: Delays = Delays {
+        are: 0,
+        line_clear_are: 0,
+        clear_animation: 0,
+    };
+
+    pub fn are(&self) -> Duration {
+        TICK * self.are
+    }
+
+    pub fn line_clear_are(&self) -> Duration {
+        TICK * self.line_clear_are
+    }
+
+    pub fn clear_animation(&self) -> Duration {
+        TICK * self.clear_animation
+    }
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Delays {
+            are: 10,
+            line_clear_are: 3,
+            clear_animation: 10,
+        }
+    }
+}
+
+// How user actions are buffered
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct InputPolicy {
+    // Maximum number of queued user actions, further actions are dropped. None for unlimited queue
+    pub max_queue: Option<usize>,
+    // Initial rotation system: rotations queued when the tetromino locks are applied to the next
+    // tetromino as it appears. Otherwise they are dropped together with other queued actions
+    pub irs: bool,
+    // Initial hold system: hold queued when the tetromino locks is applied to the next tetromino
+    pub ihs: bool,
+}
+
+impl Default for InputPolicy {
+    fn default() -> Self {
+        InputPolicy {
+            max_queue: Some(32),
+            irs: false,
+            ihs: false,
+        }
+    }
+}
+
+// Order in which tetrominos come
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Randomizer {
+    // Each tetromino is chosen independently
+    Random,
+    // All seven tetrominos in random order, then the next seven
+    Bag7,
+    // Tetromino is rerolled up to 4 times if it's one of the last 4 tetrominos.
+    // The first tetromino is never S, Z or O
+    Tgm,
+}
+
+// How tetromino rotates and what it does when rotation is blocked
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum RotationSystem {
+    // Rotation around the top left corner, blocked rotation fails
+    Classic,
+    // Super Rotation System with wall kicks
+    Srs,
+    // Classic rotation which tries to kick one cell right, then left. I doesn't kick
+    Tgm,
+}
+
+// What restarts lock delay
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum LockReset {
+    // Any successful move or rotation, limited by `LockDelay::max_resets`
+    Move,
+    // Only moving down to a row below the lowest one reached
+    Step,
+}
+
+// Time the tetromino can stay on the ground before it locks, in ticks.
+// Zero locks the tetromino as soon as it can't fall
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct LockDelay {
+    pub delay: u32,
+    pub reset: LockReset,
+    // Number of move resets per tetromino, None for unlimited
+    pub max_resets: Option<usize>,
+}
+
+impl LockDelay {
+    pub const NONE: LockDelay = LockDelay {
+        delay: 0,
+        reset: LockReset::Step,
+        max_resets: None,
+    };
+
+    pub fn duration(&self) -> Duration {
+        TICK * self.delay
+    }
+}
+
+// What `Action::Drop` does
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DropStyle {
+    // Tetromino falls with drop gravity until it locks
+    Fast,
+    // Tetromino moves to the bottom and locks at once
+    Hard,
+    // Tetromino moves to the bottom, lock delay applies
+    Sonic,
+}
+
+// How the level grows
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum LevelUp {
+    // Next level every N cleared lines
+    Lines(usize),
+    // Level grows by one for each tetromino and by number of cleared lines.
+    // Tetromino doesn't move the level past 99, 199 and so on, only cleared lines do
+    Tgm,
+}
+
+// Falling speed depending on level
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum GravityCurve {
+    // Same speed on all levels
+    Fixed(Gravity),
+    // (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds per row, starting at level 1
+    Guideline,
+    // Frames per row of NES Tetris, starting at level 0
+    Nes,
+    // Gravity table of TGM, levels 0 to 999
+    Tgm,
+}
+
+// NES frames per row for levels 0 to 29
+const NES_FRAMES_PER_ROW: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    1,
+];
+
+const NES_FRAMES_PER_SECOND: f64 = 60.0988;
+
+// TGM gravity in 1/256 G starting from the level
+const TGM_GRAVITY: [(usize, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+];
+
+impl GravityCurve {
+    pub fn gravity(&self, level: usize) -> Gravity {
+        match self {
+            GravityCurve::Fixed(gravity) => *gravity,
+            GravityCurve::Guideline => {
+                let level = level.max(1) as f64;
+                let seconds_per_row = (0.8 - (level - 1.0) * 0.007).powf(level - 1.0);
+                // Speed stops growing at 20G
+                Gravity::RowsPerSecond(
+                    (1.0 / seconds_per_row).min(Gravity::G(20.0).rows_per_second()),
+                )
+            }
+            GravityCurve::Nes => {
+                let frames = NES_FRAMES_PER_ROW[level.min(NES_FRAMES_PER_ROW.len() - 1)];
+                Gravity::RowsPerSecond(NES_FRAMES_PER_SECOND / frames as f64)
+            }
+            GravityCurve::Tgm => {
+                let (_, gravity) = TGM_GRAVITY
+                    .iter()
+                    .rev()
+                    .find(|(start, _)| level >= *start)
+                    .unwrap_or(&TGM_GRAVITY[0]);
+                Gravity::G(*gravity as f64 / 256.0)
+            }
+        }
+    }
+}
+
+/// Complete set of game rules. Mechanics depend on each other, i.e. 20G gravity is playable only
+/// with lock delay, so they are chosen together. Rules are serializable, so custom variants
+/// can be stored in JSON files, and there are bundled presets of well-known games.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RuleSet {
+    pub name: String,
+    pub randomizer: Randomizer,
+    pub rotation: RotationSystem,
+    pub lock_delay: LockDelay,
+    pub scoring: Scoring,
+    pub gravity: GravityCurve,
+    pub level_up: LevelUp,
+    pub start_level: usize,
+    pub drop: DropStyle,
+    // Falling speed after `Action::Drop` with `DropStyle::Fast`
+    pub drop_gravity: Gravity,
+    // Allows `Action::Hold`
+    pub hold: bool,
+    // Number of next tetrominos shown
+    pub preview_count: usize,
+    // Show where the tetromino lands
+    pub ghost: bool,
+    pub delays: Delays,
+    pub auto_repeat: AutoRepeatSettings,
+    pub input_policy: InputPolicy,
+}
+
+impl RuleSet {
+    // Names of bundled presets
+    pub const PRESETS: [&'static str; 4] = ["default", "guideline", "nes", "tgm"];
+
+    pub fn preset(name: &str) -> Option<RuleSet> {
+        match name.to_lowercase().as_str() {
+            "default" => Some(RuleSet::default()),
+            "guideline" => Some(RuleSet::guideline()),
+            "nes" => Some(RuleSet::nes()),
+            "tgm" => Some(RuleSet::tgm()),
+            _ => None,
+        }
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<RuleSet> {
+        serde_json::from_str(json)
+    }
+
+    // Preset by its name, otherwise rules read from the JSON file at the path
+    pub fn load(name_or_path: &str) -> Result<RuleSet, String> {
+        if let Some(rules) = RuleSet::preset(name_or_path) {
+            return Ok(rules);
+        }
+        let json = fs::read_to_string(name_or_path).map_err(|err| {
+            format!(
+                "{} is neither a preset ({}) nor a readable file: {}",
+                name_or_path,
+                RuleSet::PRESETS.join(", "),
+                err
+            )
+        })?;
+        RuleSet::from_json(&json)
+            .map_err(|err| format!("invalid rules file {}: {}", name_or_path, err))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("rules are always serializable")
+    }
+
+    // Modern games: 7-bag, SRS, hard drop, hold, ghost and five next tetrominos
+    pub fn guideline() -> Self {
+        RuleSet {
+            name: "guideline".to_string(),
+            randomizer: Randomizer::Bag7,
+            rotation: RotationSystem::Srs,
+            lock_delay: LockDelay {
+                delay: 50,
+                reset: LockReset::Move,
+                max_resets: Some(15),
+            },
+            scoring: Scoring::Guideline,
+            gravity: GravityCurve::Guideline,
+            level_up: LevelUp::Lines(10),
+            start_level: 1,
+            drop: DropStyle::Hard,
+            drop_gravity: Gravity::G(20.0),
+            hold: true,
+            preview_count: 5,
+            ghost: true,
+            delays: Delays {
+                are: 2,
+                line_clear_are: 2,
+                clear_animation: 20,
+            },
+            auto_repeat: AutoRepeatSettings {
+                das: 17,
+                arr: 3,
+                dcd: 0,
+                soft_drop_arr: 3,
+            },
+            input_policy: InputPolicy {
+                max_queue: Some(32),
+                irs: true,
+                ihs: true,
+            },
+        }
+    }
+
+    // NES Tetris: no hold, no kicks, no lock delay, long entry delay and slow auto-repeat
+    pub fn nes() -> Self {
+        RuleSet {
+            name: "nes".to_string(),
+            randomizer: Randomizer::Random,
+            rotation: RotationSystem::Classic,
+            lock_delay: LockDelay::NONE,
+            scoring: Scoring::Nes,
+            gravity: GravityCurve::Nes,
+            level_up: LevelUp::Lines(10),
+            start_level: 0,
+            drop: DropStyle::Fast,
+            drop_gravity: Gravity::G(0.5),
+            hold: false,
+            preview_count: 1,
+            ghost: false,
+            delays: Delays {
+                are: 17,
+                line_clear_are: 0,
+                clear_animation: 30,
+            },
+            auto_repeat: AutoRepeatSettings {
+                das: 27,
+                arr: 10,
+                dcd: 0,
+                soft_drop_arr: 3,
+            },
+            input_policy: InputPolicy {
+                max_queue: Some(32),
+                irs: false,
+                ihs: false,
+            },
+        }
+    }
+
+    // TGM: history randomizer, step reset lock delay and gravity reaching 20G at level 500
+    pub fn tgm() -> Self {
+        RuleSet {
+            name: "tgm".to_string(),
+            randomizer: Randomizer::Tgm,
+            rotation: RotationSystem::Tgm,
+            lock_delay: LockDelay {
+                delay: 50,
+                reset: LockReset::Step,
+                max_resets: None,
+            },
+            scoring: Scoring::Tgm,
+            gravity: GravityCurve::Tgm,
+            level_up: LevelUp::Tgm,
+            start_level: 0,
+            drop: DropStyle::Sonic,
+            drop_gravity: Gravity::G(20.0),
+            hold: false,
+            preview_count: 1,
+            ghost: true,
+            delays: Delays {
+                are: 50,
+                line_clear_are: 50,
+                clear_animation: 68,
+            },
+            auto_repeat: AutoRepeatSettings {
+                das: 27,
+                arr: 2,
+                dcd: 0,
+                soft_drop_arr: 2,
+            },
+            input_policy: InputPolicy {
+                max_queue: Some(32),
+                irs: true,
+                ihs: false,
+            },
+        }
+    }
+}
+
+// Rules of this crate before rule sets appeared: classic rotation without lock delay,
+// soft drop after `Action::Drop` and constant speed
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            name: "default".to_string(),
+            randomizer: Randomizer::Random,
+            rotation: RotationSystem::Classic,
+            lock_delay: LockDelay::NONE,
+            scoring: Scoring::Guideline,
+            gravity: GravityCurve::Fixed(Gravity::from_ticks(1, 100)),
+            level_up: LevelUp::Lines(10),
+            start_level: 1,
+            drop: DropStyle::Fast,
+            drop_gravity: Gravity::from_ticks(1, 10),
+            hold: false,
+            preview_count: 1,
+            ghost: false,
+            delays: Delays::default(),
+            auto_repeat: AutoRepeatSettings::default(),
+            input_policy: InputPolicy::default(),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    clock::{Clock, MonotonicClock, TICK},
+    coop::CoopTetris,
+    game_mode::{ModeInput, SinglePlayer},
+    match_series::{MatchInput, MatchSeries},
+    state::{CoopState, MatchSeriesState, SinglePlayerState, TetrisPairState, TetrisState},
+    tetris::{Action, Tetris},
+    tetris_pair::{PlayerSide, TetrisPair},
+};
+
+/// Game which can be driven by `GameRunner`
+pub trait Game: Send + 'static {
+    type Input: Send + 'static;
+    type State: Clone + Send + 'static;
+    fn handle_input(&mut self, input: Self::Input);
+    // Advance the game by the elapsed time. Returns true if the state should be published
+    fn advance(&mut self, elapsed: Duration) -> bool;
+    fn state(&self) -> Self::State;
+}
+
+impl Game for Tetris {
+    type Input = Action;
+    type State = TetrisState;
+    fn handle_input(&mut self, input: Action) {
+        self.add_action(input);
+    }
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        !Tetris::advance(self, elapsed).is_empty()
+    }
+    fn state(&self) -> TetrisState {
+        self.get_state()
+    }
+}
+
+impl Game for SinglePlayer {
+    type Input = ModeInput;
+    type State = SinglePlayerState;
+    fn handle_input(&mut self, input: ModeInput) {
+        match input {
+            ModeInput::Action(action) => {
+                self.add_action(action);
+            }
+            ModeInput::Key(event) => self.key_event(event),
+            ModeInput::Command(command) => self.command(command),
+        }
+    }
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        let finished = self.result().is_some();
+        let tenths = self.time().as_millis() / 100;
+        let results = SinglePlayer::advance(self, elapsed);
+        // Running timer is shown with tenths of a second
+        !results.is_empty()
+            || self.result().is_some() != finished
+            || self.time().as_millis() / 100 != tenths
+    }
+    fn state(&self) -> SinglePlayerState {
+        self.get_state()
+    }
+}
+
+impl Game for TetrisPair {
+    type Input = (PlayerSide, Action);
+    type State = TetrisPairState;
+    fn handle_input(&mut self, (player, action): (PlayerSide, Action)) {
+        self.add_player_action(player, action);
+    }
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        let (player, opponent) = TetrisPair::advance(self, elapsed);
+        !player.is_empty() || !opponent.is_empty()
+    }
+    fn state(&self) -> TetrisPairState {
+        self.get_state()
+    }
+}
+
+impl Game for MatchSeries {
+    type Input = MatchInput;
+    type State = MatchSeriesState;
+    fn handle_input(&mut self, input: MatchInput) {
+        match input {
+            MatchInput::Action(player, action) => {
+                self.add_player_action(player, action);
+            }
+            MatchInput::Key(player, event) => self.player_key_event(player, event),
+            MatchInput::Command(player, command) => self.command(player, command),
+        }
+    }
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        let (player, opponent) = MatchSeries::advance(self, elapsed);
+        !player.is_empty() || !opponent.is_empty() || self.take_changed()
+    }
+    fn state(&self) -> MatchSeriesState {
+        self.get_state()
+    }
+}
+
+impl Game for CoopTetris {
+    type Input = (PlayerSide, Action);
+    type State = CoopState;
+    fn handle_input(&mut self, (player, action): (PlayerSide, Action)) {
+        self.add_player_action(player, action);
+    }
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        let (player, opponent) = CoopTetris::advance(self, elapsed);
+        !player.is_empty() || !opponent.is_empty()
+    }
+    fn state(&self) -> CoopState {
+        self.get_state()
+    }
+}
+
+/// Source of game inputs polled by the runner each tick
+pub trait InputSource<T>: Send {
+    // Return next available input without blocking, None if there is no input now
+    fn try_next(&mut self) -> Option<T>;
+}
+
+impl<T: Send> InputSource<T> for mpsc::Receiver<T> {
+    fn try_next(&mut self) -> Option<T> {
+        self.try_recv().ok()
+    }
+}
+
+// Any closure can be a source, i.e. `move || rx.try_recv().ok()` for other channel types
+impl<T, F: FnMut() -> Option<T> + Send> InputSource<T> for F {
+    fn try_next(&mut self) -> Option<T> {
+        self()
+    }
+}
+
+/// Receiver of game states published by the runner
+pub trait StateSink<S>: Send {
+    // Returns false if the receiving side is gone. The runner stops then
+    fn send(&mut self, state: S) -> bool;
+}
+
+impl<S: Send> StateSink<S> for mpsc::Sender<S> {
+    fn send(&mut self, state: S) -> bool {
+        mpsc::Sender::send(self, state).is_ok()
+    }
+}
+
+impl<S, F: FnMut(S) -> bool + Send> StateSink<S> for F {
+    fn send(&mut self, state: S) -> bool {
+        self(state)
+    }
+}
+
+/// Fixed timestep loop driving a `Game`.
+/// Each tick the runner passes all inputs available from the sources to the game,
+/// advances it by the tick duration and publishes the state to all sinks if the game reports a change.
+/// Ticks are scheduled by absolute time, so sleep inaccuracy doesn't accumulate.
+/// When the loop falls behind, missed ticks are run back to back, but not more than
+/// `max_catch_up` at once; the rest of the backlog is dropped.
+pub struct GameRunner<G: Game> {
+    game: G,
+    tick: Duration,
+    max_catch_up: usize,
+    clock: Box<dyn Clock>,
+    inputs: Vec<Box<dyn InputSource<G::Input>>>,
+    sinks: Vec<Box<dyn StateSink<G::State>>>,
+}
+
+impl<G: Game> GameRunner<G> {
+    pub fn new(game: G) -> Self {
+        GameRunner {
+            game,
+            tick: TICK,
+            max_catch_up: 10,
+            clock: Box::new(MonotonicClock::new()),
+            inputs: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    pub fn with_tick(mut self, tick: Duration) -> Self {
+        assert!(!tick.is_zero(), "tick must be longer than zero");
+        self.tick = tick;
+        self
+    }
+
+    pub fn with_max_catch_up(mut self, ticks: usize) -> Self {
+        self.max_catch_up = ticks.max(1);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    pub fn with_input(mut self, source: impl InputSource<G::Input> + 'static) -> Self {
+        self.inputs.push(Box::new(source));
+        self
+    }
+
+    pub fn with_sink(mut self, sink: impl StateSink<G::State> + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    // Run the loop on the current thread until `stop` is set or a sink is disconnected.
+    // Returns the game in its final state
+    pub fn run(mut self, stop: &AtomicBool) -> G {
+        let mut next_tick = self.clock.now();
+        while !stop.load(Ordering::Relaxed) {
+            let now = self.clock.now();
+            let mut ticks = 0;
+            while now >= next_tick && ticks < self.max_catch_up {
+                if !self.tick() {
+                    return self.game;
+                }
+                next_tick += self.tick;
+                ticks += 1;
+            }
+            // Too far behind, drop the backlog instead of running the game in fast-forward
+            if now >= next_tick {
+                next_tick = now + self.tick;
+            }
+            let now = self.clock.now();
+            if next_tick > now {
+                self.clock.sleep(next_tick - now);
+            }
+        }
+        self.game
+    }
+
+    // Run the loop on a new thread
+    pub fn spawn(self) -> RunnerHandle<G> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || self.run(&stop))
+        };
+        RunnerHandle { stop, thread }
+    }
+
+    // Run single tick. Returns false if the runner should stop
+    fn tick(&mut self) -> bool {
+        for source in &mut self.inputs {
+            while let Some(input) = source.try_next() {
+                self.game.handle_input(input);
+            }
+        }
+        if self.game.advance(self.tick) {
+            let state = self.game.state();
+            for sink in &mut self.sinks {
+                if !sink.send(state.clone()) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Handle of the runner started with `GameRunner::spawn`
+pub struct RunnerHandle<G> {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<G>,
+}
+
+impl<G> RunnerHandle<G> {
+    // Ask the runner to stop after the current tick
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    // Wait for the runner to stop and get the game back
+    pub fn join(self) -> G {
+        self.thread.join().expect("game runner thread panicked")
+    }
+
+    pub fn stop_and_join(self) -> G {
+        self.stop();
+        self.join()
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rules::LevelUp,
+    tetris::{Lock, TSpin},
+};
+
+// How points are given for cleared lines and drops
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Scoring {
+    // 100/300/500/800 points multiplied by level, more for T-spins, back-to-back
+    // and combo bonuses, 1 point per soft dropped row and 2 per hard dropped row
+    Guideline,
+    // 40/100/300/1200 points multiplied by level + 1, 1 point per soft dropped row
+    Nes,
+    // (level + lines) / 4 plus soft dropped rows, multiplied by lines and combo.
+    // Clearing the whole well multiplies it by 4
+    Tgm,
+}
+
+// Progress of one game
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Score {
+    pub score: usize,
+    pub lines: usize,
+    pub level: usize,
+    // Locked tetrominos
+    pub pieces: usize,
+}
+
+// Counts score, lines and level by the rules
+#[derive(Debug, Clone)]
+pub(crate) struct ScoreCounter {
+    scoring: Scoring,
+    level_up: LevelUp,
+    start_level: usize,
+    // Level doesn't grow past it
+    max_level: Option<usize>,
+    score: Score,
+    // Consecutive locks which cleared lines
+    combo: usize,
+    // TGM combo multiplier, grows faster with multi-line clears
+    tgm_combo: usize,
+    // Last clear was a tetris or a T-spin
+    back_to_back: bool,
+    // Rows soft dropped by the current tetromino
+    soft_drop_rows: usize,
+}
+
+const MAX_TGM_LEVEL: usize = 999;
+
+impl ScoreCounter {
+    pub fn new(scoring: Scoring, level_up: LevelUp, start_level: usize) -> Self {
+        ScoreCounter {
+            scoring,
+            level_up,
+            start_level,
+            max_level: None,
+            score: Score {
+                level: start_level,
+                ..Score::default()
+            },
+            combo: 0,
+            tgm_combo: 1,
+            back_to_back: false,
+            soft_drop_rows: 0,
+        }
+    }
+
+    pub fn score(&self) -> Score {
+        self.score
+    }
+
+    pub fn reset(&mut self) {
+        let max_level = self.max_level;
+        *self = ScoreCounter::new(self.scoring, self.level_up, self.start_level);
+        self.max_level = max_level;
+    }
+
+    pub fn set_max_level(&mut self, max_level: Option<usize>) {
+        self.max_level = max_level;
+    }
+
+    // New tetromino appeared. Returns true if the level changed
+    pub fn spawn(&mut self) -> bool {
+        self.soft_drop_rows = 0;
+        if self.level_up == LevelUp::Tgm
+            && self.score.level % 100 != 99
+            && self.score.level < MAX_TGM_LEVEL - 1
+            && self
+                .max_level
+                .is_none_or(|max_level| self.score.level < max_level)
+        {
+            self.score.level += 1;
+            return true;
+        }
+        false
+    }
+
+    pub fn soft_drop(&mut self, rows: usize) {
+        self.soft_drop_rows += rows;
+        match self.scoring {
+            Scoring::Guideline | Scoring::Nes => self.score.score += rows,
+            Scoring::Tgm => {}
+        }
+    }
+
+    pub fn hard_drop(&mut self, rows: usize) {
+        match self.scoring {
+            Scoring::Guideline => self.score.score += rows * 2,
+            Scoring::Nes => {}
+            // Sonic drop counts as soft drop
+            Scoring::Tgm => self.soft_drop_rows += rows,
+        }
+    }
+
+    // Tetromino locked. Returns true if the level changed
+    pub fn lock(&mut self, lock: &Lock) -> bool {
+        let Lock {
+            lines,
+            tspin,
+            perfect_clear,
+            ..
+        } = *lock;
+        self.score.pieces += 1;
+        let level = self.score.level;
+        match self.scoring {
+            Scoring::Guideline => {
+                let lines = lines.min(4);
+                let points = match tspin {
+                    TSpin::None => [0, 100, 300, 500, 800][lines],
+                    TSpin::Mini => [100, 200, 400, 400, 400][lines],
+                    TSpin::Full => [400, 800, 1200, 1600, 1600][lines],
+                };
+                let mut points = points * level.max(1);
+                // T-spins without lines don't break back-to-back chain
+                if lines > 0 {
+                    let difficult = lines >= 4 || tspin != TSpin::None;
+                    if difficult && self.back_to_back {
+                        points = points * 3 / 2;
+                    }
+                    self.back_to_back = difficult;
+                    points += 50 * self.combo * level.max(1);
+                }
+                self.score.score += points;
+            }
+            Scoring::Nes => {
+                self.score.score += [0, 40, 100, 300, 1200][lines.min(4)] * (level + 1);
+            }
+            Scoring::Tgm => {
+                if lines > 0 {
+                    self.tgm_combo += lines * 2 - 2;
+                    let bravo = if perfect_clear { 4 } else { 1 };
+                    self.score.score += ((level + lines).div_ceil(4) + self.soft_drop_rows)
+                        * lines
+                        * self.tgm_combo
+                        * bravo;
+                } else {
+                    self.tgm_combo = 1;
+                }
+            }
+        }
+        if lines > 0 {
+            self.combo += 1;
+        } else {
+            self.combo = 0;
+        }
+
+        self.score.lines += lines;
+        let level = match self.level_up {
+            LevelUp::Lines(lines_per_level) => {
+                self.start_level + self.score.lines / lines_per_level.max(1)
+            }
+            LevelUp::Tgm => (self.score.level + lines).min(MAX_TGM_LEVEL),
+        };
+        let level = self
+            .max_level
+            .map_or(level, |max_level| level.min(max_level));
+        let changed = level != self.score.level;
+        self.score.level = level;
+        changed
+    }
+}
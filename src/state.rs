@@ -1,22 +1,132 @@
 use serde::{Deserialize, Serialize};
 
-use crate::Field;
+use std::time::Duration;
+
+use crate::{Field, GameMode, ModeResult, PlayerSide, Score};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TetrisState {
     pub well: Field,
     pub preview: Field,
+    // Held tetromino, None if the rules don't allow hold
+    pub hold: Option<Field>,
+    pub score: Score,
+    // Lines with garbage left in the well
+    pub garbage_lines: usize,
     pub game_over: bool,
+    pub paused: bool,
+    // Countdown number shown before the game starts
+    pub countdown: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SinglePlayerState {
+    pub tetris: TetrisState,
+    pub mode: GameMode,
+    // Game time, without countdown and pauses
+    pub time: Duration,
+    // Set when the game is finished
+    pub result: Option<ModeResult>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct TetrisPairState {
     pub player: TetrisState,
     pub opponent: TetrisState,
+    pub paused_by_player: bool,
+    pub paused_by_opponent: bool,
 }
 
 impl TetrisPairState {
     pub fn swap(&mut self) {
         std::mem::swap(&mut self.player, &mut self.opponent);
+        std::mem::swap(&mut self.paused_by_player, &mut self.paused_by_opponent);
     }
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum MatchPhase {
+    // Round is in progress
+    Playing,
+    // Round is finished, next round starts after a short break
+    RoundOver,
+    // One of the players won the series, waiting for rematch
+    MatchOver,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum RoundOutcome {
+    Winner(PlayerSide),
+    // Both players lost on the same step, the round is replayed
+    Draw,
+}
+
+impl RoundOutcome {
+    pub fn swap(&self) -> RoundOutcome {
+        match self {
+            RoundOutcome::Winner(side) => RoundOutcome::Winner(side.other()),
+            RoundOutcome::Draw => RoundOutcome::Draw,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub outcome: RoundOutcome,
+    // Seed of the piece sequence shared by both players, allows to replay the round
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MatchSeriesState {
+    pub pair: TetrisPairState,
+    pub best_of: usize,
+    pub round: usize,
+    pub player_wins: usize,
+    pub opponent_wins: usize,
+    pub phase: MatchPhase,
+    pub rounds: Vec<RoundResult>,
+    pub rematch_player: bool,
+    pub rematch_opponent: bool,
+}
+
+impl MatchSeriesState {
+    pub fn swap(&mut self) {
+        self.pair.swap();
+        std::mem::swap(&mut self.player_wins, &mut self.opponent_wins);
+        std::mem::swap(&mut self.rematch_player, &mut self.rematch_opponent);
+        for round in &mut self.rounds {
+            round.outcome = round.outcome.swap();
+        }
+    }
+
+    pub fn last_round(&self) -> Option<RoundOutcome> {
+        self.rounds.last().map(|round| round.outcome)
+    }
+
+    // Side which won the series, if it's over
+    pub fn winner(&self) -> Option<PlayerSide> {
+        if self.phase != MatchPhase::MatchOver {
+            return None;
+        }
+        if self.player_wins > self.opponent_wins {
+            Some(PlayerSide::Player)
+        } else {
+            Some(PlayerSide::Opponent)
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CoopState {
+    // Shared well with both active tetrominos drawn
+    pub well: Field,
+    pub player_preview: Field,
+    pub opponent_preview: Field,
+    // Hold boxes, None if hold is not allowed
+    pub player_hold: Option<Field>,
+    pub opponent_hold: Option<Field>,
+    // Score, lines and level of both players together
+    pub score: Score,
+    pub game_over: bool,
+}
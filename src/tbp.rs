@@ -0,0 +1,668 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bot::{Bot, BotMove, BotPacing},
+    path::PathPlanner,
+    placement::{cleared_rows, Placement},
+    rotation::spawn_x,
+    rules::RuleSet,
+    tetris::{CellType, Field, Rotation, TSpin, Tetris, Tetromino, TetrominoType},
+};
+
+// Board size of Tetris Bot Protocol messages
+pub const TBP_COLS: usize = 10;
+pub const TBP_ROWS: usize = 40;
+
+// Time the bot is given to quit before it's killed
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+const QUIT_POLL: Duration = Duration::from_millis(10);
+
+/// Message of the Tetris Bot Protocol sent by the game (frontend) to the bot, one JSON object per line
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TbpFrontendMessage {
+    // Game rules, the bot answers with ready or error. Only the standard rules are used
+    Rules,
+    Start(TbpStart),
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        tbp_move: TbpMove,
+    },
+    NewPiece {
+        piece: TetrominoType,
+    },
+    Quit,
+}
+
+/// Message of the Tetris Bot Protocol sent by the bot to the game
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TbpBotMessage {
+    Info {
+        name: String,
+        version: String,
+        author: String,
+        features: Vec<String>,
+    },
+    Ready,
+    Error {
+        reason: String,
+    },
+    // Moves from the best one
+    Suggestion {
+        moves: Vec<TbpMove>,
+    },
+}
+
+/// Position the bot starts to play from
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TbpStart {
+    pub hold: Option<TetrominoType>,
+    // Current tetromino followed by the preview
+    pub queue: Vec<TetrominoType>,
+    pub combo: u32,
+    pub back_to_back: bool,
+    // `TBP_ROWS` rows of `TBP_COLS` cells from the bottom, a cell is a tetromino letter,
+    // 'G' for garbage or null if it's empty
+    pub board: Vec<Vec<Option<char>>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct TbpMove {
+    pub location: TbpLocation,
+    pub spin: TbpSpin,
+}
+
+/// Where the tetromino is locked: its rotation center in the SRS rotation, from the bottom left
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct TbpLocation {
+    #[serde(rename = "type")]
+    pub tetromino_type: TetrominoType,
+    pub orientation: TbpOrientation,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TbpOrientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TbpSpin {
+    None,
+    Mini,
+    Full,
+}
+
+impl From<TSpin> for TbpSpin {
+    fn from(tspin: TSpin) -> Self {
+        match tspin {
+            TSpin::None => TbpSpin::None,
+            TSpin::Mini => TbpSpin::Mini,
+            TSpin::Full => TbpSpin::Full,
+        }
+    }
+}
+
+impl From<TbpSpin> for TSpin {
+    fn from(spin: TbpSpin) -> Self {
+        match spin {
+            TbpSpin::None => TSpin::None,
+            TbpSpin::Mini => TSpin::Mini,
+            TbpSpin::Full => TSpin::Full,
+        }
+    }
+}
+
+impl TbpOrientation {
+    fn from_rotation(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::R0 => TbpOrientation::North,
+            Rotation::R90 => TbpOrientation::East,
+            Rotation::R180 => TbpOrientation::South,
+            Rotation::R270 => TbpOrientation::West,
+        }
+    }
+
+    fn rotation(&self) -> Rotation {
+        match self {
+            TbpOrientation::North => Rotation::R0,
+            TbpOrientation::East => Rotation::R90,
+            TbpOrientation::South => Rotation::R180,
+            TbpOrientation::West => Rotation::R270,
+        }
+    }
+
+    // Clockwise quarter turns from north
+    fn turns(&self) -> usize {
+        match self {
+            TbpOrientation::North => 0,
+            TbpOrientation::East => 1,
+            TbpOrientation::South => 2,
+            TbpOrientation::West => 3,
+        }
+    }
+}
+
+// Cells around the rotation center, y goes up
+fn center_offsets(
+    tetromino_type: TetrominoType,
+    orientation: TbpOrientation,
+) -> [(isize, isize); 4] {
+    let mut offsets = match tetromino_type {
+        TetrominoType::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        TetrominoType::J => [(-1, 1), (-1, 0), (0, 0), (1, 0)],
+        TetrominoType::L => [(1, 1), (-1, 0), (0, 0), (1, 0)],
+        TetrominoType::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        TetrominoType::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        TetrominoType::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        TetrominoType::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+    };
+    for _ in 0..orientation.turns() {
+        for offset in &mut offsets {
+            *offset = (offset.1, -offset.0);
+        }
+    }
+    offsets
+}
+
+impl TbpLocation {
+    // Location of the tetromino in the well with the given number of rows
+    pub fn from_tetromino(tetromino: &Tetromino, rows: usize) -> Self {
+        let tetromino_type = tetromino.tetromino_type();
+        let orientation = TbpOrientation::from_rotation(tetromino.rotation());
+        let cells = tetromino.cells();
+        let offsets = center_offsets(tetromino_type, orientation);
+        // Both shapes are aligned by their lower left corner
+        let left = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let bottom = cells
+            .iter()
+            .map(|&(_, y)| rows as isize - 1 - y)
+            .min()
+            .unwrap_or(0);
+        let offset_left = offsets.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let offset_bottom = offsets.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        TbpLocation {
+            tetromino_type,
+            orientation,
+            x: (left - offset_left) as i32,
+            y: (bottom - offset_bottom) as i32,
+        }
+    }
+
+    // Tetromino in the well with the given number of rows, None if it's outside
+    pub fn to_tetromino(&self, rows: usize) -> Option<Tetromino> {
+        let cells: Vec<(isize, isize)> = center_offsets(self.tetromino_type, self.orientation)
+            .iter()
+            .map(|&(x, y)| {
+                let x = self.x as isize + x;
+                let y = rows as isize - 1 - (self.y as isize + y);
+                (x, y)
+            })
+            .collect();
+        if cells.iter().any(|&(x, y)| x < 0 || y < 0) {
+            return None;
+        }
+        let left = cells.iter().map(|&(x, _)| x).min()?;
+        let top = cells.iter().map(|&(_, y)| y).min()?;
+        let tetromino = Tetromino::new(self.tetromino_type, self.orientation.rotation(), left, top);
+        let mut expected = cells.clone();
+        expected.sort();
+        let mut actual = tetromino.cells();
+        actual.sort();
+        if actual == expected {
+            Some(tetromino)
+        } else {
+            // Rotation system where the orientation has another shape
+            Tetromino::from_cells(self.tetromino_type, &cells)
+        }
+    }
+}
+
+// Position as the bot sees it
+#[derive(Debug, Clone, PartialEq)]
+struct TbpState {
+    field: Field,
+    hold: Option<TetrominoType>,
+    queue: Vec<TetrominoType>,
+}
+
+impl TbpState {
+    // Current tetromino and the preview of the game. None if there is no current tetromino
+    fn from_game(game: &Tetris) -> Option<Self> {
+        let current = (*game.get_current())?;
+        let mut queue = vec![current.tetromino_type()];
+        queue.extend(game.get_next_queue().iter().take(game.preview_count()));
+        let hold = if game.rules().hold {
+            game.get_hold()
+        } else {
+            None
+        };
+        Some(TbpState {
+            field: game.get_field().clone(),
+            hold,
+            queue,
+        })
+    }
+
+    fn from_start(start: &TbpStart) -> Self {
+        let mut field = Field::new(TBP_COLS, TBP_ROWS);
+        for (row, cells) in start.board.iter().take(TBP_ROWS).enumerate() {
+            for (x, cell) in cells.iter().take(TBP_COLS).enumerate() {
+                let cell_type = cell
+                    .and_then(CellType::from_char)
+                    .unwrap_or(CellType::Empty);
+                field.set_cell(x, TBP_ROWS - 1 - row, cell_type);
+            }
+        }
+        TbpState {
+            field,
+            hold: start.hold,
+            queue: start.queue.clone(),
+        }
+    }
+
+    fn to_start(&self) -> TbpStart {
+        let rows = self.field.rows();
+        let board = (0..TBP_ROWS)
+            .map(|row| {
+                (0..TBP_COLS)
+                    .map(|x| {
+                        let y = (rows > row).then(|| rows - 1 - row)?;
+                        if x >= self.field.cols() {
+                            return None;
+                        }
+                        match self.field.get_cell(x, y) {
+                            CellType::Empty | CellType::Ghost => None,
+                            CellType::Blasted => Some(CellType::Garbage.to_char()),
+                            cell_type => Some(cell_type.to_char()),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        TbpStart {
+            hold: self.hold,
+            queue: self.queue.clone(),
+            combo: 0,
+            back_to_back: false,
+            board,
+        }
+    }
+
+    // Tetromino type played after hold, if the move holds
+    fn held_type(&self) -> Option<TetrominoType> {
+        self.hold.or_else(|| self.queue.get(1).copied())
+    }
+
+    // Play the move the way the bot does: hold if it's another tetromino, lock it and clear lines.
+    // Returns false if the move can't be played
+    fn play(&mut self, tbp_move: &TbpMove) -> bool {
+        let tetromino_type = tbp_move.location.tetromino_type;
+        let Some(&current) = self.queue.first() else {
+            return false;
+        };
+        if current != tetromino_type {
+            if self.held_type() != Some(tetromino_type) {
+                return false;
+            }
+            match self.hold {
+                Some(held) => self.queue[0] = held,
+                None => {
+                    self.queue.remove(0);
+                }
+            }
+            self.hold = Some(current);
+        }
+        let Some(tetromino) = tbp_move.location.to_tetromino(self.field.rows()) else {
+            return false;
+        };
+        self.queue.remove(0);
+        tetromino.draw(&mut self.field);
+        if self.field.blast_full_lines() {
+            self.field.remove_blasted_lines();
+        }
+        true
+    }
+}
+
+/// External bot speaking the Tetris Bot Protocol over stdin and stdout of its process.
+/// It's told about each tetromino played and each new one in the preview, when the well changes
+/// otherwise, i.e. garbage comes, it starts again from the new position. Suggested moves are
+/// played only if the tetromino can get there with the rules of the game.
+pub struct TbpBot {
+    process: Child,
+    stdin: ChildStdin,
+    messages: Receiver<TbpBotMessage>,
+    name: String,
+    // Time to wait for a suggestion
+    timeout: Duration,
+    pacing: BotPacing,
+    started: bool,
+    // Suggest requests the bot hasn't answered yet, including the ones which timed out
+    unanswered: usize,
+    // Position of the bot after the last played move, None if it has to start again
+    expected: Option<TbpState>,
+}
+
+impl TbpBot {
+    // Run the bot process and wait until it's ready
+    pub fn spawn(mut command: Command) -> io::Result<TbpBot> {
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = process.stdin.take().expect("stdin is piped");
+        let stdout = process.stdout.take().expect("stdout is piped");
+        let (tx, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                // Messages of later protocol versions are skipped
+                if let Ok(message) = serde_json::from_str(&line) {
+                    if tx.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        let mut bot = TbpBot {
+            process,
+            stdin,
+            messages,
+            name: String::new(),
+            timeout: Duration::from_secs(1),
+            pacing: BotPacing::INSTANT,
+            started: false,
+            unanswered: 0,
+            expected: None,
+        };
+        let handshake = Duration::from_secs(10);
+        match bot.receive(handshake)? {
+            TbpBotMessage::Info { name, .. } => bot.name = name,
+            message => return Err(unexpected(&message)),
+        }
+        bot.send(&TbpFrontendMessage::Rules)?;
+        match bot.receive(handshake)? {
+            TbpBotMessage::Ready => Ok(bot),
+            TbpBotMessage::Error { reason } => Err(io::Error::other(reason)),
+            message => Err(unexpected(&message)),
+        }
+    }
+
+    // Time to wait for each suggestion, the tetromino falls on its own if there is none by then
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_pacing(mut self, pacing: BotPacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    // Name the bot introduced itself with
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&mut self, message: &TbpFrontendMessage) -> io::Result<()> {
+        let line = serde_json::to_string(message)?;
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()
+    }
+
+    fn receive(&mut self, timeout: Duration) -> io::Result<TbpBotMessage> {
+        self.messages
+            .recv_timeout(timeout)
+            .map_err(|err| match err {
+                RecvTimeoutError::Timeout => io::Error::from(io::ErrorKind::TimedOut),
+                RecvTimeoutError::Disconnected => io::Error::from(io::ErrorKind::BrokenPipe),
+            })
+    }
+
+    // Bring the bot to the position of the game, with as few messages as possible
+    fn sync(&mut self, state: &TbpState) -> io::Result<()> {
+        if let Some(expected) = self.expected.take() {
+            if expected.field == state.field
+                && expected.hold == state.hold
+                && state.queue.starts_with(&expected.queue)
+            {
+                for &piece in &state.queue[expected.queue.len()..] {
+                    self.send(&TbpFrontendMessage::NewPiece { piece })?;
+                }
+                return Ok(());
+            }
+        }
+        if self.started {
+            self.send(&TbpFrontendMessage::Stop)?;
+        }
+        self.send(&TbpFrontendMessage::Start(state.to_start()))?;
+        self.started = true;
+        Ok(())
+    }
+
+    fn suggest(&mut self, state: &TbpState) -> io::Result<Vec<TbpMove>> {
+        self.sync(state)?;
+        self.send(&TbpFrontendMessage::Suggest)?;
+        self.unanswered += 1;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if let TbpBotMessage::Suggestion { moves } = self.receive(timeout)? {
+                self.unanswered -= 1;
+                // The bot answers in order, late suggestions to the timed out requests come first
+                if self.unanswered == 0 {
+                    return Ok(moves);
+                }
+            }
+        }
+    }
+
+    // First suggested move the tetromino can be played with in the game
+    fn playable(game: &Tetris, state: &TbpState, moves: &[TbpMove]) -> Option<(TbpMove, BotMove)> {
+        let current = (*game.get_current())?;
+        let field = game.get_field();
+        let system = game.rules().rotation;
+        let planner = PathPlanner::new(game.rules());
+        moves.iter().find_map(|tbp_move| {
+            let tetromino = tbp_move.location.to_tetromino(field.rows())?;
+            let tetromino_type = tetromino.tetromino_type();
+            let hold = tetromino_type != current.tetromino_type();
+            let start = if hold {
+                if !game.can_hold() || state.held_type() != Some(tetromino_type) {
+                    return None;
+                }
+                let x = spawn_x(system, tetromino_type, field.cols());
+                Tetromino::new(tetromino_type, Rotation::R0, x, 0)
+            } else {
+                current
+            };
+            // The spin the bot claims is checked, it needs the tetromino rotated into place last
+            let tspin = match tbp_move.spin {
+                TbpSpin::None => TSpin::None,
+                TbpSpin::Mini | TbpSpin::Full => tetromino.tspin(field),
+            };
+            let placement = Placement {
+                tetromino,
+                tspin,
+                lines: cleared_rows(field, &tetromino).len(),
+            };
+            planner.find_placement(field, start, &placement)?;
+            Some((*tbp_move, BotMove { hold, placement }))
+        })
+    }
+}
+
+impl Bot for TbpBot {
+    fn choose(&mut self, game: &Tetris) -> Option<BotMove> {
+        let state = TbpState::from_game(game)?;
+        // Bots may suggest moves the rules don't allow, i.e. hold. Then they are asked again
+        // to place the current tetromino alone
+        let current_only = TbpState {
+            field: state.field.clone(),
+            hold: None,
+            queue: state.queue[..1].to_vec(),
+        };
+        for state in [state, current_only] {
+            // Without a suggestion in time the tetromino falls on its own,
+            // the late one is skipped by the next request
+            let Ok(moves) = self.suggest(&state) else {
+                self.expected = None;
+                return None;
+            };
+            let Some((tbp_move, bot_move)) = TbpBot::playable(game, &state, &moves) else {
+                self.expected = None;
+                continue;
+            };
+            let mut expected = state;
+            if !expected.play(&tbp_move)
+                || self.send(&TbpFrontendMessage::Play { tbp_move }).is_err()
+            {
+                return None;
+            }
+            self.expected = Some(expected);
+            return Some(bot_move);
+        }
+        None
+    }
+
+    fn pacing(&self) -> BotPacing {
+        self.pacing
+    }
+}
+
+impl Drop for TbpBot {
+    fn drop(&mut self) {
+        // The bot is killed if it doesn't quit in time
+        if self.send(&TbpFrontendMessage::Quit).is_ok() {
+            let deadline = Instant::now() + QUIT_TIMEOUT;
+            while Instant::now() < deadline {
+                match self.process.try_wait() {
+                    Ok(Some(_)) => return,
+                    Ok(None) => thread::sleep(QUIT_POLL),
+                    Err(_) => break,
+                }
+            }
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+fn unexpected(message: &TbpBotMessage) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected message from the bot: {:?}", message),
+    )
+}
+
+/// Serves a `Bot` over the Tetris Bot Protocol, so that other games can play with it.
+/// Positions are played in a well of `TBP_COLS` x `TBP_ROWS` with the given rules.
+pub struct TbpServer {
+    bot: Box<dyn Bot>,
+    name: String,
+    author: String,
+    rules: RuleSet,
+}
+
+impl TbpServer {
+    pub fn new(bot: Box<dyn Bot>, name: &str, author: &str) -> Self {
+        TbpServer {
+            bot,
+            name: name.to_string(),
+            author: author.to_string(),
+            rules: RuleSet::guideline(),
+        }
+    }
+
+    // Rules of the game the bot is asked about, guideline by default
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // Answer the messages until quit or the end of input
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        let mut send = |message: &TbpBotMessage| -> io::Result<()> {
+            writeln!(output, "{}", serde_json::to_string(message)?)?;
+            output.flush()
+        };
+        send(&TbpBotMessage::Info {
+            name: self.name.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            author: self.author.clone(),
+            features: Vec::new(),
+        })?;
+        let mut state: Option<TbpState> = None;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Messages of later protocol versions are skipped
+            let Ok(message) = serde_json::from_str(&line) else {
+                continue;
+            };
+            match message {
+                TbpFrontendMessage::Rules => send(&TbpBotMessage::Ready)?,
+                TbpFrontendMessage::Start(start) => state = Some(TbpState::from_start(&start)),
+                TbpFrontendMessage::Stop => state = None,
+                TbpFrontendMessage::Suggest => {
+                    let moves = state
+                        .as_ref()
+                        .and_then(|state| self.suggest(state))
+                        .into_iter()
+                        .collect();
+                    send(&TbpBotMessage::Suggestion { moves })?;
+                }
+                TbpFrontendMessage::Play { tbp_move } => {
+                    if let Some(state) = &mut state {
+                        state.play(&tbp_move);
+                    }
+                }
+                TbpFrontendMessage::NewPiece { piece } => {
+                    if let Some(state) = &mut state {
+                        state.queue.push(piece);
+                    }
+                }
+                TbpFrontendMessage::Quit => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn suggest(&mut self, state: &TbpState) -> Option<TbpMove> {
+        let mut game = Tetris::with_rules(TBP_COLS, TBP_ROWS, self.rules.clone());
+        game.set_start_field(Some(state.field.clone())).ok()?;
+        game.set_fixed_queue(Some(state.queue.clone()));
+        game.set_preview_count(state.queue.len().saturating_sub(1));
+        game.set_hold(state.hold);
+        if !game.place_next_tetromino() {
+            return None;
+        }
+        let bot_move = self.bot.choose(&game)?;
+        Some(TbpMove {
+            location: TbpLocation::from_tetromino(&bot_move.placement.tetromino, TBP_ROWS),
+            spin: bot_move.placement.tspin.into(),
+        })
+    }
+}
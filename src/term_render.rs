@@ -1,7 +1,12 @@
+use std::time::Duration;
+
 use crate::{
-    state::{TetrisPairState, TetrisState},
+    state::{
+        CoopState, MatchPhase, MatchSeriesState, RoundOutcome, SinglePlayerState, TetrisPairState,
+        TetrisState,
+    },
     tetris::CellType,
-    Field,
+    Field, GameMode, ModeResult, PlayerSide,
 };
 
 #[derive(Clone, PartialEq)]
@@ -38,7 +43,7 @@ pub trait TermRender {
 }
 
 // Make all lines in block the same width by padding with TermCell::Space
-pub fn pad_block_right(block: &mut Vec<Vec<TermCell>>, style: &impl TermStyle) {
+pub fn pad_block_right(block: &mut [Vec<TermCell>], style: &impl TermStyle) {
     // Requite that the width of TermCell::Space display is 1
     assert_eq!(style.width(&TermCell::Space), 1);
     // calculate width of each line of the block and the maximum width
@@ -80,6 +85,7 @@ impl TermStyle for PlainTermStyle {
         match cell {
             TermCell::FieldCell(CellType::Empty) => "  ",
             TermCell::FieldCell(CellType::Blasted) => "**",
+            TermCell::FieldCell(CellType::Ghost) => "::",
             TermCell::FieldCell(_) => "[]",
             TermCell::BorderVertical => "|",
             TermCell::BorderTopLeft => "+",
@@ -113,6 +119,8 @@ impl TermStyle for AnsiTermStyle {
         match cell {
             TermCell::FieldCell(CellType::Empty) => "\x1b[0m  ",
             TermCell::FieldCell(CellType::Blasted) => "\x1b[0;31m**",
+            TermCell::FieldCell(CellType::Ghost) => "\x1b[0;90m::",
+            TermCell::FieldCell(CellType::Garbage) => "\x1b[0;90m[]",
             TermCell::FieldCell(CellType::I) => "\x1b[0;34m[]",
             TermCell::FieldCell(CellType::J) => "\x1b[0;32m[]",
             TermCell::FieldCell(CellType::L) => "\x1b[0;33m[]",
@@ -163,11 +171,47 @@ impl TermRender for Field {
 pub struct WellField {
     field: Field,
     game_over: bool,
+    paused: bool,
+    countdown: Option<usize>,
 }
 
 impl WellField {
     pub fn new(field: Field, game_over: bool) -> Self {
-        Self { field, game_over }
+        Self {
+            field,
+            game_over,
+            paused: false,
+            countdown: None,
+        }
+    }
+
+    pub fn from_state(state: TetrisState) -> Self {
+        Self {
+            field: state.well,
+            game_over: state.game_over,
+            paused: state.paused,
+            countdown: state.countdown,
+        }
+    }
+
+    // Paused well is rendered empty, so that the player can't plan moves during pause
+    pub fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    pub fn with_countdown(mut self, countdown: Option<usize>) -> Self {
+        self.countdown = countdown;
+        self
+    }
+
+    // Replace middle line of the well with the message centered in it
+    fn put_message(&self, lines: &mut [Vec<TermCell>], text: &str, style: &impl TermStyle) {
+        let width = self.field.cols() * style.width(&TermCell::FieldCell(CellType::Empty));
+        let indent = width.saturating_sub(text.len()) / 2;
+        let middle = lines.len() / 2;
+        lines[middle] = vec![TermCell::Message(format!("{}{}", " ".repeat(indent), text))];
+        pad_block_right(lines, style);
     }
 }
 
@@ -175,14 +219,14 @@ impl TermRender for WellField {
     fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
         let mut lines = self.field.output(style);
         if self.game_over {
-            // Find middle line of the field
-            let middle = lines.len() / 2;
-            // Replace line with message
-            let message = TermCell::Message("     Game Over".to_string());
-            lines[middle] = vec![message];
-            // Add padding
-            // TODO: Center the message
-            pad_block_right(&mut lines, style);
+            self.put_message(&mut lines, "Game Over", style);
+        } else if self.paused {
+            for line in &mut lines {
+                line.fill(TermCell::FieldCell(CellType::Empty));
+            }
+            self.put_message(&mut lines, "PAUSED", style);
+        } else if let Some(count) = self.countdown {
+            self.put_message(&mut lines, &count.to_string(), style);
         }
 
         for line in &mut lines {
@@ -230,30 +274,72 @@ impl TermRender for PreviewField {
     }
 }
 
-pub struct GameFieldLeft {
-    well: WellField,
+// Tetrominos in the preview and hold boxes are not shown while the game is paused
+fn hide_if_paused(field: &Field, paused: bool) -> Field {
+    if paused {
+        Field::new(field.cols(), field.rows())
+    } else {
+        field.clone()
+    }
+}
+
+// Preview, hold box if any, then the text and score under them
+struct SideBlock {
     preview: PreviewField,
+    hold: Option<PreviewField>,
     text: Vec<String>,
 }
 
+impl SideBlock {
+    fn new(state: &TetrisState, mut text: Vec<String>) -> Self {
+        let preview = PreviewField(hide_if_paused(&state.preview, state.paused));
+        let hold = state
+            .hold
+            .as_ref()
+            .map(|hold| PreviewField(hide_if_paused(hold, state.paused)));
+        text.push(format!("Score: {}", state.score.score));
+        text.push(format!("Lines: {}", state.score.lines));
+        text.push(format!("Level: {}", state.score.level));
+        Self { preview, hold, text }
+    }
+}
+
+impl TermRender for SideBlock {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let mut lines = self.preview.output(style);
+        if let Some(hold) = &self.hold {
+            lines.extend(hold.output(style));
+        }
+        // Append empty line and text after preview block
+        lines.push(Vec::new());
+        lines.extend(self.text.iter().map(|s| vec![TermCell::Message(s.clone())]));
+        lines
+    }
+}
+
+pub struct GameFieldLeft {
+    well: WellField,
+    side: SideBlock,
+}
+
 impl GameFieldLeft {
     fn new(state: TetrisState, text: Vec<String>) -> Self {
-        let well = WellField::new(state.well, state.game_over);
-        let preview = PreviewField(state.preview);
-        Self { well, preview, text }
+        let side = SideBlock::new(&state, text);
+        let well = WellField::from_state(state);
+        Self { well, side }
     }
 }
 
 impl TermRender for GameFieldLeft {
     fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
         let mut lines = self.well.output(style);
-        let mut preview_block = self.preview.output(style);
-        // Append empty line and player name after preview block
-        preview_block.push(Vec::new());
-        preview_block.extend(self.text.iter().map(|s| vec![TermCell::Message(s.clone())]));
+        let preview_block = self.side.output(style);
+        // Small well can be shorter than preview, put empty lines above it
+        let missing = preview_block.len().saturating_sub(lines.len());
+        lines.splice(0..0, std::iter::repeat_n(Vec::new(), missing));
+        pad_block_right(&mut lines, style);
         // Append preview lines to well lines, padding with TermCell::Space
-        // Preview is always shorter than well
-        for (well_line, mut preview_line) in lines.iter_mut().zip(preview_block.into_iter()) {
+        for (well_line, mut preview_line) in lines.iter_mut().zip(preview_block) {
             well_line.push(TermCell::Space);
             well_line.append(&mut preview_line);
         }
@@ -264,31 +350,29 @@ impl TermRender for GameFieldLeft {
 
 pub struct GameFieldRight {
     well: WellField,
-    preview: PreviewField,
-    text: Vec<String>,
+    side: SideBlock,
 }
 
 impl GameFieldRight {
     fn new(state: TetrisState, text: Vec<String>) -> Self {
-        let well = WellField::new(state.well, state.game_over);
-        let preview = PreviewField(state.preview);
-        Self { well, preview, text }
+        let side = SideBlock::new(&state, text);
+        let well = WellField::from_state(state);
+        Self { well, side }
     }
 }
 
 impl TermRender for GameFieldRight {
     fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
-        let mut lines = self.preview.output(style);
-        let well_block = self.well.output(style);
-        // Append empty line and text after preview block
-        lines.push(Vec::new());
-        lines.extend(self.text.iter().map(|s| vec![TermCell::Message(s.clone())]));
+        let mut lines = self.side.output(style);
+        let mut well_block = self.well.output(style);
+        // Small well can be shorter than preview, put empty lines above it
+        let missing = lines.len().saturating_sub(well_block.len());
+        well_block.splice(0..0, std::iter::repeat_n(Vec::new(), missing));
         // extend height of lines to the height of well_block and then pad it with TermCell::Space
-        // Preview is always shorter than well
         lines.resize(well_block.len(), Vec::new());
         pad_block_right(&mut lines, style);
         // Append well lines to preview lines, padding with TermCell::Space
-        for (preview_line, mut well_line) in lines.iter_mut().zip(well_block.into_iter()) {
+        for (preview_line, mut well_line) in lines.iter_mut().zip(well_block) {
             preview_line.push(TermCell::Space);
             preview_line.append(&mut well_line);
         }
@@ -302,22 +386,361 @@ pub struct GameFieldPair {
 }
 
 impl GameFieldPair {
- pub fn new(state: TetrisPairState, text_player: Vec<String>, text_opponent: Vec<String> ) -> Self {
+    pub fn new(state: TetrisPairState, text_player: Vec<String>, text_opponent: Vec<String>) -> Self {
         let player = GameFieldRight::new(state.player, text_player);
         let opponent = GameFieldLeft::new(state.opponent, text_opponent);
         Self { opponent, player }
- }
+    }
 }
 
 impl TermRender for GameFieldPair {
     fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
         let mut lines = self.opponent.output(style);
-        let right_block = self.player.output(style);
+        let mut right_block = self.player.output(style);
+        // Players may have wells of different height, align them by bottom
+        let height = lines.len().max(right_block.len());
+        for block in [&mut lines, &mut right_block] {
+            let missing = height - block.len();
+            block.splice(0..0, std::iter::repeat_n(Vec::new(), missing));
+        }
+        pad_block_right(&mut lines, style);
         // Append opponent lines to player lines, padding with TermCell::Space
-        for (line, mut right_line) in lines.iter_mut().zip(right_block.into_iter()) {
+        for (line, mut right_line) in lines.iter_mut().zip(right_block) {
+            line.push(TermCell::Space);
             line.push(TermCell::Space);
             line.push(TermCell::Space);
             line.push(TermCell::Space);
+            line.append(&mut right_line);
+        }
+        lines
+    }
+}
+
+// Round number, score and match status lines shown above the pair of wells
+pub struct MatchHeader {
+    lines: Vec<String>,
+}
+
+impl MatchHeader {
+    pub fn new(state: &MatchSeriesState, name_player: &str, name_opponent: &str) -> Self {
+        let name = |side: PlayerSide| match side {
+            PlayerSide::Player => name_player,
+            PlayerSide::Opponent => name_opponent,
+        };
+        let round = format!("Round {} (best of {})", state.round, state.best_of);
+        let score = format!(
+            "{} {} : {} {}",
+            name_opponent, state.opponent_wins, state.player_wins, name_player
+        );
+        let status = match (state.phase, state.last_round()) {
+            (MatchPhase::Playing, _) => {
+                match (state.pair.paused_by_player, state.pair.paused_by_opponent) {
+                    (true, true) => "Paused by both players".to_string(),
+                    (true, false) => format!("Paused by {}", name_player),
+                    (false, true) => format!("Paused by {}", name_opponent),
+                    (false, false) => String::new(),
+                }
+            }
+            (MatchPhase::RoundOver, Some(RoundOutcome::Winner(side))) => {
+                format!("{} wins the round", name(side))
+            }
+            (MatchPhase::RoundOver, _) => "Draw, the round is replayed".to_string(),
+            (MatchPhase::MatchOver, _) => {
+                let winner = state.winner().map(name).unwrap_or_default();
+                let waiting = match (state.rematch_player, state.rematch_opponent) {
+                    (true, false) => format!(", waiting for {}", name_opponent),
+                    (false, true) => format!(", {} wants a rematch", name_opponent),
+                    _ => String::new(),
+                };
+                format!("{} wins the match. Rematch?{}", winner, waiting)
+            }
+        };
+        Self {
+            lines: vec![round, score, status],
+        }
+    }
+}
+
+impl TermRender for MatchHeader {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let mut lines: Vec<Vec<TermCell>> = self
+            .lines
+            .iter()
+            .map(|s| vec![TermCell::Message(s.clone())])
+            .collect();
+        pad_block_right(&mut lines, style);
+        lines
+    }
+}
+
+pub struct GameFieldSeries {
+    header: MatchHeader,
+    pair: GameFieldPair,
+}
+
+impl GameFieldSeries {
+    pub fn new(state: MatchSeriesState, name_player: &str, name_opponent: &str) -> Self {
+        let header = MatchHeader::new(&state, name_player, name_opponent);
+        let pair = GameFieldPair::new(
+            state.pair,
+            vec![name_player.to_string()],
+            vec![name_opponent.to_string()],
+        );
+        Self { header, pair }
+    }
+}
+
+impl TermRender for GameFieldSeries {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let mut lines = self.header.output(style);
+        lines.push(Vec::new());
+        lines.extend(self.pair.output(style));
+        pad_block_right(&mut lines, style);
+        lines
+    }
+}
+
+// Time as minutes, seconds and hundredths, i.e. 1:02.35
+pub fn format_time(time: Duration) -> String {
+    let hundredths = time.as_millis() / 10;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
+// Goal and progress of the single player game, and the result when it's finished
+pub struct ModeHeader {
+    lines: Vec<String>,
+}
+
+impl ModeHeader {
+    pub fn new(state: &SinglePlayerState) -> Self {
+        let score = state.tetris.score;
+        let progress = match state.mode {
+            GameMode::Endless => format!("{}  Time: {}", state.mode.name(), format_time(state.time)),
+            GameMode::Sprint { lines } => format!(
+                "{}  Lines: {}/{}  Time: {}",
+                state.mode.name(),
+                score.lines.min(lines),
+                lines,
+                format_time(state.time)
+            ),
+            GameMode::Ultra { time_limit } => format!(
+                "{}  Score: {}  Time left: {}",
+                state.mode.name(),
+                score.score,
+                format_time(time_limit.saturating_sub(state.time))
+            ),
+            GameMode::Marathon { lines, .. } => format!(
+                "{}  Level: {}  Lines: {}/{}  Time: {}",
+                state.mode.name(),
+                score.level,
+                score.lines,
+                lines,
+                format_time(state.time)
+            ),
+            GameMode::Dig { .. } => format!(
+                "{}  Garbage: {}  Time: {}",
+                state.mode.name(),
+                state.tetris.garbage_lines,
+                format_time(state.time)
+            ),
+            GameMode::Puzzle { goal } => {
+                format!("{}  Goal: {}", state.mode.name(), goal.description())
+            }
+        };
+        let status = match state.result {
+            None if state.tetris.paused => "Paused".to_string(),
+            None => String::new(),
+            Some(ModeResult::Sprint {
+                completed: true,
+                time,
+                ..
+            }) => format!("Finished in {}. Restart?", format_time(time)),
+            Some(ModeResult::Ultra {
+                completed: true,
+                score,
+                ..
+            }) => format!("Time is up, score {}. Restart?", score.score),
+            Some(ModeResult::Marathon { cleared: true, .. }) => "Cleared! Restart?".to_string(),
+            Some(ModeResult::Dig {
+                completed: true,
+                time,
+                ..
+            }) => format!("Dug out in {}. Restart?", format_time(time)),
+            Some(ModeResult::Puzzle { solved: true, .. }) => "Solved!".to_string(),
+            Some(ModeResult::Puzzle { solved: false, .. }) => "Failed. Try again?".to_string(),
+            Some(_) => "Game over. Restart?".to_string(),
+        };
+        Self {
+            lines: vec![progress, status],
+        }
+    }
+}
+
+impl TermRender for ModeHeader {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let mut lines: Vec<Vec<TermCell>> = self
+            .lines
+            .iter()
+            .map(|s| vec![TermCell::Message(s.clone())])
+            .collect();
+        pad_block_right(&mut lines, style);
+        lines
+    }
+}
+
+pub struct GameFieldSingle {
+    header: ModeHeader,
+    field: GameFieldLeft,
+}
+
+impl GameFieldSingle {
+    pub fn new(state: SinglePlayerState, text: Vec<String>) -> Self {
+        let header = ModeHeader::new(&state);
+        let field = GameFieldLeft::new(state.tetris, text);
+        Self { header, field }
+    }
+}
+
+impl TermRender for GameFieldSingle {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let mut lines = self.header.output(style);
+        lines.push(Vec::new());
+        lines.extend(self.field.output(style));
+        pad_block_right(&mut lines, style);
+        lines
+    }
+}
+
+// Statistics of the finished single player game
+pub struct ModeStats {
+    lines: Vec<String>,
+}
+
+impl ModeStats {
+    pub fn new(state: &SinglePlayerState) -> Self {
+        let score = state.tetris.score;
+        let outcome = match state.result {
+            Some(result) if result.is_completed() => match result {
+                ModeResult::Marathon { .. } => "Cleared",
+                ModeResult::Puzzle { .. } => "Solved",
+                _ => "Completed",
+            },
+            Some(_) => "Game over",
+            None => "In progress",
+        };
+        let seconds = state.time.as_secs_f64();
+        // Rates are meaningless in the first moments of the game
+        let per_second = |count: usize| {
+            if seconds > 0.0 {
+                count as f64 / seconds
+            } else {
+                0.0
+            }
+        };
+        Self {
+            lines: vec![
+                format!("{} - {}", state.mode.name(), outcome),
+                String::new(),
+                format!("Score:  {}", score.score),
+                format!("Lines:  {}", score.lines),
+                format!("Level:  {}", score.level),
+                format!("Pieces: {}", score.pieces),
+                format!("Time:   {}", format_time(state.time)),
+                format!("PPS:    {:.2}", per_second(score.pieces)),
+                format!("LPM:    {:.1}", per_second(score.lines) * 60.0),
+            ],
+        }
+    }
+}
+
+impl TermRender for ModeStats {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let mut lines: Vec<Vec<TermCell>> = self
+            .lines
+            .iter()
+            .map(|s| vec![TermCell::Space, TermCell::Message(s.clone()), TermCell::Space])
+            .collect();
+        pad_block_right(&mut lines, style);
+        // Frame the stats like a well
+        let width = lines
+            .first()
+            .map_or(0, |line| line.iter().map(|cell| style.width(cell)).sum::<usize>());
+        let borders = width.div_ceil(style.width(&TermCell::BorderHorizontal));
+        let border = |left, right| {
+            let mut line = vec![left];
+            line.extend(std::iter::repeat_n(TermCell::BorderHorizontal, borders));
+            line.push(right);
+            line
+        };
+        let padding = borders * style.width(&TermCell::BorderHorizontal) - width;
+        for line in &mut lines {
+            line.insert(0, TermCell::BorderVertical);
+            line.extend(std::iter::repeat_n(TermCell::Space, padding));
+            line.push(TermCell::BorderVertical);
+        }
+        lines.insert(0, border(TermCell::BorderTopLeft, TermCell::BorderTopRight));
+        lines.push(border(TermCell::BorderBottomLeft, TermCell::BorderBottomRight));
+        lines
+    }
+}
+
+// Shared well of the cooperative game with previews and hold boxes of both players on the sides
+pub struct GameFieldCoop {
+    well: WellField,
+    player_preview: PreviewField,
+    opponent_preview: PreviewField,
+    player_hold: Option<PreviewField>,
+    opponent_hold: Option<PreviewField>,
+    text_player: Vec<String>,
+    text_opponent: Vec<String>,
+}
+
+impl GameFieldCoop {
+    pub fn new(state: CoopState, text_player: Vec<String>, text_opponent: Vec<String>) -> Self {
+        let mut text_player = text_player;
+        text_player.push(format!("Score: {}", state.score.score));
+        text_player.push(format!("Lines: {}", state.score.lines));
+        text_player.push(format!("Level: {}", state.score.level));
+        Self {
+            well: WellField::new(state.well, state.game_over),
+            player_preview: PreviewField(state.player_preview),
+            opponent_preview: PreviewField(state.opponent_preview),
+            player_hold: state.player_hold.map(PreviewField),
+            opponent_hold: state.opponent_hold.map(PreviewField),
+            text_player,
+            text_opponent,
+        }
+    }
+}
+
+impl TermRender for GameFieldCoop {
+    fn output(&self, style: &impl TermStyle) -> Vec<Vec<TermCell>> {
+        let side_block = |preview: &PreviewField, hold: &Option<PreviewField>, text: &Vec<String>| {
+            let mut block = preview.output(style);
+            if let Some(hold) = hold {
+                block.extend(hold.output(style));
+            }
+            block.push(Vec::new());
+            block.extend(text.iter().map(|s| vec![TermCell::Message(s.clone())]));
+            block
+        };
+        let mut lines = side_block(&self.opponent_preview, &self.opponent_hold, &self.text_opponent);
+        let well_block = self.well.output(style);
+        let right_block = side_block(&self.player_preview, &self.player_hold, &self.text_player);
+        let height = well_block.len().max(lines.len()).max(right_block.len());
+        lines.resize(height, Vec::new());
+        pad_block_right(&mut lines, style);
+        for (line, mut well_line) in lines.iter_mut().zip(well_block) {
+            line.push(TermCell::Space);
+            line.append(&mut well_line);
+        }
+        pad_block_right(&mut lines, style);
+        for (line, mut right_line) in lines.iter_mut().zip(right_block) {
             line.push(TermCell::Space);
             line.append(&mut right_line);
         }
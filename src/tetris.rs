@@ -1,11 +1,29 @@
-use crate::{frequency_regulator::FrequencyRegulator, state::TetrisState};
+use crate::{
+    auto_repeat::{AutoRepeat, AutoRepeatSettings, KeyEvent},
+    clock::{Gravity, TICK},
+    frequency_regulator::RateRegulator,
+    path::PathPlanner,
+    piece_sequence::{next_seed, PieceSequence},
+    placement::Placement,
+    rotation::{rotation_offsets, spawn_x},
+    rules::{
+        Delays, DropStyle, GravityCurve, InputPolicy, LockDelay, LockReset, RotationSystem, RuleSet,
+    },
+    scoring::{Score, ScoreCounter},
+    state::TetrisState,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::{collections::VecDeque, time::Duration};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CellType {
     Empty = 0,
     Blasted,
+    // Where the current tetromino lands, only drawn in game state
+    Ghost,
+    // Cell of garbage row added by `Action::BottomRefill`
+    Garbage,
     I,
     J,
     L,
@@ -28,9 +46,44 @@ impl CellType {
             _ => unreachable!(),
         }
     }
+
+    // Character of the cell in the ASCII notation, see `Field::from_ascii`
+    pub fn to_char(&self) -> char {
+        match self {
+            CellType::Empty => '.',
+            CellType::Blasted => '*',
+            CellType::Ghost => ':',
+            CellType::Garbage => 'G',
+            CellType::I => 'I',
+            CellType::J => 'J',
+            CellType::L => 'L',
+            CellType::O => 'O',
+            CellType::S => 'S',
+            CellType::T => 'T',
+            CellType::Z => 'Z',
+        }
+    }
+
+    // Cell by its character, `#` is accepted for garbage too
+    pub fn from_char(c: char) -> Option<CellType> {
+        match c {
+            '.' => Some(CellType::Empty),
+            '*' => Some(CellType::Blasted),
+            ':' => Some(CellType::Ghost),
+            'G' | '#' => Some(CellType::Garbage),
+            'I' => Some(CellType::I),
+            'J' => Some(CellType::J),
+            'L' => Some(CellType::L),
+            'O' => Some(CellType::O),
+            'S' => Some(CellType::S),
+            'T' => Some(CellType::T),
+            'Z' => Some(CellType::Z),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Rotation {
     R0,
     R90,
@@ -210,7 +263,7 @@ fn get_tetromino_matrix(tetromino_type: &TetrominoType) -> &TetrominoMatrix {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TetrominoType {
     I,
     J,
@@ -271,9 +324,23 @@ impl TetrominoType {
             TetrominoType::Z => CellType::Z,
         }
     }
+
+    // Tetromino type by its letter, in any case
+    pub fn from_char(c: char) -> Option<TetrominoType> {
+        match c.to_ascii_uppercase() {
+            'I' => Some(TetrominoType::I),
+            'J' => Some(TetrominoType::J),
+            'L' => Some(TetrominoType::L),
+            'O' => Some(TetrominoType::O),
+            'S' => Some(TetrominoType::S),
+            'T' => Some(TetrominoType::T),
+            'Z' => Some(TetrominoType::Z),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Tetromino {
     // Tetromino type
     tetromino_type: TetrominoType,
@@ -291,11 +358,68 @@ impl Tetromino {
         Tetromino {
             tetromino_type,
             rotation,
-            x: x,
-            y: y,
+            x,
+            y,
         }
     }
 
+    pub fn tetromino_type(&self) -> TetrominoType {
+        self.tetromino_type
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    pub fn x(&self) -> isize {
+        self.x
+    }
+
+    pub fn y(&self) -> isize {
+        self.y
+    }
+
+    // Same tetromino moved by given offset and rotated
+    pub fn moved(&self, x: isize, y: isize, rotation: Rotation) -> Self {
+        Tetromino::new(
+            self.tetromino_type,
+            self.rotation + rotation,
+            self.x + x,
+            self.y + y,
+        )
+    }
+
+    // Tetromino of given type occupying exactly the given cells, in any order
+    pub fn from_cells(tetromino_type: TetrominoType, cells: &[(isize, isize)]) -> Option<Self> {
+        let x = cells.iter().map(|&(x, _)| x).min()?;
+        let y = cells.iter().map(|&(_, y)| y).min()?;
+        let mut cells = cells.to_vec();
+        cells.sort();
+        [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270]
+            .into_iter()
+            .map(|rotation| Tetromino::new(tetromino_type, rotation, x, y))
+            .find(|tetromino| {
+                let mut tetromino_cells = tetromino.cells();
+                tetromino_cells.sort();
+                tetromino_cells == cells
+            })
+    }
+
+    // Positions of the tetromino cells in the well
+    pub fn cells(&self) -> Vec<(isize, isize)> {
+        let width = self.tetromino_type.get_width(&self.rotation);
+        let height = self.tetromino_type.get_height(&self.rotation);
+        let mut cells = Vec::new();
+        for cell_y in 0..height {
+            for cell_x in 0..width {
+                if self.tetromino_type.get_cell(cell_x, cell_y, &self.rotation) {
+                    cells.push((self.x + cell_x as isize, self.y + cell_y as isize));
+                }
+            }
+        }
+        cells
+    }
+
     // Check if tetromino intersects with field borders or other tetrominos
     pub fn intersects(&self, field: &Field) -> bool {
         // Check if tetromino intersects with field borders or other tetrominos
@@ -331,14 +455,52 @@ impl Tetromino {
         false
     }
 
+    // T-spin by the 3-corner rule for a T rotated into its place: at least 3 of 4 cells diagonal
+    // to its center are occupied. Cells outside the field count as occupied.
+    // It's a mini T-spin if only one of two corners the T points to is occupied
+    pub fn tspin(&self, field: &Field) -> TSpin {
+        if self.tetromino_type != TetrominoType::T {
+            return TSpin::None;
+        }
+        // Center of T relative to its position and corners on the side the T points to
+        let ((center_x, center_y), front) = match self.rotation {
+            Rotation::R0 => ((1, 1), [(-1, -1), (1, -1)]),
+            Rotation::R90 => ((0, 1), [(1, -1), (1, 1)]),
+            Rotation::R180 => ((1, 0), [(-1, 1), (1, 1)]),
+            Rotation::R270 => ((1, 1), [(-1, -1), (-1, 1)]),
+        };
+        let occupied = |(dx, dy): (isize, isize)| {
+            let x = self.x + center_x + dx;
+            let y = self.y + center_y + dy;
+            x < 0
+                || y < 0
+                || x >= field.cols() as isize
+                || y >= field.rows() as isize
+                || field.get_cell(x as usize, y as usize) != CellType::Empty
+        };
+        let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+            .into_iter()
+            .filter(|corner| occupied(*corner))
+            .count();
+        let front_corners = front.into_iter().filter(|corner| occupied(*corner)).count();
+        match (corners, front_corners) {
+            (0..=2, _) => TSpin::None,
+            (_, 2) => TSpin::Full,
+            _ => TSpin::Mini,
+        }
+    }
+
     // Draw tetromino on field. If tetromino intersects with field borders, draw it partially.
     // I.e for any cell position check is it inside field borders and if it is, draw it.
     pub fn draw(&self, field: &mut Field) {
-        // Draw tetromino on field
+        self.draw_as(field, self.tetromino_type.get_cell_type());
+    }
+
+    // Draw tetromino on field with given cell type instead of the tetromino one
+    pub fn draw_as(&self, field: &mut Field, cell_type: CellType) {
         // Get tetromino width and height
         let width = self.tetromino_type.get_width(&self.rotation);
         let height = self.tetromino_type.get_height(&self.rotation);
-        let cell_type = self.tetromino_type.get_cell_type();
         // Draw tetromino on field
         for cell_y in 0..height {
             for cell_x in 0..width {
@@ -365,10 +527,12 @@ pub enum Action {
     RotateLeft,
     RotateRight,
     Drop,
+    // Swap current tetromino with the held one, once per tetromino. Allowed by `RuleSet::hold`
+    Hold,
     BottomRefill,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Eq, Clone, Serialize, Deserialize)]
 pub struct Field {
     // Field width
     cols: usize,
@@ -388,6 +552,32 @@ impl Field {
         }
     }
 
+    // Parse the field from ASCII notation: one line per row from top to bottom,
+    // one character per cell as given by `CellType::to_char`.
+    // Blank lines and surrounding whitespace are ignored, all rows must have the same width
+    pub fn from_ascii(text: &str) -> Result<Field, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let cols = lines.first().map_or(0, |line| line.chars().count());
+        let mut field = Field::new(cols, lines.len());
+        for (y, line) in lines.iter().enumerate() {
+            if line.chars().count() != cols {
+                return Err(format!("row {} has different width: {}", y + 1, line));
+            }
+            for (x, c) in line.chars().enumerate() {
+                let cell_type = CellType::from_char(c)
+                    .ok_or_else(|| format!("unknown cell `{}` in row {}", c, y + 1))?;
+                if cell_type != CellType::Empty {
+                    field.set_cell(x, y, cell_type);
+                }
+            }
+        }
+        Ok(field)
+    }
+
     pub fn clear(&mut self) {
         self.cells.clear();
     }
@@ -423,6 +613,128 @@ impl Field {
             .cloned()
             .unwrap_or(CellType::Empty)
     }
+
+    // Blasts full lines and returns true if there were full lines
+    pub fn blast_full_lines(&mut self) -> bool {
+        // Iterate over all lines
+        // If line is full, replace it's Empty cells to Blasted cells and set return value to true
+        let mut full_lines = false;
+        for y in 0..self.rows {
+            let mut full_line = true;
+            for x in 0..self.cols {
+                if self.get_cell(x, y) == CellType::Empty {
+                    full_line = false;
+                    break;
+                }
+            }
+            if full_line {
+                full_lines = true;
+                for x in 0..self.cols {
+                    self.set_cell(x, y, CellType::Blasted);
+                }
+            }
+        }
+        full_lines
+    }
+
+    // Remove all blasted lines, return number of removed lines
+    pub fn remove_blasted_lines(&mut self) -> usize {
+        let mut lines = 0;
+        while self.remove_top_blasted_line().is_some() {
+            lines += 1;
+        }
+        lines
+    }
+
+    // Number of blasted lines
+    pub fn blasted_lines(&self) -> usize {
+        (0..self.rows)
+            .filter(|&y| self.get_cell(0, y) == CellType::Blasted)
+            .count()
+    }
+
+    // Number of lines with garbage cells
+    pub fn garbage_lines(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|row| row.contains(&CellType::Garbage))
+            .count()
+    }
+
+    // True if there is nothing in the well except blasted lines
+    pub fn is_cleared(&self) -> bool {
+        self.cells
+            .iter()
+            .flatten()
+            .all(|cell| matches!(cell, CellType::Empty | CellType::Blasted))
+    }
+
+    // Find topmost blasted line and shift all lines above it down to one line
+    // Line is blasted if it's first cell is Blasted
+    // Return index of removed line or None if there are no blasted lines
+    pub fn remove_top_blasted_line(&mut self) -> Option<usize> {
+        // Find topmost blasted line
+        let top_blasted_line =
+            (0..self.rows).find(|&y| self.get_cell(0, y) == CellType::Blasted)?;
+        // Shift all lines above topmost blasted line down to one line
+        for y in (0..top_blasted_line).rev() {
+            for x in 0..self.cols {
+                self.set_cell(x, y + 1, self.get_cell(x, y));
+            }
+        }
+        // Fill topmost line with Empty cells
+        for x in 0..self.cols {
+            self.set_cell(x, 0, CellType::Empty);
+        }
+        Some(top_blasted_line)
+    }
+}
+
+// Fields are equal if they have the same size and cells, however the cells are stored
+impl PartialEq for Field {
+    fn eq(&self, other: &Self) -> bool {
+        self.cols == other.cols
+            && self.rows == other.rows
+            && (0..self.rows)
+                .all(|y| (0..self.cols).all(|x| self.get_cell(x, y) == other.get_cell(x, y)))
+    }
+}
+
+// ASCII notation of the field, parsed back by `Field::from_ascii`
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for y in 0..self.rows {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for x in 0..self.cols {
+                write!(f, "{}", self.get_cell(x, y).to_char())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Kind of T-spin, see `Lock`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum TSpin {
+    None,
+    // Only one of the corners the T points to is occupied
+    Mini,
+    Full,
+}
+
+// Tetromino locked in the well
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Lock {
+    // Tetromino in the place where it's locked
+    pub tetromino: Tetromino,
+    // Lines cleared by the tetromino
+    pub lines: usize,
+    // T rotated into the place where 3 of 4 corners around its center are occupied
+    pub tspin: TSpin,
+    // Nothing is left in the well after the lines are cleared
+    pub perfect_clear: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
@@ -431,10 +743,247 @@ pub enum StepResult {
     None,
     // Action was performed
     ActionPerformed(Action, bool),
-    // Line removed
-    LineRemoved,
+    // Tetromino locked
+    Locked(Lock),
+    // Blasted lines collapsed, number of lines is reported
+    LinesRemoved(usize),
     // Game over
     GameOver,
+    // Game is paused, nothing happens until it's resumed
+    Paused,
+    // Countdown before the game starts, current count is reported
+    Countdown(usize),
+}
+
+// Reason why user action was not performed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DropReason {
+    // Action queue is full, see `InputPolicy::max_queue`
+    QueueFull,
+    // The game is paused or counting down
+    Paused,
+    // Action was queued for the tetromino which locked before the action was performed
+    Locked,
+}
+
+// Number of dropped user actions by reason
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct DroppedActions {
+    pub queue_full: usize,
+    pub paused: usize,
+    pub locked: usize,
+}
+
+impl DroppedActions {
+    pub(crate) fn add(&mut self, reason: DropReason) {
+        match reason {
+            DropReason::QueueFull => self.queue_full += 1,
+            DropReason::Paused => self.paused += 1,
+            DropReason::Locked => self.locked += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.queue_full + self.paused + self.locked
+    }
+}
+
+// Countdown before the game starts or resumes after pause
+#[derive(Debug, Clone, Copy)]
+struct Countdown {
+    time_left: Duration,
+    time_per_count: Duration,
+}
+
+impl Countdown {
+    // Number to show to the players, i.e. 3, 2, 1
+    fn count(&self) -> usize {
+        self.time_left
+            .as_nanos()
+            .div_ceil(self.time_per_count.as_nanos()) as usize
+    }
+}
+
+// Cells the controlled tetromino can't pass. `CoopTetris` adds the partner's tetromino
+// to the fixed cells of the well
+pub(crate) trait Obstacles {
+    fn blocks(&self, tetromino: &Tetromino) -> bool;
+}
+
+impl Obstacles for Field {
+    fn blocks(&self, tetromino: &Tetromino) -> bool {
+        tetromino.intersects(self)
+    }
+}
+
+// Drop and lock delay state of the tetromino controlled by a player.
+// Moves are checked against the given obstacles, so `CoopTetris` uses the same code
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct PieceState {
+    // Drop state
+    pub(crate) drop: bool,
+    // Time left before the grounded tetromino locks, None if it's not on the ground
+    lock_timer: Option<Duration>,
+    // Lock delay resets used by the tetromino
+    lock_resets: usize,
+    // Lowest row reached by the tetromino
+    lowest_y: isize,
+    // Last successful move of the tetromino was a rotation, for T-spin detection
+    last_rotated: bool,
+}
+
+impl PieceState {
+    // State of the tetromino which has just appeared
+    pub(crate) fn new(tetromino: &Tetromino) -> Self {
+        PieceState {
+            lowest_y: tetromino.y,
+            ..PieceState::default()
+        }
+    }
+
+    // Move and rotate the tetromino by the offset, if it fits the well
+    pub(crate) fn shift(
+        &mut self,
+        current: &mut Tetromino,
+        x: isize,
+        y: isize,
+        rotation: Rotation,
+        well: &impl Obstacles,
+    ) -> bool {
+        let new_tetromino = current.moved(x, y, rotation);
+        if well.blocks(&new_tetromino) {
+            return false;
+        }
+        *current = new_tetromino;
+        self.last_rotated = rotation != Rotation::R0;
+        true
+    }
+
+    pub(crate) fn move_down(&mut self, current: &mut Tetromino, well: &impl Obstacles) -> bool {
+        if !self.shift(current, 0, 1, Rotation::R0, well) {
+            return false;
+        }
+        // Reaching a new lowest row restarts lock delay
+        if current.y > self.lowest_y {
+            self.lowest_y = current.y;
+            self.lock_timer = None;
+            self.lock_resets = 0;
+        }
+        true
+    }
+
+    // Rotate the tetromino, trying kicks of the rotation system one by one
+    pub(crate) fn rotate(
+        &mut self,
+        current: &mut Tetromino,
+        system: RotationSystem,
+        rotation: Rotation,
+        well: &impl Obstacles,
+    ) -> bool {
+        let to = current.rotation + rotation;
+        rotation_offsets(system, current.tetromino_type, current.rotation, to)
+            .into_iter()
+            .any(|(x, y)| self.shift(current, x, y, rotation, well))
+    }
+
+    // Successful move of the grounded tetromino restarts lock delay, if the rules allow it
+    pub(crate) fn reset_lock_delay(&mut self, lock_delay: LockDelay) {
+        if self.lock_timer.is_none() || lock_delay.reset != LockReset::Move {
+            return;
+        }
+        if lock_delay
+            .max_resets
+            .is_some_and(|max_resets| self.lock_resets >= max_resets)
+        {
+            return;
+        }
+        self.lock_resets += 1;
+        self.lock_timer = Some(lock_delay.duration());
+    }
+
+    // Count lock delay down while the tetromino is on the ground. Returns true when it must lock
+    pub(crate) fn lock_delay_expired(
+        &mut self,
+        current: &Tetromino,
+        elapsed: Duration,
+        well: &impl Obstacles,
+    ) -> bool {
+        let Some(timer) = self.lock_timer else {
+            return false;
+        };
+        if !is_grounded(current, well) {
+            self.lock_timer = None;
+        } else if timer > elapsed {
+            self.lock_timer = Some(timer - elapsed);
+        } else {
+            return true;
+        }
+        false
+    }
+
+    // Start lock delay when the tetromino reaches the ground
+    pub(crate) fn start_lock_delay(
+        &mut self,
+        current: &Tetromino,
+        lock_delay: LockDelay,
+        well: &impl Obstacles,
+    ) {
+        if self.lock_timer.is_none() && lock_delay.delay > 0 && is_grounded(current, well) {
+            self.lock_timer = Some(lock_delay.duration());
+        }
+    }
+
+    // T-spin by the 3-corner rule, see `Tetromino::tspin`. Only counts if the last move was a rotation
+    pub(crate) fn tspin(&self, current: &Tetromino, well: &Field) -> TSpin {
+        if self.last_rotated {
+            current.tspin(well)
+        } else {
+            TSpin::None
+        }
+    }
+}
+
+// Tetromino can't move down
+pub(crate) fn is_grounded(tetromino: &Tetromino, well: &impl Obstacles) -> bool {
+    well.blocks(&tetromino.moved(0, 1, Rotation::R0))
+}
+
+// Queued actions were meant for the locked tetromino, remove them. Garbage stays in the queue,
+// rotations are carried over to the next tetromino with IRS and hold with IHS
+pub(crate) fn drop_queued_actions(
+    actions: &mut VecDeque<Action>,
+    policy: InputPolicy,
+    carried: &mut Vec<Action>,
+    dropped: &mut DroppedActions,
+) {
+    let mut kept = VecDeque::new();
+    for action in actions.drain(..) {
+        match action {
+            Action::BottomRefill => kept.push_back(action),
+            Action::RotateLeft | Action::RotateRight if policy.irs => carried.push(action),
+            Action::Hold if policy.ihs => carried.push(action),
+            _ => dropped.add(DropReason::Locked),
+        }
+    }
+    *actions = kept;
+}
+
+// Next tetrominos drawn one under another, with one empty row between them
+pub(crate) fn preview_field(next: &VecDeque<TetrominoType>, preview_count: usize) -> Field {
+    let mut preview = Field::new(4, (preview_count * 3).max(4));
+    for (n, tetromino_type) in next.iter().take(preview_count).enumerate() {
+        Tetromino::new(*tetromino_type, Rotation::R0, 0, n as isize * 3).draw(&mut preview);
+    }
+    preview
+}
+
+// Hold box with the held tetromino, if any
+pub(crate) fn hold_field(hold: Option<TetrominoType>) -> Field {
+    let mut field = Field::new(4, 2);
+    if let Some(tetromino_type) = hold {
+        Tetromino::new(tetromino_type, Rotation::R0, 0, 0).draw(&mut field);
+    }
+    field
 }
 
 pub struct Tetris {
@@ -449,35 +998,64 @@ pub struct Tetris {
     preview: Field,
     // Current tetromino
     current: Option<Tetromino>,
-    // Next tetromino
-    next: TetrominoType,
+    // Source of next tetrominos
+    sequence: PieceSequence,
+    // Source of garbage rows, seeded together with the sequence
+    garbage: StdRng,
+    // Next tetrominos, first one is placed next
+    next: VecDeque<TetrominoType>,
+    // Fixed sequence of tetrominos, no tetrominos are generated after it's used up
+    fixed_queue: Option<Vec<TetrominoType>>,
+    // Held tetromino and whether hold was used by the current tetromino
+    hold: Option<TetrominoType>,
+    hold_used: bool,
+    // Game rules. Setters below change the corresponding rule
+    rules: RuleSet,
+    // Rows of garbage added to the bottom of the well at game start
+    start_garbage: usize,
+    // Content of the well at game start
+    start_field: Option<Field>,
     // User actions queue
     actions: VecDeque<Action>,
-    // Drop state
-    drop: bool,
+    // Rotations and hold carried over to the next tetromino with IRS and IHS
+    carried: Vec<Action>,
+    dropped: DroppedActions,
+    // Drop and lock delay state of the current tetromino
+    piece: PieceState,
     // Game speed
-    fall_speed: FrequencyRegulator,
+    fall_speed: RateRegulator,
     // Drop speed
-    drop_speed: FrequencyRegulator,
-    // Blasting speed
-    line_remove_speed: FrequencyRegulator,
-    // Delay before line shifting
-    line_remove_delay: Option<usize>,
-    // Game score
-    _score: usize,
+    drop_speed: RateRegulator,
+    // Last lock, reported by `advance` as `StepResult::Locked`
+    locked: Option<Lock>,
+    // Lines collapsed right after the lock, reported by `advance` as `StepResult::LinesRemoved`
+    removed: Option<usize>,
+    // Time left before blasted lines collapse
+    clear_delay: Option<Duration>,
+    // Time left before the next tetromino appears
+    spawn_delay: Option<Duration>,
+    // Repeating of held keys
+    auto_repeat: AutoRepeat,
+    // Pause flag. When paused, speed regulators and delays are not advanced
+    paused: bool,
+    // Countdown before the game starts
+    countdown: Option<Countdown>,
+    // Score, lines and level
+    score: ScoreCounter,
 }
 
 impl Tetris {
     pub fn new(cols: usize, rows: usize) -> Self {
+        Self::with_rules(cols, rows, RuleSet::default())
+    }
+
+    pub fn with_rules(cols: usize, rows: usize, rules: RuleSet) -> Self {
         // Create new tetris game
         // Create game field, functional style
         let well = Field::new(cols, rows);
 
         // Create preview field, functional style
-        let mut preview = Field::new(4, 4);
-
-        // Set next tetromino type
-        let next = Self::create_next_tetromino_type(&mut preview);
+        let preview = Field::new(4, 4);
 
         // Create user actions queue
         let actions = VecDeque::new();
@@ -485,38 +1063,169 @@ impl Tetris {
         // Set game over flag
         let game_over = false;
 
-        // Score
-        let _score = 0;
+        // Seed of tetrominos and garbage
+        let seed = rand::random();
 
         // Create new tetris game
-        Tetris {
+        let mut tetris = Tetris {
             cols,
             rows,
             game_over,
             well,
             preview,
             current: None,
-            next,
+            sequence: PieceSequence::with_randomizer(rules.randomizer, seed),
+            garbage: StdRng::seed_from_u64(next_seed(seed)),
+            next: VecDeque::new(),
+            fixed_queue: None,
+            hold: None,
+            hold_used: false,
+            start_garbage: 0,
+            start_field: None,
             actions,
-            drop: false,
-            fall_speed: FrequencyRegulator::new(1, 100),
-            drop_speed: FrequencyRegulator::new(1, 10),
-            line_remove_speed: FrequencyRegulator::new(1, 3),
-            line_remove_delay: None,
-            _score,
+            carried: Vec::new(),
+            dropped: DroppedActions::default(),
+            piece: PieceState::default(),
+            fall_speed: RateRegulator::new(
+                rules.gravity.gravity(rules.start_level).rows_per_second(),
+            ),
+            drop_speed: RateRegulator::new(rules.drop_gravity.rows_per_second()),
+            locked: None,
+            removed: None,
+            clear_delay: None,
+            spawn_delay: None,
+            auto_repeat: AutoRepeat::new(rules.auto_repeat),
+            paused: false,
+            countdown: None,
+            score: ScoreCounter::new(rules.scoring, rules.level_up, rules.start_level),
+            rules,
+        };
+        tetris.fill_next_queue();
+        tetris
+    }
+
+    // Game in the position given by ASCII notation of the well, see `Field::from_ascii`.
+    // Lowercase letters mark the cells of the active tetromino, i.e. `t` for T.
+    // The well without the active tetromino becomes the start field
+    pub fn from_ascii(text: &str, rules: RuleSet) -> Result<Tetris, String> {
+        let mut marked = Vec::new();
+        let mut active_type = None;
+        let mut ascii = String::new();
+        for (y, line) in text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+        {
+            for (x, c) in line.chars().enumerate() {
+                if c.is_ascii_lowercase() {
+                    let tetromino_type = TetrominoType::from_char(c)
+                        .ok_or_else(|| format!("unknown tetromino `{}` in row {}", c, y + 1))?;
+                    if active_type.is_some_and(|t| t != tetromino_type) {
+                        return Err("active tetromino has cells of different types".to_string());
+                    }
+                    active_type = Some(tetromino_type);
+                    marked.push((x as isize, y as isize));
+                    ascii.push(CellType::Empty.to_char());
+                } else {
+                    ascii.push(c);
+                }
+            }
+            ascii.push('\n');
         }
+        let field = Field::from_ascii(&ascii)?;
+        let mut tetris = Tetris::with_rules(field.cols(), field.rows(), rules);
+        tetris.set_start_field(Some(field))?;
+        if let Some(tetromino_type) = active_type {
+            let tetromino = Tetromino::from_cells(tetromino_type, &marked)
+                .ok_or("marked cells don't form a tetromino")?;
+            if tetromino.intersects(&tetris.well) {
+                return Err("active tetromino is out of the well".to_string());
+            }
+            tetris.start_tetromino(tetromino);
+        }
+        Ok(tetris)
+    }
+
+    // ASCII notation of the well with the active tetromino in lowercase letters,
+    // parsed back by `Tetris::from_ascii`
+    pub fn to_ascii(&self) -> String {
+        let mut rows: Vec<Vec<char>> = self
+            .well
+            .to_string()
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        if let Some(current) = &self.current {
+            let c = current.tetromino_type.get_cell_type().to_char();
+            for (x, y) in current.cells() {
+                if let Some(cell) = rows
+                    .get_mut(y as usize)
+                    .and_then(|row| row.get_mut(x as usize))
+                {
+                    *cell = c.to_ascii_lowercase();
+                }
+            }
+        }
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    // Set falling speed of the tetromino. It doesn't change with level anymore
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.rules.gravity = GravityCurve::Fixed(gravity);
+        self.fall_speed.set(gravity.rows_per_second());
+    }
+
+    pub fn gravity(&self) -> Gravity {
+        Gravity::RowsPerSecond(self.fall_speed.rate())
+    }
+
+    // Set falling speed of the tetromino after `Action::Drop`
+    pub fn set_drop_gravity(&mut self, gravity: Gravity) {
+        self.rules.drop_gravity = gravity;
+        self.drop_speed.set(gravity.rows_per_second());
     }
 
+    pub fn set_delays(&mut self, delays: Delays) {
+        self.rules.delays = delays;
+    }
+
+    pub fn delays(&self) -> Delays {
+        self.rules.delays
+    }
+
+    pub fn score(&self) -> Score {
+        self.score.score()
+    }
+
+    // Stop the level, and so the speed, from growing past `max_level`
+    pub fn set_max_level(&mut self, max_level: Option<usize>) {
+        self.score.set_max_level(max_level);
+    }
+
+    // Speeds below are given in (lines, steps) format, where step is `TICK` long.
+    // They are kept for loops which call `step` each `TICK`
+
     pub fn set_fall_speed(&mut self, lines: usize, steps: usize) {
-        self.fall_speed = FrequencyRegulator::new(lines, steps);
+        self.set_gravity(Gravity::from_ticks(lines, steps));
     }
 
     pub fn set_drop_speed(&mut self, lines: usize, steps: usize) {
-        self.drop_speed = FrequencyRegulator::new(lines, steps);
+        self.set_drop_gravity(Gravity::from_ticks(lines, steps));
     }
 
+    // Blasted lines collapse all at once after `Delays::clear_animation`,
+    // the speed is converted to the animation time of one line
+    #[deprecated(note = "use `set_delays` with `Delays::clear_animation`")]
     pub fn set_line_remove_speed(&mut self, lines: usize, steps: usize) {
-        self.line_remove_speed = FrequencyRegulator::new(lines, steps);
+        self.rules.delays.clear_animation = (steps / lines.max(1)) as u32;
     }
 
     pub fn cols(&self) -> usize {
@@ -527,82 +1236,376 @@ impl Tetris {
         self.rows
     }
 
-    // Add user action to actions queue
-    pub fn add_action(&mut self, action: Action) {
+    // Add user action to actions queue. Returns false if the action is dropped, see `dropped_actions`.
+    // User actions are ignored while the game is paused or counting down, only garbage is accepted.
+    // Garbage is never dropped
+    pub fn add_action(&mut self, action: Action) -> bool {
+        if action != Action::BottomRefill {
+            if self.paused || self.countdown.is_some() {
+                self.dropped.add(DropReason::Paused);
+                return false;
+            }
+            let queued = self
+                .actions
+                .iter()
+                .filter(|action| **action != Action::BottomRefill)
+                .count();
+            if self
+                .rules
+                .input_policy
+                .max_queue
+                .is_some_and(|max_queue| queued >= max_queue)
+            {
+                self.dropped.add(DropReason::QueueFull);
+                return false;
+            }
+        }
         self.actions.push_back(action);
+        true
+    }
+
+    pub fn set_input_policy(&mut self, policy: InputPolicy) {
+        self.rules.input_policy = policy;
+    }
+
+    pub fn input_policy(&self) -> InputPolicy {
+        self.rules.input_policy
+    }
+
+    // Number of user actions dropped since the game start
+    pub fn dropped_actions(&self) -> DroppedActions {
+        self.dropped
+    }
+
+    // Key of the action is pressed. Moves are repeated while the key is held, see `AutoRepeat`.
+    // Keys pressed during countdown start charging DAS, but the action itself is ignored
+    pub fn press_action(&mut self, action: Action) {
+        if self.paused {
+            return;
+        }
+        self.auto_repeat.press(action);
+        self.add_action(action);
+    }
+
+    pub fn release_action(&mut self, action: Action) {
+        self.auto_repeat.release(action);
+    }
+
+    pub fn key_event(&mut self, event: KeyEvent) {
+        match event {
+            KeyEvent::Press(action) => self.press_action(action),
+            KeyEvent::Release(action) => self.release_action(action),
+        }
     }
 
-    // Process single user action
-    pub fn step(&mut self) -> StepResult {
+    pub fn set_auto_repeat(&mut self, settings: AutoRepeatSettings) {
+        self.rules.auto_repeat = settings;
+        self.auto_repeat.set_settings(settings);
+    }
+
+    pub fn auto_repeat(&self) -> AutoRepeatSettings {
+        self.auto_repeat.settings()
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.countdown = None;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Hold the game for `counts` * `time_per_count`, counting down from `counts` to 1
+    pub fn start_countdown(&mut self, counts: usize, time_per_count: Duration) {
+        if counts == 0 || time_per_count.is_zero() {
+            self.countdown = None;
+            return;
+        }
+        self.countdown = Some(Countdown {
+            time_left: time_per_count * counts as u32,
+            time_per_count,
+        });
+    }
+
+    // Current countdown number, if countdown is in progress
+    pub fn countdown(&self) -> Option<usize> {
+        self.countdown.map(|countdown| countdown.count())
+    }
+
+    // Advance the game by one `TICK`
+    pub fn step(&mut self) -> Vec<StepResult> {
+        self.advance(TICK)
+    }
+
+    // Advance the game by the time elapsed since the previous call.
+    // All queued user actions are applied, then held keys are repeated and gravity moves
+    // the tetromino down. Gravity is reported as `Action::MoveDown`, failed one means
+    // the tetromino is locked.
+    // Returns results in the order they happened, empty if nothing happened
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<StepResult> {
         if self.game_over {
-            return StepResult::GameOver;
+            return vec![StepResult::GameOver];
         }
 
-        if let Some(ref mut delay) = self.line_remove_delay {
-            if *delay > 0 {
-                *delay -= 1;
-                return StepResult::None;
-            } else {
-                self.line_remove_delay = None;
+        if self.paused {
+            return vec![StepResult::Paused];
+        }
+
+        // Held keys are charged even when there is nothing to move
+        let active = self.current.is_some() && self.countdown.is_none();
+        let repeated = self
+            .auto_repeat
+            .advance(elapsed, active, self.cols, self.rows);
+
+        if let Some(ref mut countdown) = self.countdown {
+            let count = countdown.count();
+            countdown.time_left = countdown.time_left.saturating_sub(elapsed);
+            if countdown.time_left.is_zero() {
+                self.countdown = None;
             }
+            return vec![StepResult::Countdown(count)];
         }
-        for _ in 0..self.line_remove_speed.step() {
-            if self.current.is_none() {
-                if self.remove_top_blasted_line() {
-                    return StepResult::LineRemoved;
-                } else {
-                    if !self.place_next_tetromino() {
-                        self.game_over = true;
-                        return StepResult::GameOver;
-                    }
+
+        let mut results = Vec::new();
+        // Time left after a delay expired goes on to the next tetromino
+        let mut elapsed = elapsed;
+
+        // Blasted lines are shown for clear animation time, then all of them collapse at once
+        if let Some(delay) = self.clear_delay {
+            if delay > elapsed {
+                self.clear_delay = Some(delay - elapsed);
+                return results;
+            }
+            elapsed -= delay;
+            self.clear_delay = None;
+            let lines = self.well.remove_blasted_lines();
+            results.push(StepResult::LinesRemoved(lines));
+            if !self.start_spawn_delay(self.rules.delays.line_clear_are()) {
+                results.push(StepResult::GameOver);
+                return results;
+            }
+        }
+
+        if self.current.is_none() {
+            if let Some(delay) = self.spawn_delay {
+                if delay > elapsed {
+                    self.spawn_delay = Some(delay - elapsed);
+                    return results;
                 }
+                elapsed -= delay;
+                self.spawn_delay = None;
+            }
+            if !self.spawn() {
+                results.push(StepResult::GameOver);
+                return results;
             }
         }
 
-        if self.drop {
-            for _ in 0..self.drop_speed.step() {
-                self.actions.push_back(Action::MoveDown);
+        while let Some(action) = self.actions.pop_front() {
+            let succeed = self.perform(action);
+            results.push(StepResult::ActionPerformed(action, succeed));
+            // Move down is special case. If it fails, fix current tetromino and blast full lines.
+            // With hard drop the tetromino is locked by drop instead, so soft drop can slide it.
+            // Only garbage is left in the queue then
+            if !succeed
+                && action == Action::MoveDown
+                && self.current.is_some()
+                && self.rules.drop != DropStyle::Hard
+            {
+                self.lock();
             }
-        } else {
-            for _ in 0..self.fall_speed.step() {
-                self.actions.push_back(Action::MoveDown);
+            // Hard drop locks the tetromino too
+            self.report_lock(&mut results);
+            if self.game_over {
+                results.push(StepResult::GameOver);
+                return results;
             }
         }
 
-        let Some(action) = self.actions.pop_front() else {
-            return StepResult::None;
-        };
+        // Moves of held keys are performed at once. Failed move down doesn't fix
+        // the tetromino, it's done by gravity
+        for action in repeated {
+            if self.perform(action) {
+                results.push(StepResult::ActionPerformed(action, true));
+            }
+        }
+
+        // Lock delay counts down while the tetromino is on the ground
+        if let Some(current) = self.current {
+            if self.piece.lock_delay_expired(&current, elapsed, &self.well) {
+                results.push(StepResult::ActionPerformed(Action::MoveDown, false));
+                self.lock();
+                self.report_lock(&mut results);
+                if self.game_over {
+                    results.push(StepResult::GameOver);
+                }
+                return results;
+            }
+        }
+
+        if self.current.is_some() {
+            let falls = if self.piece.drop {
+                self.drop_speed.advance(elapsed)
+            } else {
+                self.fall_speed.advance(elapsed)
+            };
+            for _ in 0..falls {
+                if self.move_down() {
+                    results.push(StepResult::ActionPerformed(Action::MoveDown, true));
+                    continue;
+                }
+                // Without lock delay the tetromino locks as soon as it can't fall
+                if self.rules.lock_delay.delay == 0 {
+                    results.push(StepResult::ActionPerformed(Action::MoveDown, false));
+                    self.lock();
+                    self.report_lock(&mut results);
+                    if self.game_over {
+                        results.push(StepResult::GameOver);
+                    }
+                }
+                break;
+            }
+        }
+
+        if let Some(current) = self.current {
+            self.piece
+                .start_lock_delay(&current, self.rules.lock_delay, &self.well);
+        }
+        results
+    }
+
+    // Add the lock which happened, if any, to the step results
+    fn report_lock(&mut self, results: &mut Vec<StepResult>) {
+        if let Some(lock) = self.locked.take() {
+            results.push(StepResult::Locked(lock));
+        }
+        if let Some(lines) = self.removed.take() {
+            results.push(StepResult::LinesRemoved(lines));
+        }
+    }
+
+    // Perform user action. Returns false if it's impossible
+    fn perform(&mut self, action: Action) -> bool {
         let succeed = match action {
             Action::MoveLeft => self.move_left(),
             Action::MoveRight => self.move_right(),
-            Action::MoveDown => self.move_down(),
+            Action::MoveDown => {
+                let moved = self.move_down();
+                if moved {
+                    self.score.soft_drop(1);
+                }
+                moved
+            }
             Action::RotateLeft => self.rotate_left(),
             Action::RotateRight => self.rotate_right(),
             Action::Drop => self.drop(),
+            Action::Hold => self.hold(),
             Action::BottomRefill => self.bottom_refill(),
         };
-        // Move down is special case. If it fails, fix current tetromino and blast full lines
-        if !succeed && action == Action::MoveDown {
-            self.fix_current_figure();
-            self.blast_full_lines();
-            self.actions.clear();
-            self.line_remove_delay = Some(10); // Wait 10 ticks before placing next tetromino to show blast animation
+        if succeed
+            && matches!(
+                action,
+                Action::MoveLeft | Action::MoveRight | Action::RotateLeft | Action::RotateRight
+            )
+        {
+            self.piece.reset_lock_delay(self.rules.lock_delay);
         }
-        return StepResult::ActionPerformed(action, succeed);
+        succeed
     }
 
-    // Create next tetromino type and draw it on preview field
-    fn create_next_tetromino_type(preview: &mut Field) -> TetrominoType {
-        // Create next tetromino and draw it on preview field
-        // Get next tetromino type
-        let tetromino_type = TetrominoType::new_random();
-        // Create new tetromino
-        let tetromino = Tetromino::new(tetromino_type, Rotation::R0, 0, 0);
-        // Draw tetromino on preview field
-        preview.clear();
-        tetromino.draw(preview);
-        // Get tetromino
-        tetromino_type
+    // Falling speed of the current level
+    fn update_gravity(&mut self) {
+        let gravity = self.rules.gravity.gravity(self.score.score().level);
+        self.fall_speed.set(gravity.rows_per_second());
+    }
+
+    // Add random tetromino types to the next queue until it's long enough for preview
+    // and draw them on preview field
+    fn fill_next_queue(&mut self) {
+        while self.fixed_queue.is_none() && self.next.len() < self.rules.preview_count.max(1) {
+            self.next.push_back(self.sequence.next_tetromino_type());
+        }
+        self.draw_preview();
+    }
+
+    fn draw_preview(&mut self) {
+        self.preview = preview_field(&self.next, self.rules.preview_count);
+    }
+
+    // Restart piece sequence and garbage from the given seed.
+    // Games with the same seed get the same tetrominos and garbage rows
+    pub fn set_seed(&mut self, seed: u64) {
+        self.sequence = PieceSequence::with_randomizer(self.rules.randomizer, seed);
+        self.garbage = StdRng::seed_from_u64(next_seed(seed));
+        self.restart_next_queue();
+    }
+
+    // Play the given tetrominos in order instead of the generated ones. When they are used up,
+    // the game is over. With None tetrominos are generated again. Applied immediately and on each reset
+    pub fn set_fixed_queue(&mut self, tetrominos: Option<Vec<TetrominoType>>) {
+        self.fixed_queue = tetrominos;
+        self.restart_next_queue();
+    }
+
+    fn restart_next_queue(&mut self) {
+        self.next = self.fixed_queue.clone().unwrap_or_default().into();
+        self.fill_next_queue();
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.sequence.seed()
+    }
+
+    // Set number of next tetrominos shown on preview field
+    pub fn set_preview_count(&mut self, count: usize) {
+        self.rules.preview_count = count.max(1);
+        self.fill_next_queue();
+    }
+
+    pub fn preview_count(&self) -> usize {
+        self.rules.preview_count
+    }
+
+    // Set content of the well at game start, garbage rows are added on top of it.
+    // Applied immediately and on each reset. The field must have the size of the well
+    pub fn set_start_field(&mut self, field: Option<Field>) -> Result<(), String> {
+        if let Some(field) = &field {
+            if field.cols() != self.cols || field.rows() != self.rows {
+                return Err(format!(
+                    "start field is {}x{}, the well is {}x{}",
+                    field.cols(),
+                    field.rows(),
+                    self.cols,
+                    self.rows
+                ));
+            }
+        }
+        self.start_field = field;
+        self.fill_start_well();
+        Ok(())
+    }
+
+    // Set number of garbage rows the well starts with. Applied immediately, replacing
+    // the content of the well, and on each reset
+    pub fn set_start_garbage(&mut self, rows: usize) {
+        self.start_garbage = rows;
+        self.fill_start_well();
+    }
+
+    // Put the start field and the start garbage into the well
+    fn fill_start_well(&mut self) {
+        match &self.start_field {
+            Some(field) => self.well = field.clone(),
+            None => self.well.clear(),
+        }
+        for _ in 0..self.start_garbage {
+            self.bottom_refill();
+        }
     }
 
     pub fn get_field(&self) -> &Field {
@@ -617,59 +1620,115 @@ impl Tetris {
         &self.current
     }
 
-    pub fn get_next(&self) -> &TetrominoType {
+    // Next tetromino, None if the fixed queue is used up
+    pub fn get_next(&self) -> Option<&TetrominoType> {
+        self.next.front()
+    }
+
+    pub fn get_next_queue(&self) -> &VecDeque<TetrominoType> {
         &self.next
     }
 
+    pub fn get_hold(&self) -> Option<TetrominoType> {
+        self.hold
+    }
+
+    // Set the held tetromino, i.e. to continue a position from another game
+    pub fn set_hold(&mut self, tetromino_type: Option<TetrominoType>) {
+        self.hold = tetromino_type;
+    }
+
+    // Whether the current tetromino can be swapped with the held one now, see `hold`
+    pub fn can_hold(&self) -> bool {
+        self.rules.hold && !self.hold_used && self.current.is_some()
+    }
+
+    // Placements of the current tetromino from where it is now, see `Placement::find_all`
+    pub fn placements(&self) -> Vec<Placement> {
+        match &self.current {
+            Some(current) => Placement::find_from(&self.well, *current, self.rules.rotation),
+            None => Vec::new(),
+        }
+    }
+
+    // Actions locking the current tetromino in the placement, when they are queued at once.
+    // See `PathPlanner`
+    pub fn path_to(&self, placement: &Placement) -> Option<Vec<Action>> {
+        let current = self.current?;
+        PathPlanner::new(&self.rules).find_placement(&self.well, current, placement)
+    }
+
     // Place new tetromino on the field. Return false if it's impossible to place new tetromino
     pub fn place_next_tetromino(&mut self) -> bool {
+        // Fixed queue is used up
+        let Some(&tetromino_type) = self.next.front() else {
+            return false;
+        };
         // Create new tetromino
-        let new_tetromino = Tetromino::new(self.next, Rotation::R0, self.cols as isize / 2 - 2, 0);
+        let new_tetromino = self.new_tetromino(tetromino_type);
 
         // Check if new tetromino intersects with field borders or other tetrominos
         if new_tetromino.intersects(&self.well) {
             return false;
         }
         // Set new tetromino as current
-        self.current = Some(new_tetromino);
+        self.start_tetromino(new_tetromino);
+        self.hold_used = false;
 
         // Set next tetromino type and draw it on preview field
-        self.next = Self::create_next_tetromino_type(&mut self.preview);
+        self.next.pop_front();
+        self.fill_next_queue();
+
+        if self.score.spawn() {
+            self.update_gravity();
+        }
+
+        // Apply hold and rotations carried over from the previous tetromino
+        let carried = std::mem::take(&mut self.carried);
+        if carried.contains(&Action::Hold) {
+            self.hold();
+        }
+        for action in carried {
+            match action {
+                Action::RotateLeft => self.rotate_left(),
+                Action::RotateRight => self.rotate_right(),
+                _ => false,
+            };
+        }
 
-        // Clear drop flag
-        self.drop = false;
+        self.auto_repeat.on_spawn();
 
         // Return true if new tetromino was placed on the field
         true
     }
 
+    // Tetromino of given type at the spawn position
+    fn new_tetromino(&self, tetromino_type: TetrominoType) -> Tetromino {
+        let x = spawn_x(self.rules.rotation, tetromino_type, self.cols);
+        Tetromino::new(tetromino_type, Rotation::R0, x, 0)
+    }
+
+    // Make the tetromino current, with fresh drop and lock state
+    fn start_tetromino(&mut self, tetromino: Tetromino) {
+        self.current = Some(tetromino);
+        self.piece = PieceState::new(&tetromino);
+    }
+
     // Change position and rotation of current tetromino, if it's possible
     pub fn change_current_tetromino(&mut self, x: isize, y: isize, rotation: Rotation) -> bool {
-        // Change position and rotation of current tetromino, if it's possible
         // Check if current tetromino exists
         let Some(current) = &mut self.current else {
             return false;
         };
-
-        // Create new tetromino
-        let new_tetromino = Tetromino::new(
-            current.tetromino_type,
-            current.rotation + rotation,
-            current.x as isize + x,
-            current.y as isize + y,
-        );
-        // Check if new tetromino intersects with field borders or other tetrominos
-        if new_tetromino.intersects(&self.well) {
-            return false;
-        }
-        *current = new_tetromino;
-        return true;
+        self.piece.shift(current, x, y, rotation, &self.well)
     }
 
     // Move current tetromino down, if it's possible
     pub fn move_down(&mut self) -> bool {
-        // Move current tetromino down, if it's possible
-        self.change_current_tetromino(0, 1, Rotation::R0)
+        let Some(current) = &mut self.current else {
+            return false;
+        };
+        self.piece.move_down(current, &self.well)
     }
 
     // Move current tetromino left, if it's possible
@@ -686,24 +1745,76 @@ impl Tetris {
 
     // Rotate current tetromino left, if it's possible
     pub fn rotate_left(&mut self) -> bool {
-        // Rotate current tetromino left, if it's possible
-        self.change_current_tetromino(0, 0, Rotation::R270)
+        self.rotate(Rotation::R270)
     }
 
     // Rotate current tetromino right, if it's possible
     pub fn rotate_right(&mut self) -> bool {
-        // Rotate current tetromino right, if it's possible
-        self.change_current_tetromino(0, 0, Rotation::R90)
+        self.rotate(Rotation::R90)
     }
 
-    // Set drop flag
+    // Rotate current tetromino, trying kicks of the rotation system one by one
+    fn rotate(&mut self, rotation: Rotation) -> bool {
+        let Some(current) = &mut self.current else {
+            return false;
+        };
+        self.piece
+            .rotate(current, self.rules.rotation, rotation, &self.well)
+    }
+
+    // Drop current tetromino the way the rules say: set drop flag for fast drop,
+    // or move it to the bottom for hard and sonic drop. Hard drop locks it at once
     pub fn drop(&mut self) -> bool {
-        // Set drop flag
-        self.drop = true;
+        match self.rules.drop {
+            DropStyle::Fast => {
+                self.piece.drop = true;
+                true
+            }
+            DropStyle::Hard | DropStyle::Sonic => {
+                if self.current.is_none() {
+                    return false;
+                }
+                let mut rows = 0;
+                while self.move_down() {
+                    rows += 1;
+                }
+                self.score.hard_drop(rows);
+                if self.rules.drop == DropStyle::Hard {
+                    self.lock();
+                }
+                true
+            }
+        }
+    }
+
+    // Swap current tetromino with the held one, the first hold takes the next tetromino.
+    // Only once per tetromino and only if the rules allow it
+    pub fn hold(&mut self) -> bool {
+        if !self.rules.hold || self.hold_used {
+            return false;
+        }
+        let Some(current) = self.current else {
+            return false;
+        };
+        let Some(tetromino_type) = self.hold.or(self.next.front().copied()) else {
+            return false;
+        };
+        let tetromino = self.new_tetromino(tetromino_type);
+        if tetromino.intersects(&self.well) {
+            return false;
+        }
+        if self.hold.is_none() {
+            self.next.pop_front();
+            self.fill_next_queue();
+        }
+        self.hold = Some(current.tetromino_type);
+        self.start_tetromino(tetromino);
+        self.hold_used = true;
         true
     }
 
-    // Push all lines up and fill bottom line with random cells with probability of filled cell = 0.5
+    // Push all lines up and fill bottom line with garbage cells with probability of filled cell = 0.5.
+    // The line always has at least one hole and at least one garbage cell
     pub fn bottom_refill(&mut self) -> bool {
         // Push all lines up
         for y in 1..self.rows {
@@ -711,10 +1822,16 @@ impl Tetris {
                 self.well.set_cell(x, y - 1, self.well.get_cell(x, y));
             }
         }
-        // Fill bottom line with random cells with probability of filled cell = 0.3
-        for x in 0..self.cols {
-            let cell_type = if rand::random::<f32>() < 0.5 {
-                CellType::new_random()
+        let mut cells: Vec<bool> = (0..self.cols).map(|_| self.garbage.gen::<bool>()).collect();
+        let x = self.garbage.gen_range(0..self.cols);
+        if cells.iter().all(|filled| *filled) {
+            cells[x] = false;
+        } else if cells.iter().all(|filled| !*filled) {
+            cells[x] = true;
+        }
+        for (x, filled) in cells.into_iter().enumerate() {
+            let cell_type = if filled {
+                CellType::Garbage
             } else {
                 CellType::Empty
             };
@@ -733,75 +1850,115 @@ impl Tetris {
         }
     }
 
-    // Blasts full lines and returns true if there were full lines
-    fn blast_full_lines(&mut self) -> bool {
-        // Iterate over all lines
-        // If line is full, replace it's Empty cells to Blasted cells and set return value to true
-        let mut full_lines = false;
-        for y in 0..self.rows {
-            let mut full_line = true;
-            for x in 0..self.cols {
-                if self.well.get_cell(x, y) == CellType::Empty {
-                    full_line = false;
-                    break;
-                }
-            }
-            if full_line {
-                full_lines = true;
-                for x in 0..self.cols {
-                    self.well.set_cell(x, y, CellType::Blasted);
-                }
-            }
+    // Fix current tetromino and blast full lines. Blasted lines are shown during clear animation,
+    // otherwise the next tetromino appears after entry delay
+    fn lock(&mut self) {
+        let Some(current) = self.current else {
+            return;
+        };
+        let tspin = self.piece.tspin(&current, &self.well);
+        self.fix_current_figure();
+        drop_queued_actions(
+            &mut self.actions,
+            self.rules.input_policy,
+            &mut self.carried,
+            &mut self.dropped,
+        );
+        let blasted = self.well.blast_full_lines();
+        let lines = self.well.blasted_lines();
+        let lock = Lock {
+            tetromino: current,
+            lines,
+            tspin,
+            perfect_clear: blasted && self.well.is_cleared(),
+        };
+        if self.score.lock(&lock) {
+            self.update_gravity();
+        }
+        self.locked = Some(lock);
+        if !blasted {
+            self.start_spawn_delay(self.rules.delays.are());
+        } else if self.rules.delays.clear_animation == 0 {
+            // Without clear animation the lines collapse at once
+            self.removed = Some(self.well.remove_blasted_lines());
+            self.start_spawn_delay(self.rules.delays.line_clear_are());
+        } else {
+            self.clear_delay = Some(self.rules.delays.clear_animation());
         }
-        full_lines
     }
 
-    // Find topmost blasted line and shift all lines above it down to one line
-    // Line is blasted if it's first cell is Blasted
-    // Return false if there are no blasted lines
-    fn remove_top_blasted_line(&mut self) -> bool {
-        // Find topmost blasted line
-        let mut top_blasted_line = None;
-        for y in 0..self.rows {
-            if self.well.get_cell(0, y) == CellType::Blasted {
-                top_blasted_line = Some(y);
-                break;
-            }
-        }
-        // If there are no blasted lines, return false
-        let Some(top_blasted_line) = top_blasted_line else {
-            return false;
-        };
-        // Shift all lines above topmost blasted line down to one line
-        for y in (0..top_blasted_line).rev() {
-            for x in 0..self.cols {
-                self.well.set_cell(x, y + 1, self.well.get_cell(x, y));
-            }
+    // Wait `delay` before the next tetromino appears, without delay it's placed at once.
+    // Returns false if the game is over
+    fn start_spawn_delay(&mut self, delay: Duration) -> bool {
+        if delay.is_zero() {
+            self.spawn()
+        } else {
+            self.spawn_delay = Some(delay);
+            true
         }
-        // Fill topmost line with Empty cells
-        for x in 0..self.cols {
-            self.well.set_cell(x, 0, CellType::Empty);
+    }
+
+    // Place next tetromino, the game is over if there is no room for it
+    fn spawn(&mut self) -> bool {
+        if !self.place_next_tetromino() {
+            self.game_over = true;
         }
-        // Return true if there were blasted lines
-        true
+        !self.game_over
     }
 
     // get game state for serialization
     pub fn get_state(&self) -> TetrisState {
         let mut well = self.well.clone();
-        // draw current tetromino on the field
+        // draw current tetromino on the field, and where it lands under it
         if let Some(current) = &self.current {
+            if self.rules.ghost {
+                let mut ghost = *current;
+                while !ghost.moved(0, 1, Rotation::R0).intersects(&self.well) {
+                    ghost = ghost.moved(0, 1, Rotation::R0);
+                }
+                ghost.draw_as(&mut well, CellType::Ghost);
+            }
             current.draw(&mut well);
         }
         let preview = self.preview.clone();
+        // Hold box is shown only if hold is allowed
+        let hold = self.rules.hold.then(|| hold_field(self.hold));
         TetrisState {
             well,
             preview,
+            hold,
+            score: self.score(),
+            garbage_lines: self.well.garbage_lines(),
             game_over: self.game_over,
+            paused: self.paused,
+            countdown: self.countdown(),
         }
     }
 
     pub fn is_game_over(&self) -> bool {
         self.game_over
     }
+
+    // Start a new game on the same well, keeping speed settings
+    pub fn reset(&mut self) {
+        self.game_over = false;
+        self.current = None;
+        self.restart_next_queue();
+        self.hold = None;
+        self.hold_used = false;
+        self.actions.clear();
+        self.piece = PieceState::default();
+        self.locked = None;
+        self.removed = None;
+        self.score.reset();
+        self.update_gravity();
+        self.clear_delay = None;
+        self.spawn_delay = None;
+        self.carried.clear();
+        self.dropped = DroppedActions::default();
+        self.auto_repeat.clear();
+        self.paused = false;
+        self.countdown = None;
+        self.fill_start_well();
+    }
 }
@@ -1,43 +1,335 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
+    auto_repeat::{AutoRepeatSettings, KeyEvent},
+    bot::{Bot, BotController},
+    clock::{Gravity, TICK},
+    frequency_regulator::FrequencyRegulator,
+    piece_sequence::next_seed,
+    rules::{Delays, InputPolicy, RuleSet},
     state::TetrisPairState,
     tetris::{Action, StepResult, Tetris},
 };
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum PlayerSide {
     Player,
     Opponent,
 }
 
+impl PlayerSide {
+    pub fn other(&self) -> PlayerSide {
+        match self {
+            PlayerSide::Player => PlayerSide::Opponent,
+            PlayerSide::Opponent => PlayerSide::Player,
+        }
+    }
+}
+
+// Settings of one side of the pair. Allows to give handicap to one of the players
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerSettings {
+    // Well size
+    pub cols: usize,
+    pub rows: usize,
+    // Game rules: speed, delays, previews, input buffering and so on
+    pub rules: RuleSet,
+    // Rows of garbage in the well when the game starts
+    pub garbage_rows: usize,
+    // Garbage rows received for lines removed by the other player, in (rows, lines) format.
+    // I.e. (1, 2) means one garbage row for every two lines of the other player.
+    // Zero lines count as one
+    pub garbage_multiplier: (usize, usize),
+}
+
+impl PlayerSettings {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self::with_rules(cols, rows, RuleSet::default())
+    }
+
+    pub fn with_rules(cols: usize, rows: usize, rules: RuleSet) -> Self {
+        PlayerSettings {
+            cols,
+            rows,
+            rules,
+            garbage_rows: 0,
+            garbage_multiplier: (1, 1),
+        }
+    }
+
+    fn create_tetris(&self) -> Tetris {
+        let mut tetris = Tetris::with_rules(self.cols, self.rows, self.rules.clone());
+        tetris.set_start_garbage(self.garbage_rows);
+        tetris
+    }
+
+    fn create_garbage_regulator(&self) -> FrequencyRegulator {
+        let (rows, lines) = self.garbage_multiplier;
+        FrequencyRegulator::new(rows, lines.max(1))
+    }
+}
+
+// Total number of lines removed in the step
+fn removed_lines(results: &[StepResult]) -> usize {
+    results
+        .iter()
+        .map(|result| match result {
+            StepResult::LinesRemoved(lines) => *lines,
+            _ => 0,
+        })
+        .sum()
+}
+
 pub struct TetrisPair {
     player: Tetris,
     opponent: Tetris,
+    // Incoming garbage rows for each line removed by the other side
+    garbage_player: FrequencyRegulator,
+    garbage_opponent: FrequencyRegulator,
+    // When set, both players get the same tetrominos generated from this seed
+    shared_seed: Option<u64>,
+    // Players who requested pause. The game resumes only when all of them agreed to resume
+    paused_by_player: bool,
+    paused_by_opponent: bool,
+    // Countdown before start and after pause, in (counts, time per count) format
+    countdown: (usize, Duration),
     // The step is performed when both players have called step method
     // This is to prevent one player from getting an advantage by calling step more often
     step_player: bool,
     step_opponent: bool,
     step_divergence: usize,
+    // Bots playing instead of the players, they are the input of their side
+    bot_player: Option<BotController>,
+    bot_opponent: Option<BotController>,
 }
 
 impl TetrisPair {
     pub fn new(cols: usize, rows: usize) -> TetrisPair {
+        let settings = PlayerSettings::new(cols, rows);
+        Self::with_settings(&settings, &settings)
+    }
+
+    pub fn with_settings(player: &PlayerSettings, opponent: &PlayerSettings) -> TetrisPair {
         TetrisPair {
-            player: Tetris::new(cols, rows),
-            opponent: Tetris::new(cols, rows),
+            player: player.create_tetris(),
+            opponent: opponent.create_tetris(),
+            garbage_player: player.create_garbage_regulator(),
+            garbage_opponent: opponent.create_garbage_regulator(),
+            shared_seed: None,
+            paused_by_player: false,
+            paused_by_opponent: false,
+            countdown: (3, Duration::from_secs(1)),
             step_player: false,
             step_opponent: false,
             step_divergence: 0,
+            bot_player: None,
+            bot_opponent: None,
+        }
+    }
+
+    // Well size of the side, the wells may differ with per-player settings
+    pub fn rows(&self, player: PlayerSide) -> usize {
+        self.get(player).rows()
+    }
+
+    pub fn cols(&self, player: PlayerSide) -> usize {
+        self.get(player).cols()
+    }
+
+    pub fn get(&self, player: PlayerSide) -> &Tetris {
+        match player {
+            PlayerSide::Player => &self.player,
+            PlayerSide::Opponent => &self.opponent,
+        }
+    }
+
+    pub fn get_mut(&mut self, player: PlayerSide) -> &mut Tetris {
+        match player {
+            PlayerSide::Player => &mut self.player,
+            PlayerSide::Opponent => &mut self.opponent,
+        }
+    }
+
+    // Let the bot play the side, usually `PlayerSide::Opponent`. Before each step it adds actions
+    // for the chosen moves to the side's game, timed by `Bot::pacing`. Actions of the player
+    // still work, but the bot doesn't expect them. With None the side is controlled by the player again.
+    // With shared seed the bot's think time jitter comes from it too, so games can be replayed
+    pub fn set_bot(&mut self, player: PlayerSide, bot: Option<Box<dyn Bot>>) {
+        let seed = self.shared_seed.unwrap_or_else(rand::random);
+        let controller = bot.map(|bot| BotController::new(bot, seed));
+        match player {
+            PlayerSide::Player => self.bot_player = controller,
+            PlayerSide::Opponent => self.bot_opponent = controller,
+        }
+    }
+
+    pub fn has_bot(&self, player: PlayerSide) -> bool {
+        match player {
+            PlayerSide::Player => self.bot_player.is_some(),
+            PlayerSide::Opponent => self.bot_opponent.is_some(),
         }
     }
 
-    pub fn rows(&self) -> usize {
-        self.player.rows()
+    // Replace settings of one side. The well of this side is recreated, so it's
+    // expected to be called between games
+    pub fn set_player_settings(&mut self, player: PlayerSide, settings: &PlayerSettings) {
+        match player {
+            PlayerSide::Player => {
+                self.player = settings.create_tetris();
+                self.garbage_player = settings.create_garbage_regulator();
+                if let Some(bot) = &mut self.bot_player {
+                    bot.reset();
+                }
+            }
+            PlayerSide::Opponent => {
+                self.opponent = settings.create_tetris();
+                self.garbage_opponent = settings.create_garbage_regulator();
+                if let Some(bot) = &mut self.bot_opponent {
+                    bot.reset();
+                }
+            }
+        }
+        if let Some(seed) = self.shared_seed {
+            self.get_mut(player).set_seed(seed);
+        }
+    }
+
+    // Give both players the identical piece sequence generated from the seed.
+    // With `None` each player gets own random sequence
+    pub fn set_shared_seed(&mut self, seed: Option<u64>) {
+        self.shared_seed = seed;
+        let seeds = match seed {
+            Some(seed) => (seed, seed),
+            None => (rand::random(), rand::random()),
+        };
+        self.player.set_seed(seeds.0);
+        self.opponent.set_seed(seeds.1);
+        for bot in [&mut self.bot_player, &mut self.bot_opponent]
+            .into_iter()
+            .flatten()
+        {
+            bot.set_seed(seed.unwrap_or_else(rand::random));
+        }
+    }
+
+    pub fn shared_seed(&self) -> Option<u64> {
+        self.shared_seed
+    }
+
+    // Set countdown used by `start_countdown` and when the game resumes after pause
+    pub fn set_countdown(&mut self, counts: usize, time_per_count: Duration) {
+        self.countdown = (counts, time_per_count);
     }
 
-    pub fn cols(&self) -> usize {
-        self.player.cols()
+    // Start synchronized 3-2-1 countdown for both players
+    pub fn start_countdown(&mut self) {
+        let (counts, time_per_count) = self.countdown;
+        self.player.start_countdown(counts, time_per_count);
+        self.opponent.start_countdown(counts, time_per_count);
+    }
+
+    // Pause both games. Any player can pause
+    pub fn pause(&mut self, player: PlayerSide) {
+        match player {
+            PlayerSide::Player => self.paused_by_player = true,
+            PlayerSide::Opponent => self.paused_by_opponent = true,
+        }
+        self.player.pause();
+        self.opponent.pause();
     }
 
+    // Withdraw player's pause request. The games are resumed with countdown when
+    // no player holds the pause anymore
+    pub fn resume(&mut self, player: PlayerSide) {
+        match player {
+            PlayerSide::Player => self.paused_by_player = false,
+            PlayerSide::Opponent => self.paused_by_opponent = false,
+        }
+        if self.is_paused() && !self.paused_by_player && !self.paused_by_opponent {
+            self.player.resume();
+            self.opponent.resume();
+            self.start_countdown();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.player.is_paused() || self.opponent.is_paused()
+    }
+
+    pub fn is_paused_by(&self, player: PlayerSide) -> bool {
+        match player {
+            PlayerSide::Player => self.paused_by_player,
+            PlayerSide::Opponent => self.paused_by_opponent,
+        }
+    }
+
+    pub fn set_player_gravity(&mut self, player: PlayerSide, gravity: Gravity) {
+        self.get_mut(player).set_gravity(gravity);
+    }
+
+    pub fn set_player_drop_gravity(&mut self, player: PlayerSide, gravity: Gravity) {
+        self.get_mut(player).set_drop_gravity(gravity);
+    }
+
+    pub fn set_player_input_policy(&mut self, player: PlayerSide, policy: InputPolicy) {
+        self.get_mut(player).set_input_policy(policy);
+    }
+
+    pub fn set_player_delays(&mut self, player: PlayerSide, delays: Delays) {
+        self.get_mut(player).set_delays(delays);
+    }
+
+    pub fn set_player_preview_count(&mut self, player: PlayerSide, count: usize) {
+        self.get_mut(player).set_preview_count(count);
+    }
+
+    pub fn set_player_start_garbage(&mut self, player: PlayerSide, rows: usize) {
+        self.get_mut(player).set_start_garbage(rows);
+    }
+
+    pub fn set_player_auto_repeat(&mut self, player: PlayerSide, settings: AutoRepeatSettings) {
+        self.get_mut(player).set_auto_repeat(settings);
+    }
+
+    // Set how many garbage rows the player receives for lines removed by the other side.
+    // Zero lines count as one
+    pub fn set_player_garbage_multiplier(&mut self, player: PlayerSide, rows: usize, lines: usize) {
+        let lines = lines.max(1);
+        match player {
+            PlayerSide::Player => self.garbage_player = FrequencyRegulator::new(rows, lines),
+            PlayerSide::Opponent => self.garbage_opponent = FrequencyRegulator::new(rows, lines),
+        }
+    }
+
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.player.set_gravity(gravity);
+        self.opponent.set_gravity(gravity);
+    }
+
+    pub fn set_drop_gravity(&mut self, gravity: Gravity) {
+        self.player.set_drop_gravity(gravity);
+        self.opponent.set_drop_gravity(gravity);
+    }
+
+    pub fn set_delays(&mut self, delays: Delays) {
+        self.player.set_delays(delays);
+        self.opponent.set_delays(delays);
+    }
+
+    pub fn set_input_policy(&mut self, policy: InputPolicy) {
+        self.player.set_input_policy(policy);
+        self.opponent.set_input_policy(policy);
+    }
+
+    pub fn set_auto_repeat(&mut self, settings: AutoRepeatSettings) {
+        self.player.set_auto_repeat(settings);
+        self.opponent.set_auto_repeat(settings);
+    }
+
+    // Speeds in (lines, steps) format, see `Tetris::set_fall_speed`
+
     pub fn set_fall_speed(&mut self, lines: usize, steps: usize) {
         self.player.set_fall_speed(lines, steps);
         self.opponent.set_fall_speed(lines, steps);
@@ -48,23 +340,41 @@ impl TetrisPair {
         self.opponent.set_drop_speed(lines, steps);
     }
 
+    #[deprecated(note = "use `set_delays` with `Delays::clear_animation`")]
+    #[allow(deprecated)]
     pub fn set_line_remove_speed(&mut self, lines: usize, steps: usize) {
         self.player.set_line_remove_speed(lines, steps);
         self.opponent.set_line_remove_speed(lines, steps);
     }
 
-    pub fn step(&mut self) -> (StepResult, StepResult) {
+    // Advance both games by one `TICK`
+    pub fn step(&mut self) -> (Vec<StepResult>, Vec<StepResult>) {
+        self.advance(TICK)
+    }
+
+    // Advance both games by the time elapsed since the previous call, see `Tetris::advance`
+    pub fn advance(&mut self, elapsed: Duration) -> (Vec<StepResult>, Vec<StepResult>) {
         self.step_player = false;
         self.step_opponent = false;
-        let step_result_player = self.player.step();
-        let step_result_opponent = self.opponent.step();
-        if step_result_player == StepResult::LineRemoved {
-            self.opponent.add_action(Action::BottomRefill);
+        if let Some(bot) = &mut self.bot_player {
+            bot.update(&mut self.player, elapsed);
         }
-        if step_result_opponent == StepResult::LineRemoved {
-            self.player.add_action(Action::BottomRefill);
+        if let Some(bot) = &mut self.bot_opponent {
+            bot.update(&mut self.opponent, elapsed);
         }
-        (step_result_player, step_result_opponent)
+        let step_results_player = self.player.advance(elapsed);
+        let step_results_opponent = self.opponent.advance(elapsed);
+        for _ in 0..removed_lines(&step_results_player) {
+            for _ in 0..self.garbage_opponent.step() {
+                self.opponent.add_action(Action::BottomRefill);
+            }
+        }
+        for _ in 0..removed_lines(&step_results_opponent) {
+            for _ in 0..self.garbage_player.step() {
+                self.player.add_action(Action::BottomRefill);
+            }
+        }
+        (step_results_player, step_results_opponent)
     }
 
     /// Use this method when players have different control loops
@@ -83,10 +393,44 @@ impl TetrisPair {
         self.step_divergence
     }
 
-    pub fn add_player_action(&mut self, player: PlayerSide, action: Action) {
+    // Returns false if the action is dropped, see `Tetris::add_action`
+    pub fn add_player_action(&mut self, player: PlayerSide, action: Action) -> bool {
+        self.get_mut(player).add_action(action)
+    }
+
+    // Key press or release of the player, see `Tetris::press_action`
+    pub fn player_key_event(&mut self, player: PlayerSide, event: KeyEvent) {
+        self.get_mut(player).key_event(event);
+    }
+
+    // Clear both wells and start a new game with the same settings.
+    // With shared seed the next game gets new seed derived from the previous one
+    pub fn reset(&mut self) {
+        self.player.reset();
+        self.opponent.reset();
+        if let Some(seed) = self.shared_seed {
+            self.set_shared_seed(Some(next_seed(seed)));
+        }
+        for garbage in [&mut self.garbage_player, &mut self.garbage_opponent] {
+            garbage.set(garbage.get_events(), garbage.get_steps());
+        }
+        self.step_player = false;
+        self.step_opponent = false;
+        self.step_divergence = 0;
+        self.paused_by_player = false;
+        self.paused_by_opponent = false;
+        for bot in [&mut self.bot_player, &mut self.bot_opponent]
+            .into_iter()
+            .flatten()
+        {
+            bot.reset();
+        }
+    }
+
+    pub fn is_player_game_over(&self, player: PlayerSide) -> bool {
         match player {
-            PlayerSide::Player => self.player.add_action(action),
-            PlayerSide::Opponent => self.opponent.add_action(action),
+            PlayerSide::Player => self.player.is_game_over(),
+            PlayerSide::Opponent => self.opponent.is_game_over(),
         }
     }
 
@@ -98,6 +442,8 @@ impl TetrisPair {
         TetrisPairState {
             player: self.player.get_state(),
             opponent: self.opponent.get_state(),
+            paused_by_player: self.paused_by_player,
+            paused_by_opponent: self.paused_by_opponent,
         }
     }
 }
@@ -0,0 +1,371 @@
+use std::{
+    io,
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bot::Bot,
+    clock::TICK,
+    piece_sequence::next_seed,
+    scoring::Score,
+    state::RoundOutcome,
+    tbp::TbpBot,
+    tetris_pair::{PlayerSettings, PlayerSide, TetrisPair},
+};
+
+// Creates a new bot for each game, given the seed of the game
+type BotFactory = Arc<dyn Fn(u64) -> io::Result<Box<dyn Bot>> + Send + Sync>;
+
+const START_RATING: f64 = 1500.0;
+// How much a single game changes the rating
+const RATING_K: f64 = 16.0;
+
+/// Round-robin of bots playing `TetrisPair` games against each other, headless and in parallel.
+/// Each pair of bots plays the same seeds, so both get the same tetrominos and garbage,
+/// and they switch sides every other game. Elo ratings are counted from the results
+/// in the order of the games, so they don't depend on which game finished first.
+pub struct Tournament {
+    entrants: Vec<(String, BotFactory)>,
+    settings: PlayerSettings,
+    seeds: Vec<u64>,
+    // Game is a draw if nobody loses by then, in game time
+    max_time: Duration,
+    threads: usize,
+}
+
+impl Tournament {
+    pub fn new(settings: PlayerSettings) -> Self {
+        Tournament {
+            entrants: Vec::new(),
+            settings,
+            seeds: Vec::new(),
+            max_time: Duration::from_secs(300),
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+        .with_games(10, 1)
+    }
+
+    // Register the bot under the name, names of the bots must differ
+    pub fn with_bot(
+        self,
+        name: &str,
+        factory: impl Fn(u64) -> Box<dyn Bot> + Send + Sync + 'static,
+    ) -> Result<Self, String> {
+        self.with_entrant(name, Arc::new(move |seed| Ok(factory(seed))))
+    }
+
+    // Register the external bot speaking the Tetris Bot Protocol, a new process plays each game
+    pub fn with_tbp(self, name: &str, program: &str, args: &[String]) -> Result<Self, String> {
+        let program = program.to_string();
+        let args = args.to_vec();
+        self.with_entrant(
+            name,
+            Arc::new(move |_| {
+                let mut command = Command::new(&program);
+                command.args(&args);
+                Ok(Box::new(TbpBot::spawn(command)?) as Box<dyn Bot>)
+            }),
+        )
+    }
+
+    fn with_entrant(mut self, name: &str, factory: BotFactory) -> Result<Self, String> {
+        if self.entrants.iter().any(|(entrant, _)| entrant == name) {
+            return Err(format!("bot {} is registered twice", name));
+        }
+        self.entrants.push((name.to_string(), factory));
+        Ok(self)
+    }
+
+    // Number of games for each pair of bots. Seeds of the games follow from the first one
+    pub fn with_games(mut self, games: usize, first_seed: u64) -> Self {
+        self.seeds = std::iter::successors(Some(first_seed), |&seed| Some(next_seed(seed)))
+            .take(games)
+            .collect();
+        self
+    }
+
+    pub fn with_max_time(mut self, max_time: Duration) -> Self {
+        self.max_time = max_time;
+        self
+    }
+
+    // Number of games played at once
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // Games of the round-robin as (player, opponent, seed)
+    fn schedule(&self) -> Vec<(usize, usize, u64)> {
+        let mut games = Vec::new();
+        for first in 0..self.entrants.len() {
+            for second in first + 1..self.entrants.len() {
+                for (n, &seed) in self.seeds.iter().enumerate() {
+                    if n % 2 == 0 {
+                        games.push((first, second, seed));
+                    } else {
+                        games.push((second, first, seed));
+                    }
+                }
+            }
+        }
+        games
+    }
+
+    // Play all games and rate the bots
+    pub fn run(&self) -> TournamentReport {
+        let schedule = self.schedule();
+        let next = AtomicUsize::new(0);
+        let records = Mutex::new(vec![None; schedule.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(schedule.len()) {
+                scope.spawn(|| loop {
+                    let n = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(player, opponent, seed)) = schedule.get(n) else {
+                        break;
+                    };
+                    let record = self.play(player, opponent, seed);
+                    records.lock().unwrap()[n] = Some(record);
+                });
+            }
+        });
+        let games: Vec<GameRecord> = records
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        TournamentReport::new(&self.entrants, games)
+    }
+
+    fn play(&self, player: usize, opponent: usize, seed: u64) -> GameRecord {
+        let mut record = GameRecord {
+            player: self.entrants[player].0.clone(),
+            opponent: self.entrants[opponent].0.clone(),
+            seed,
+            outcome: RoundOutcome::Draw,
+            time: Duration::ZERO,
+            player_score: Score::default(),
+            opponent_score: Score::default(),
+            error: None,
+        };
+        let bots = (self.entrants[player].1)(seed)
+            .and_then(|player| Ok((player, (self.entrants[opponent].1)(seed)?)));
+        let (player_bot, opponent_bot) = match bots {
+            Ok(bots) => bots,
+            Err(err) => {
+                record.error = Some(err.to_string());
+                return record;
+            }
+        };
+        let mut pair = TetrisPair::with_settings(&self.settings, &self.settings);
+        pair.set_countdown(0, Duration::ZERO);
+        // The seed gives the tetrominos and garbage, and think time jitter of the bots
+        pair.set_shared_seed(Some(seed));
+        pair.set_bot(PlayerSide::Player, Some(player_bot));
+        pair.set_bot(PlayerSide::Opponent, Some(opponent_bot));
+        while !pair.is_game_over() && record.time < self.max_time {
+            pair.step();
+            record.time += TICK;
+        }
+        record.outcome = match (
+            pair.is_player_game_over(PlayerSide::Player),
+            pair.is_player_game_over(PlayerSide::Opponent),
+        ) {
+            (true, false) => RoundOutcome::Winner(PlayerSide::Opponent),
+            (false, true) => RoundOutcome::Winner(PlayerSide::Player),
+            _ => RoundOutcome::Draw,
+        };
+        record.player_score = pair.get(PlayerSide::Player).score();
+        record.opponent_score = pair.get(PlayerSide::Opponent).score();
+        record
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub player: String,
+    pub opponent: String,
+    pub seed: u64,
+    // Draw if both lost on the same step or nobody lost in time
+    pub outcome: RoundOutcome,
+    // Game time
+    pub time: Duration,
+    pub player_score: Score,
+    pub opponent_score: Score,
+    // Set if a bot couldn't be started, the game isn't rated then
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BotStanding {
+    pub name: String,
+    pub rating: f64,
+    pub games: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    // Games not played because of an error
+    pub errors: usize,
+    pub pieces: usize,
+    pub lines: usize,
+    // Pieces per second of game time
+    pub pieces_per_second: f64,
+    pub lines_per_piece: f64,
+}
+
+/// Results of a `Tournament`: bots from the highest rating and all games in the order they were scheduled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TournamentReport {
+    pub standings: Vec<BotStanding>,
+    pub games: Vec<GameRecord>,
+}
+
+impl TournamentReport {
+    fn new(entrants: &[(String, BotFactory)], games: Vec<GameRecord>) -> Self {
+        let mut standings: Vec<BotStanding> = entrants
+            .iter()
+            .map(|(name, _)| BotStanding {
+                name: name.clone(),
+                rating: START_RATING,
+                games: 0,
+                wins: 0,
+                losses: 0,
+                draws: 0,
+                errors: 0,
+                pieces: 0,
+                lines: 0,
+                pieces_per_second: 0.0,
+                lines_per_piece: 0.0,
+            })
+            .collect();
+        let index = |name: &str| {
+            entrants
+                .iter()
+                .position(|(entrant, _)| entrant == name)
+                .expect("game of a registered bot")
+        };
+        // Total game time of each bot
+        let mut times = vec![Duration::ZERO; standings.len()];
+        for game in &games {
+            let player = index(&game.player);
+            let opponent = index(&game.opponent);
+            if game.error.is_some() {
+                standings[player].errors += 1;
+                standings[opponent].errors += 1;
+                continue;
+            }
+            // Result of the player: 1 for a win, 0.5 for a draw
+            let result = match game.outcome {
+                RoundOutcome::Winner(PlayerSide::Player) => 1.0,
+                RoundOutcome::Winner(PlayerSide::Opponent) => 0.0,
+                RoundOutcome::Draw => 0.5,
+            };
+            let expected = 1.0
+                / (1.0
+                    + 10f64.powf((standings[opponent].rating - standings[player].rating) / 400.0));
+            let change = RATING_K * (result - expected);
+            standings[player].rating += change;
+            standings[opponent].rating -= change;
+            for (side, index, score) in [
+                (PlayerSide::Player, player, &game.player_score),
+                (PlayerSide::Opponent, opponent, &game.opponent_score),
+            ] {
+                let standing = &mut standings[index];
+                standing.games += 1;
+                match game.outcome {
+                    RoundOutcome::Winner(winner) if winner == side => standing.wins += 1,
+                    RoundOutcome::Winner(_) => standing.losses += 1,
+                    RoundOutcome::Draw => standing.draws += 1,
+                }
+                standing.pieces += score.pieces;
+                standing.lines += score.lines;
+                times[index] += game.time;
+            }
+        }
+        for (standing, time) in standings.iter_mut().zip(times) {
+            if !time.is_zero() {
+                standing.pieces_per_second = standing.pieces as f64 / time.as_secs_f64();
+            }
+            if standing.pieces > 0 {
+                standing.lines_per_piece = standing.lines as f64 / standing.pieces as f64;
+            }
+        }
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        TournamentReport { standings, games }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    // Standings with a header line, one bot per line
+    pub fn standings_csv(&self) -> String {
+        let mut csv = String::from(
+            "rank,name,rating,games,wins,losses,draws,errors,pieces,lines,pieces_per_second,lines_per_piece\n",
+        );
+        for (rank, standing) in self.standings.iter().enumerate() {
+            csv += &format!(
+                "{},{},{:.1},{},{},{},{},{},{},{},{:.3},{:.3}\n",
+                rank + 1,
+                csv_field(&standing.name),
+                standing.rating,
+                standing.games,
+                standing.wins,
+                standing.losses,
+                standing.draws,
+                standing.errors,
+                standing.pieces,
+                standing.lines,
+                standing.pieces_per_second,
+                standing.lines_per_piece,
+            );
+        }
+        csv
+    }
+
+    // Games with a header line, one game per line. Winner is empty for a draw
+    pub fn games_csv(&self) -> String {
+        let mut csv = String::from(
+            "player,opponent,seed,winner,seconds,player_pieces,player_lines,opponent_pieces,opponent_lines,error\n",
+        );
+        for game in &self.games {
+            let winner = match game.outcome {
+                RoundOutcome::Winner(PlayerSide::Player) => game.player.as_str(),
+                RoundOutcome::Winner(PlayerSide::Opponent) => game.opponent.as_str(),
+                RoundOutcome::Draw => "",
+            };
+            csv += &format!(
+                "{},{},{},{},{:.2},{},{},{},{},{}\n",
+                csv_field(&game.player),
+                csv_field(&game.opponent),
+                game.seed,
+                csv_field(winner),
+                game.time.as_secs_f64(),
+                game.player_score.pieces,
+                game.player_score.lines,
+                game.opponent_score.pieces,
+                game.opponent_score.lines,
+                csv_field(game.error.as_deref().unwrap_or_default()),
+            );
+        }
+        csv
+    }
+}
+
+// Text is quoted if it has separators, quotes or line breaks, quotes are doubled then
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
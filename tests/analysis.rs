@@ -0,0 +1,96 @@
+use gametetris_rs::{Field, FieldAnalysis};
+
+// Holes at (1, 3) under one cell and at (2, 4) under three cells
+const STACK: &str = "
+    ......
+    ..#...
+    .##...
+    #.#..#
+    ##.#.#
+    ####.#
+";
+
+fn analyze(ascii: &str) -> FieldAnalysis {
+    FieldAnalysis::new(&Field::from_ascii(ascii).unwrap())
+}
+
+#[test]
+fn heights_and_cells() {
+    let analysis = analyze(STACK);
+    assert_eq!(analysis.column_heights(), [3, 4, 5, 2, 0, 3]);
+    assert_eq!(analysis.max_height(), 5);
+    assert_eq!(analysis.aggregate_height(), 17);
+    assert_eq!(analysis.row_cells(), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(analysis.filled_cells(), 15);
+    assert_eq!(analysis.full_rows(), 0);
+    assert_eq!(analysis.bumpiness(), 10);
+}
+
+#[test]
+fn holes_and_covered_cells() {
+    let analysis = analyze(STACK);
+    assert_eq!(analysis.holes(), 2);
+    assert_eq!(analysis.hole_positions(), [(1, 3), (2, 4)]);
+    assert_eq!(analysis.columns_with_holes(), 2);
+    assert_eq!(analysis.covered_cells(), 4);
+}
+
+#[test]
+fn transitions_count_walls_and_floor_as_filled() {
+    let analysis = analyze(STACK);
+    assert_eq!(analysis.row_transitions(), 20);
+    assert_eq!(analysis.column_transitions(), 10);
+
+    let empty = analyze("...\n...");
+    // Both walls of each row, the floor under each column
+    assert_eq!(empty.row_transitions(), 4);
+    assert_eq!(empty.column_transitions(), 3);
+}
+
+#[test]
+fn wells_are_measured_against_lower_neighbour() {
+    let analysis = analyze(STACK);
+    assert_eq!(analysis.well_depths(), [1, 0, 0, 0, 2, 0]);
+    assert_eq!(analysis.deepest_well(), Some((4, 2)));
+    assert_eq!(analysis.cumulative_wells(), 4);
+
+    // Walls are as high as the well
+    let flat = analyze("...\n###");
+    assert_eq!(flat.well_depths(), [0, 0, 0]);
+    assert_eq!(flat.deepest_well(), None);
+}
+
+#[test]
+fn parity_of_checkerboard_squares() {
+    assert_eq!(analyze(STACK).parity(), -3);
+    // O covers two squares of each color, T three of one color
+    assert_eq!(analyze("....\n.##.\n.##.").parity(), 0);
+    assert_eq!(analyze("....\n.#..\n###.").parity(), 2);
+}
+
+#[test]
+fn perfect_clear_needs_areas_of_four_cells() {
+    let t_slot = analyze(
+        "
+        ......
+        ......
+        ##...#
+        ###.##
+        ",
+    );
+    assert!(t_slot.perfect_clear_possible(2));
+    assert!(!t_slot.perfect_clear_possible(1), "stack is higher");
+    assert!(!t_slot.perfect_clear_possible(3), "10 empty cells");
+    assert!(t_slot.perfect_clear_possible(4));
+    assert!(!t_slot.perfect_clear_possible(0));
+    assert!(!t_slot.perfect_clear_possible(5));
+
+    // Four empty cells, but split into areas of three and one
+    let split = analyze(
+        "
+        #..##.
+        ##.###
+        ",
+    );
+    assert!(!split.perfect_clear_possible(2));
+}
@@ -0,0 +1,92 @@
+use gametetris_rs::{CellType, Field, Rotation, RuleSet, Tetris, TetrominoType};
+
+const FIELD: &str = "\
+..........
+.....*****
+I.........
+JJJL..OO..
+SSZZTTTGGG";
+
+#[test]
+fn field_round_trips_through_ascii() {
+    let field = Field::from_ascii(FIELD).unwrap();
+    assert_eq!((field.cols(), field.rows()), (10, 5));
+    assert_eq!(field.get_cell(0, 2), CellType::I);
+    assert_eq!(field.get_cell(5, 1), CellType::Blasted);
+    assert_eq!(field.get_cell(9, 4), CellType::Garbage);
+    assert_eq!(field.to_string(), FIELD);
+}
+
+#[test]
+fn field_ascii_ignores_indentation_and_blank_lines() {
+    let field = Field::from_ascii(
+        "
+
+        ....
+        #..G
+        ",
+    )
+    .unwrap();
+    assert_eq!(field.to_string(), "....\nG..G");
+}
+
+#[test]
+fn field_ascii_errors() {
+    assert_eq!(
+        Field::from_ascii("....\n...").unwrap_err(),
+        "row 2 has different width: ..."
+    );
+    assert_eq!(
+        Field::from_ascii("....\n..x.").unwrap_err(),
+        "unknown cell `x` in row 2"
+    );
+}
+
+#[test]
+fn tetris_round_trips_through_ascii() {
+    let ascii = "\
+..........
+....t.....
+...tt.....
+....t.....
+GGG...GGGG";
+    let game = Tetris::from_ascii(ascii, RuleSet::default()).unwrap();
+    let current = game.get_current().expect("active tetromino");
+    assert_eq!(current.tetromino_type(), TetrominoType::T);
+    assert_eq!(current.rotation(), Rotation::R270);
+    assert_eq!((current.x(), current.y()), (3, 1));
+    assert_eq!(game.to_ascii(), ascii);
+}
+
+fn tetris_error(ascii: &str) -> String {
+    Tetris::from_ascii(ascii, RuleSet::default())
+        .err()
+        .expect("position is rejected")
+}
+
+#[test]
+fn tetris_ascii_errors() {
+    assert_eq!(
+        tetris_error("tt..\n.ss."),
+        "active tetromino has cells of different types"
+    );
+    assert_eq!(
+        tetris_error("t.t.\n.t.."),
+        "marked cells don't form a tetromino"
+    );
+    assert_eq!(tetris_error("....\n..x."), "unknown tetromino `x` in row 2");
+}
+
+#[test]
+fn start_field_must_fit_the_well() {
+    let mut game = Tetris::with_rules(4, 2, RuleSet::default());
+    let field = Field::from_ascii("....\n....\nGG..").unwrap();
+    assert_eq!(
+        game.set_start_field(Some(field)).unwrap_err(),
+        "start field is 4x3, the well is 4x2"
+    );
+    assert_eq!(game.get_field().rows(), 2);
+    let field = Field::from_ascii("....\nGG..").unwrap();
+    assert!(game.set_start_field(Some(field.clone())).is_ok());
+    assert_eq!(game.get_field(), &field);
+}
@@ -0,0 +1,130 @@
+use gametetris_rs::{
+    Action, AutoRepeatSettings, Delays, Gravity, KeyEvent, RuleSet, StepResult, Tetris, TICK,
+};
+
+// O in the middle of a wide well
+const WELL: &str = "
+    .........oo.........
+    .........oo.........
+    ....................
+    ....................
+    ....................
+    ....................
+    ....................
+    ....................
+";
+
+// Timings are in ticks, the O doesn't fall by itself
+fn still_game(das: u32, arr: u32, dcd: u32, soft_drop_arr: u32) -> Tetris {
+    let rules = RuleSet {
+        auto_repeat: AutoRepeatSettings {
+            das,
+            arr,
+            dcd,
+            soft_drop_arr,
+        },
+        delays: Delays::ZERO,
+        ..RuleSet::guideline()
+    };
+    let mut game = Tetris::from_ascii(WELL, rules).unwrap();
+    game.set_seed(1);
+    game.set_gravity(Gravity::RowsPerSecond(0.0));
+    game
+}
+
+// Step the game `ticks` times and return the tick (counted from 1) of each successful
+// `action`. The tick is repeated if the tetromino moved more than once in it
+fn move_ticks(game: &mut Tetris, action: Action, ticks: usize) -> Vec<usize> {
+    let mut moves = Vec::new();
+    for tick in 1..=ticks {
+        for result in game.step() {
+            if result == StepResult::ActionPerformed(action, true) {
+                moves.push(tick);
+            }
+        }
+    }
+    moves
+}
+
+// Lock the O and hold left during entry delay of 10 ticks, the next tetromino appears in tick 10
+fn hold_left_during_are(game: &mut Tetris) {
+    game.set_delays(Delays {
+        are: 10,
+        ..Delays::ZERO
+    });
+    game.add_action(Action::Drop);
+    game.step();
+    assert!(game.get_current().is_none());
+    game.key_event(KeyEvent::Press(Action::MoveLeft));
+}
+
+#[test]
+fn held_key_repeats_after_das_every_arr() {
+    let mut game = still_game(5, 2, 0, 2);
+    game.key_event(KeyEvent::Press(Action::MoveLeft));
+    // The press moves at once, repeating starts when DAS is charged
+    assert_eq!(
+        move_ticks(&mut game, Action::MoveLeft, 12),
+        [1, 5, 7, 9, 11]
+    );
+    game.key_event(KeyEvent::Release(Action::MoveLeft));
+    assert!(move_ticks(&mut game, Action::MoveLeft, 10).is_empty());
+}
+
+#[test]
+fn zero_arr_slides_to_the_wall() {
+    let mut game = still_game(5, 0, 0, 2);
+    game.key_event(KeyEvent::Press(Action::MoveLeft));
+    assert_eq!(
+        move_ticks(&mut game, Action::MoveLeft, 8),
+        [1, 5, 5, 5, 5, 5, 5, 5, 5]
+    );
+    assert_eq!(game.get_current().unwrap().x(), 0);
+}
+
+#[test]
+fn soft_drop_repeats_every_soft_drop_arr() {
+    let mut game = still_game(5, 2, 0, 2);
+    game.key_event(KeyEvent::Press(Action::MoveDown));
+    // Soft drop has no DAS
+    assert_eq!(move_ticks(&mut game, Action::MoveDown, 8), [1, 2, 4, 6, 8]);
+
+    let mut game = still_game(5, 2, 0, 0);
+    game.key_event(KeyEvent::Press(Action::MoveDown));
+    assert_eq!(move_ticks(&mut game, Action::MoveDown, 2), [1; 6]);
+    assert_eq!(game.get_current().unwrap().y(), 6);
+}
+
+#[test]
+fn das_charges_during_are() {
+    let mut game = still_game(5, 2, 0, 2);
+    hold_left_during_are(&mut game);
+    // The press is performed by the new tetromino, DAS is charged already, so it repeats
+    // from the next tick on
+    assert_eq!(
+        move_ticks(&mut game, Action::MoveLeft, 16),
+        [10, 11, 12, 14, 16]
+    );
+}
+
+#[test]
+fn dcd_suspends_repeating_after_spawn() {
+    let mut game = still_game(5, 2, 4, 2);
+    hold_left_during_are(&mut game);
+    assert_eq!(
+        move_ticks(&mut game, Action::MoveLeft, 20),
+        [10, 14, 16, 18, 20]
+    );
+}
+
+#[test]
+fn das_charges_during_countdown() {
+    let mut game = still_game(5, 2, 0, 2);
+    game.start_countdown(1, TICK * 10);
+    // The press itself is ignored during countdown
+    game.key_event(KeyEvent::Press(Action::MoveLeft));
+    assert_eq!(
+        move_ticks(&mut game, Action::MoveLeft, 16),
+        [11, 12, 14, 16]
+    );
+}
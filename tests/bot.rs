@@ -0,0 +1,23 @@
+use gametetris_rs::{BotDifficulty, HeuristicBot, PlayerSide, TetrisPair};
+
+// Well of the bot after it played the given number of steps with the shared seed
+fn play(seed: u64, steps: usize) -> String {
+    let mut pair = TetrisPair::new(10, 20);
+    pair.set_shared_seed(Some(seed));
+    let bot = HeuristicBot::default()
+        .with_difficulty(&BotDifficulty::beginner())
+        .with_seed(seed);
+    pair.set_bot(PlayerSide::Opponent, Some(Box::new(bot)));
+    pair.start_countdown();
+    for _ in 0..steps {
+        pair.step();
+    }
+    pair.get(PlayerSide::Opponent).to_ascii()
+}
+
+#[test]
+fn paced_bot_plays_the_same_with_the_same_seed() {
+    let well = play(7, 2000);
+    assert!(well.chars().any(|c| c != '.' && c != '\n'));
+    assert_eq!(play(7, 2000), well);
+}
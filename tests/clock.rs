@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use gametetris_rs::{Clock, FakeClock, Gravity, RuleSet, StepResult, Tetris};
+
+const STEPS: [Duration; 3] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+];
+
+// Advance the game by the time the fake clock moved, as a game loop with the given frame time would.
+// Returns the time when the first tetromino locked, if it did
+fn run(game: &mut Tetris, step: Duration, duration: Duration) -> Option<Duration> {
+    let clock = FakeClock::new();
+    let mut last = clock.now();
+    let mut locked = None;
+    while clock.now() < duration {
+        clock.advance(step);
+        let results = game.advance(clock.now() - last);
+        last = clock.now();
+        if locked.is_none()
+            && results
+                .iter()
+                .any(|result| matches!(result, StepResult::Locked(_)))
+        {
+            locked = Some(clock.now());
+        }
+    }
+    locked
+}
+
+fn game(rules: RuleSet, gravity: Gravity) -> Tetris {
+    let mut game = Tetris::with_rules(10, 20, rules);
+    game.set_seed(1);
+    game.set_gravity(gravity);
+    game
+}
+
+#[test]
+fn gravity_does_not_depend_on_frame_rate() {
+    for step in STEPS {
+        let mut game = game(RuleSet::default(), Gravity::RowsPerSecond(5.0));
+        run(&mut game, step, Duration::from_millis(1100));
+        let current = game.get_current().expect("tetromino is falling");
+        assert_eq!(current.y(), 5, "frame time {:?}", step);
+    }
+}
+
+#[test]
+fn g_is_rows_per_frame_at_60_fps() {
+    for step in STEPS {
+        let mut game = game(RuleSet::default(), Gravity::G(0.1));
+        run(&mut game, step, Duration::from_millis(1050));
+        let current = game.get_current().expect("tetromino is falling");
+        assert_eq!(current.y(), 6, "frame time {:?}", step);
+    }
+}
+
+#[test]
+fn lock_delay_does_not_depend_on_frame_rate() {
+    let rules = RuleSet::guideline();
+    let lock_delay = rules.lock_delay.duration();
+    for step in STEPS {
+        // 20G crosses the well in less than 20ms, then the tetromino locks after lock delay
+        let mut game = game(rules.clone(), Gravity::G(20.0));
+        let locked = run(&mut game, step, Duration::from_secs(1)).expect("tetromino locked");
+        let fall = Duration::from_millis(20);
+        assert!(
+            locked >= lock_delay && locked <= lock_delay + fall + step * 2,
+            "locked at {:?} with frame time {:?}",
+            locked,
+            step
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use gametetris_rs::{
+    Action, CoopTetris, Delays, Gravity, PlayerSide, Rotation, RuleSet, StepResult, TetrominoType,
+};
+
+#[test]
+fn all_queued_actions_are_applied_in_one_tick() {
+    let mut game = CoopTetris::new(5, 20);
+    game.step();
+    assert!(game.get_current(PlayerSide::Player).is_some());
+    let actions = [Action::MoveRight, Action::MoveLeft, Action::MoveLeft];
+    for action in actions {
+        assert!(game.add_player_action(PlayerSide::Player, action));
+    }
+    let (player, opponent) = game.step();
+    let performed: Vec<Action> = player
+        .iter()
+        .filter_map(|result| match result {
+            StepResult::ActionPerformed(action, true) if *action != Action::MoveDown => {
+                Some(*action)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(performed, actions);
+    assert!(opponent
+        .iter()
+        .all(|result| matches!(result, StepResult::ActionPerformed(Action::MoveDown, _))));
+}
+
+// Co-op game where tetrominos don't fall by themselves and each player gets only `player`
+// and `opponent` tetrominos
+fn still_game(
+    cols_per_player: usize,
+    rules: RuleSet,
+    player: TetrominoType,
+    opponent: TetrominoType,
+) -> CoopTetris {
+    let mut game = CoopTetris::with_rules(cols_per_player, 20, rules);
+    game.set_gravity(Gravity::from_ticks(0, 1));
+    game.set_fixed_queue(PlayerSide::Player, Some(vec![player; 10]));
+    game.set_fixed_queue(PlayerSide::Opponent, Some(vec![opponent; 10]));
+    game.step();
+    game
+}
+
+fn step_with(
+    game: &mut CoopTetris,
+    player: &[Action],
+    opponent: &[Action],
+) -> (Vec<StepResult>, Vec<StepResult>) {
+    for action in player {
+        game.add_player_action(PlayerSide::Player, *action);
+    }
+    for action in opponent {
+        game.add_player_action(PlayerSide::Opponent, *action);
+    }
+    game.step()
+}
+
+fn performed(results: &[StepResult]) -> Vec<bool> {
+    results
+        .iter()
+        .filter_map(|result| match result {
+            StepResult::ActionPerformed(_, succeed) => Some(*succeed),
+            _ => None,
+        })
+        .collect()
+}
+
+fn cells(game: &CoopTetris, side: PlayerSide) -> Vec<(isize, isize)> {
+    game.get_current(side).unwrap().cells()
+}
+
+#[test]
+fn tetrominos_block_each_other_on_moves() {
+    let mut game = still_game(5, RuleSet::guideline(), TetrominoType::O, TetrominoType::O);
+    assert_eq!(cells(&game, PlayerSide::Player)[0], (6, 0));
+    assert_eq!(cells(&game, PlayerSide::Opponent)[0], (1, 0));
+    let (player, opponent) = step_with(&mut game, &[Action::MoveLeft; 4], &[Action::MoveRight]);
+    assert_eq!(performed(&player), [true, true, true, false]);
+    assert_eq!(performed(&opponent), [false]);
+    assert_eq!(cells(&game, PlayerSide::Player)[0], (3, 0));
+    assert_eq!(cells(&game, PlayerSide::Opponent)[0], (1, 0));
+}
+
+#[test]
+fn partner_tetromino_blocks_rotation() {
+    // No kicks in NES rules, so the rotation either fits or fails
+    let mut game = still_game(5, RuleSet::nes(), TetrominoType::I, TetrominoType::O);
+    let mut opponent = vec![Action::MoveDown; 5];
+    opponent.extend([Action::MoveRight; 4]);
+    step_with(&mut game, &[Action::MoveDown; 3], &opponent);
+    // The vertical I would take the column the partner's O is in
+    let (player, _) = step_with(&mut game, &[Action::RotateRight], &[Action::MoveLeft]);
+    assert_eq!(performed(&player), [false]);
+    let (player, _) = step_with(&mut game, &[Action::RotateRight], &[]);
+    assert_eq!(performed(&player), [true]);
+    assert_eq!(
+        cells(&game, PlayerSide::Player),
+        [(5, 3), (5, 4), (5, 5), (5, 6)]
+    );
+}
+
+#[test]
+fn rotation_kicks_off_partner_tetromino() {
+    let mut game = still_game(5, RuleSet::guideline(), TetrominoType::T, TetrominoType::O);
+    let mut opponent = vec![Action::MoveDown; 3];
+    opponent.extend([Action::MoveRight; 4]);
+    step_with(&mut game, &[Action::MoveDown], &opponent);
+    // In place the T would overlap the partner's O, the first kick moves it to the right
+    let (player, _) = step_with(&mut game, &[Action::RotateLeft], &[]);
+    assert_eq!(performed(&player), [true]);
+    let current = game.get_current(PlayerSide::Player).unwrap();
+    assert_eq!(
+        (current.rotation(), current.x(), current.y()),
+        (Rotation::R270, 6, 1)
+    );
+}
+
+#[test]
+fn spawn_waits_until_partner_leaves_spawn_position() {
+    let mut game = still_game(5, RuleSet::guideline(), TetrominoType::O, TetrominoType::O);
+    game.set_delays(Delays {
+        are: 5,
+        ..Delays::ZERO
+    });
+    step_with(&mut game, &[Action::Drop], &[Action::MoveRight; 5]);
+    assert!(game.get_current(PlayerSide::Player).is_none());
+    assert_eq!(cells(&game, PlayerSide::Opponent)[0], (6, 0));
+    game.advance(Duration::from_millis(100));
+    assert!(game.get_current(PlayerSide::Player).is_none());
+    assert!(!game.is_game_over());
+    step_with(&mut game, &[], &[Action::MoveLeft; 5]);
+    game.step();
+    assert_eq!(cells(&game, PlayerSide::Player)[0], (6, 0));
+}
+
+#[test]
+fn lock_delay_starts_on_partner_tetromino() {
+    let mut game = still_game(5, RuleSet::guideline(), TetrominoType::O, TetrominoType::O);
+    step_with(&mut game, &[], &[Action::MoveDown; 20]);
+    let mut player = vec![Action::MoveLeft; 4];
+    player.extend([Action::MoveDown; 20]);
+    step_with(&mut game, &player, &[]);
+    // The player's O rests on the partner's one, both lock delays are running
+    assert_eq!(cells(&game, PlayerSide::Player)[0], (2, 16));
+    let locked = |results: &[StepResult]| {
+        results
+            .iter()
+            .any(|result| matches!(result, StepResult::Locked(_)))
+    };
+    let mut locks = (None, None);
+    for tick in 0..100 {
+        let (player, opponent) = game.step();
+        if locked(&player) {
+            locks.0.get_or_insert(tick);
+        }
+        if locked(&opponent) {
+            locks.1.get_or_insert(tick);
+        }
+    }
+    let (Some(player), Some(opponent)) = locks else {
+        panic!("both tetrominos lock: {:?}", locks);
+    };
+    assert_eq!(player, opponent + 1);
+}
+
+#[test]
+fn line_clear_moves_partner_tetromino_down() {
+    let mut game = still_game(4, RuleSet::guideline(), TetrominoType::I, TetrominoType::I);
+    game.set_delays(Delays::ZERO);
+    let (player, opponent) = step_with(&mut game, &[Action::Drop], &[Action::Drop]);
+    assert!(player.contains(&StepResult::LinesRemoved(1)));
+    assert!(opponent.contains(&StepResult::LinesRemoved(1)));
+    // The player's next I appeared before the opponent's I completed the line
+    assert_eq!(game.get_current(PlayerSide::Player).unwrap().y(), 1);
+    assert_eq!(game.get_current(PlayerSide::Opponent).unwrap().y(), 0);
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use gametetris_rs::{
+    Action, Clock, CoopTetris, Delays, FakeClock, Gravity, PlayerSide, RuleSet, StepResult, Tetris,
+    TetrominoType,
+};
+
+// I tetromino hanging over the hole of a row missing four cells
+const WELL: &str = "
+    ...iiii...
+    ..........
+    ..........
+    ..........
+    GGG....GGG
+";
+
+const STEPS: [Duration; 3] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+];
+
+// 10ms of clear animation and 10ms of entry delay after it, so the next tetromino appears at 20ms
+const SHORT_DELAYS: Delays = Delays {
+    are: 0,
+    line_clear_are: 1,
+    clear_animation: 1,
+};
+
+// Advance by the time the fake clock moved, as a game loop with the given frame time would
+fn run(step: Duration, duration: Duration, mut advance: impl FnMut(Duration)) {
+    let clock = FakeClock::new();
+    let mut last = clock.now();
+    while clock.now() < duration {
+        clock.advance(step);
+        advance(clock.now() - last);
+        last = clock.now();
+    }
+}
+
+#[test]
+fn lines_collapse_in_the_same_step_without_clear_animation() {
+    let rules = RuleSet {
+        delays: Delays::ZERO,
+        ..RuleSet::guideline()
+    };
+    let mut game = Tetris::from_ascii(WELL, rules).unwrap();
+    game.set_seed(1);
+    game.add_action(Action::Drop);
+    let results = game.advance(Duration::from_millis(1));
+    let locked = results
+        .iter()
+        .position(|result| matches!(result, StepResult::Locked(lock) if lock.lines == 1))
+        .expect("tetromino locked");
+    assert_eq!(results.get(locked + 1), Some(&StepResult::LinesRemoved(1)));
+    assert!(game.get_current().is_some(), "next tetromino is placed");
+    assert!(!game.to_ascii().contains('G'), "garbage row is removed");
+}
+
+#[test]
+fn lines_collapse_after_clear_animation() {
+    let rules = RuleSet {
+        delays: Delays {
+            clear_animation: 10,
+            ..Delays::ZERO
+        },
+        ..RuleSet::guideline()
+    };
+    let mut game = Tetris::from_ascii(WELL, rules).unwrap();
+    game.set_seed(1);
+    game.add_action(Action::Drop);
+    let results = game.advance(Duration::from_millis(1));
+    assert!(!results.contains(&StepResult::LinesRemoved(1)));
+    assert!(game.get_current().is_none());
+    let results = game.advance(Duration::from_millis(100));
+    assert!(results.contains(&StepResult::LinesRemoved(1)));
+    assert!(game.get_current().is_some(), "next tetromino is placed");
+}
+
+#[test]
+fn time_left_after_delays_goes_to_the_next_tetromino() {
+    let mut well = String::from("...iiii...\n");
+    well.push_str(&"..........\n".repeat(22));
+    well.push_str("GGG....GGG\n");
+    for step in STEPS {
+        let rules = RuleSet {
+            delays: SHORT_DELAYS,
+            ..RuleSet::guideline()
+        };
+        let mut game = Tetris::from_ascii(&well, rules).unwrap();
+        game.set_seed(1);
+        // One row every 30ms
+        game.set_gravity(Gravity::from_ticks(1, 3));
+        game.add_action(Action::Drop);
+        game.advance(Duration::ZERO);
+        run(step, Duration::from_millis(450), |elapsed| {
+            game.advance(elapsed);
+        });
+        // The tetromino appeared at 20ms and fell for 430ms
+        let current = game.get_current().expect("tetromino is falling");
+        assert_eq!(current.y(), 14, "frame time {:?}", step);
+    }
+}
+
+#[test]
+fn coop_time_left_after_delays_goes_to_the_players() {
+    for step in STEPS {
+        let mut game = CoopTetris::with_rules(4, 24, RuleSet::guideline());
+        game.set_delays(SHORT_DELAYS);
+        game.set_gravity(Gravity::from_ticks(1, 3));
+        for side in [PlayerSide::Player, PlayerSide::Opponent] {
+            game.set_fixed_queue(side, Some(vec![TetrominoType::I; 10]));
+        }
+        game.advance(Duration::ZERO);
+        // Both I tetrominos drop and fill the bottom row, the player's next one appears at once
+        for side in [PlayerSide::Player, PlayerSide::Opponent] {
+            game.add_player_action(side, Action::Drop);
+        }
+        game.advance(Duration::ZERO);
+        run(step, Duration::from_millis(450), |elapsed| {
+            game.advance(elapsed);
+        });
+        // The player's tetromino waited for clear animation, went down with the removed line
+        // and fell for 440ms, the opponent's one appeared at 20ms and fell for 430ms
+        let y = |side| game.get_current(side).expect("tetromino is falling").y();
+        assert_eq!(
+            (y(PlayerSide::Player), y(PlayerSide::Opponent)),
+            (15, 14),
+            "frame time {:?}",
+            step
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use gametetris_rs::{
+    Action, CellType, Field, Fumen, FumenPage, FumenRecorder, Puzzle, PuzzleGoal, Rotation,
+    RuleSet, Tetris, Tetromino, TetrominoType, FUMEN_ROWS,
+};
+
+const BOARD: &str = "
+    T.........
+    TT.....OOZ
+    T.JJ.IIIIZ
+    SSJ#GGGGZZ
+";
+
+// Fumen of `BOARD`
+const BOARD_FUMEN: &str = "v115@9gwwIexwEeRpAtwwAeh0AezhAtR4g0E8BtJeAgH";
+
+fn board_page() -> FumenPage {
+    FumenPage::new(&Field::from_ascii(BOARD).unwrap()).unwrap()
+}
+
+#[test]
+fn empty_fumen_is_decoded_and_encoded_back() {
+    let fumen = Fumen::decode("https://fumen.zui.jp/?v115@vhAAgH").unwrap();
+    assert_eq!(fumen.pages.len(), 1);
+    assert_eq!(fumen.pages[0], FumenPage::new(&Field::new(10, 20)).unwrap());
+    assert_eq!(fumen.encode(), "v115@vhAAgH");
+}
+
+#[test]
+fn field_round_trips_through_fumen() {
+    let fumen = Fumen::from_field(&Field::from_ascii(BOARD).unwrap()).unwrap();
+    assert_eq!(fumen.encode(), BOARD_FUMEN);
+    let decoded = Fumen::decode(BOARD_FUMEN).unwrap();
+    assert_eq!(decoded, fumen);
+    assert_eq!(
+        decoded.pages[0].field_with_rows(4).to_string(),
+        Field::from_ascii(BOARD).unwrap().to_string()
+    );
+}
+
+#[test]
+fn pages_round_trip_through_fumen() {
+    let mut first = board_page();
+    first.tetromino = Some(Tetromino::new(
+        TetrominoType::L,
+        Rotation::R90,
+        0,
+        FUMEN_ROWS as isize - 6,
+    ));
+    first.comment = Some("T-spin double! 100% {ok}".to_string());
+    first.garbage[3] = CellType::Garbage;
+    // Same field and comment, stored with the repeat counter
+    let mut second = board_page();
+    second.lock = false;
+    second.comment = first.comment.clone();
+    let mut third = board_page();
+    third.tetromino = Some(Tetromino::new(
+        TetrominoType::I,
+        Rotation::R0,
+        4,
+        FUMEN_ROWS as isize - 8,
+    ));
+    third.rise = true;
+    third.mirror = true;
+    let fumen = Fumen {
+        pages: vec![first, second, third],
+    };
+    assert_eq!(Fumen::decode(&fumen.encode()).unwrap(), fumen);
+}
+
+#[test]
+fn recorded_game_round_trips_through_fumen() {
+    let mut game = Tetris::from_ascii(
+        "
+        ....oo....
+        ....oo....
+        ..........
+        ..........
+        ..........
+        GGGG..GGGG
+        ",
+        RuleSet::guideline(),
+    )
+    .unwrap();
+    game.set_seed(1);
+    let mut recorder = FumenRecorder::new(&game);
+    game.add_action(Action::Drop);
+    let results = game.advance(Duration::from_millis(1));
+    recorder.record(&game, &results).unwrap();
+    let fumen = recorder.to_fumen().unwrap();
+    assert_eq!(fumen.pages.len(), 2);
+    let tetromino = fumen.pages[0].tetromino.expect("locked tetromino");
+    assert_eq!(tetromino.tetromino_type(), TetrominoType::O);
+    assert_eq!(tetromino.y(), FUMEN_ROWS as isize - 2);
+    assert_eq!(
+        fumen.pages[1].field_with_rows(2).to_string(),
+        "..........\n....OO...."
+    );
+    assert_eq!(Fumen::decode(&fumen.encode()).unwrap(), fumen);
+}
+
+#[test]
+fn puzzle_is_imported_from_fumen() {
+    let text = format!("goal: clear-lines 2\npieces: T L\nfumen: {}", BOARD_FUMEN);
+    let puzzle = Puzzle::from_text(&text).unwrap();
+    let board = Field::from_ascii(BOARD).unwrap().to_string();
+    assert_eq!(puzzle.board.join("\n"), board);
+    assert_eq!(puzzle.pieces, [TetrominoType::T, TetrominoType::L]);
+    assert_eq!(puzzle.goal, PuzzleGoal::ClearLines(2));
+}
@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use gametetris_rs::{
+    Action, CellType, GameMode, LevelUp, ModeResult, RuleSet, SinglePlayer, StepResult, Tetris,
+    TetrominoType, TICK,
+};
+
+// Game of the mode in a 4 wide well, where each flat I clears a line, after the countdown
+fn started(mode: GameMode, rules: RuleSet) -> SinglePlayer {
+    let mut tetris = Tetris::with_rules(4, 8, rules);
+    tetris.set_seed(1);
+    tetris.set_fixed_queue(Some(vec![TetrominoType::I; 20]));
+    let mut game = SinglePlayer::new(tetris, mode);
+    while game.tetris().countdown().is_some() {
+        game.step();
+    }
+    assert_eq!(game.time(), Duration::ZERO);
+    game
+}
+
+// Hard drop the current tetromino, then step until the next one appears.
+// Returns the number of steps
+fn drop_tetromino(game: &mut SinglePlayer) -> u32 {
+    assert!(game.add_action(Action::Drop));
+    let mut steps = 0;
+    let mut locked = false;
+    while !locked || (game.tetris().get_current().is_none() && game.result().is_none()) {
+        locked |= game
+            .step()
+            .iter()
+            .any(|result| matches!(result, StepResult::Locked(_)));
+        steps += 1;
+    }
+    steps
+}
+
+#[test]
+fn sprint_ends_at_the_lines() {
+    let mut game = started(GameMode::Sprint { lines: 3 }, RuleSet::guideline());
+    let mut steps = 0;
+    for lines in 1..3 {
+        steps += drop_tetromino(&mut game);
+        assert_eq!(game.tetris().score().lines, lines);
+        assert_eq!(game.result(), None);
+    }
+    steps += drop_tetromino(&mut game);
+    let time = game.time();
+    assert_eq!(time, TICK * steps);
+    assert_eq!(
+        game.result(),
+        Some(ModeResult::Sprint {
+            completed: true,
+            time,
+            lines: 3,
+            pieces: 3,
+        })
+    );
+    // Game stops with the result
+    game.advance(Duration::from_secs(1));
+    assert_eq!(game.time(), time);
+    assert!(!game.add_action(Action::Drop));
+}
+
+#[test]
+fn ultra_time_is_clamped_to_the_limit() {
+    let time_limit = Duration::from_secs(1);
+    let mut game = started(GameMode::Ultra { time_limit }, RuleSet::guideline());
+    drop_tetromino(&mut game);
+    game.advance(time_limit - game.time() - TICK);
+    assert_eq!(game.result(), None);
+    game.advance(TICK * 5);
+    assert_eq!(game.time(), time_limit);
+    let score = game.tetris().score();
+    assert_eq!(score.lines, 1);
+    assert_eq!(
+        game.result(),
+        Some(ModeResult::Ultra {
+            completed: true,
+            score,
+            time: time_limit,
+        })
+    );
+}
+
+// Level up with each line, up to level 2 until 3 lines are cleared
+fn marathon(endless: bool) -> SinglePlayer {
+    let rules = RuleSet {
+        level_up: LevelUp::Lines(1),
+        start_level: 1,
+        ..RuleSet::guideline()
+    };
+    let mode = GameMode::Marathon {
+        lines: 3,
+        max_level: 2,
+        endless,
+    };
+    started(mode, rules)
+}
+
+#[test]
+fn marathon_is_cleared_at_the_lines() {
+    let mut game = marathon(false);
+    drop_tetromino(&mut game);
+    drop_tetromino(&mut game);
+    assert_eq!(game.tetris().score().level, 2);
+    assert_eq!(game.result(), None);
+    drop_tetromino(&mut game);
+    let score = game.tetris().score();
+    assert_eq!((score.lines, score.level), (3, 2));
+    assert_eq!(
+        game.result(),
+        Some(ModeResult::Marathon {
+            cleared: true,
+            score,
+            time: game.time(),
+        })
+    );
+}
+
+#[test]
+fn endless_marathon_lifts_the_level_cap_after_the_lines() {
+    let mut game = marathon(true);
+    for _ in 0..3 {
+        drop_tetromino(&mut game);
+    }
+    assert_eq!(game.tetris().score().level, 2);
+    assert_eq!(game.result(), None);
+    drop_tetromino(&mut game);
+    assert_eq!(game.tetris().score().level, 5);
+    // Game is over when the queue is used up
+    while game.result().is_none() {
+        drop_tetromino(&mut game);
+    }
+    assert!(game.tetris().is_game_over());
+    assert_eq!(
+        game.result(),
+        Some(ModeResult::Marathon {
+            cleared: true,
+            score: game.tetris().score(),
+            time: game.time(),
+        })
+    );
+}
+
+// Move the current tetromino to the first placement filling cells of the column and lock it
+fn lock_in_column(game: &mut SinglePlayer, x: isize) {
+    while game.tetris().get_current().is_none() {
+        game.step();
+    }
+    let placement = game
+        .tetris()
+        .placements()
+        .into_iter()
+        .find(|placement| placement.tetromino.cells().iter().all(|cell| cell.0 == x))
+        .expect("placement in the column");
+    for action in game.tetris().path_to(&placement).unwrap() {
+        assert!(game.add_action(action));
+    }
+    let pieces = game.tetris().score().pieces;
+    while game.result().is_none()
+        && (game.tetris().score().pieces == pieces || game.tetris().get_current().is_none())
+    {
+        game.step();
+    }
+}
+
+#[test]
+fn dig_is_completed_when_garbage_is_cleared() {
+    let mode = GameMode::Dig {
+        rows: 1,
+        rise_every: None,
+    };
+    let mut game = started(mode, RuleSet::guideline());
+    let field = game.tetris().get_field();
+    assert_eq!(field.garbage_lines(), 1);
+    let bottom = field.rows() - 1;
+    let holes: Vec<isize> = (0..field.cols())
+        .filter(|&x| field.get_cell(x, bottom) == CellType::Empty)
+        .map(|x| x as isize)
+        .collect();
+    assert!(!holes.is_empty());
+    for &x in &holes {
+        assert_eq!(game.result(), None);
+        lock_in_column(&mut game, x);
+    }
+    assert_eq!(
+        game.result(),
+        Some(ModeResult::Dig {
+            completed: true,
+            time: game.time(),
+            pieces: holes.len(),
+            garbage_left: 0,
+        })
+    );
+}
+
+#[test]
+fn rising_garbage_is_not_dropped_with_full_queue() {
+    let mut rules = RuleSet::guideline();
+    rules.input_policy.max_queue = Some(1);
+    let mode = GameMode::Dig {
+        rows: 1,
+        rise_every: Some(Duration::from_millis(100)),
+    };
+    let mut game = started(mode, rules);
+    assert!(game.add_action(Action::MoveRight));
+    assert!(!game.add_action(Action::MoveLeft));
+    game.advance(Duration::from_millis(100));
+    assert_eq!(game.tetris().get_field().garbage_lines(), 2);
+    // Each full period of the elapsed time raises a row
+    game.advance(Duration::from_millis(250));
+    assert_eq!(game.tetris().get_field().garbage_lines(), 4);
+    game.advance(Duration::from_millis(50));
+    assert_eq!(game.tetris().get_field().garbage_lines(), 5);
+    assert_eq!(game.result(), None);
+}
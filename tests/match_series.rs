@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use gametetris_rs::{
+    Action, Field, MatchCommand, MatchPhase, MatchSeries, PlayerSettings, PlayerSide, RoundOutcome,
+    RuleSet, TetrisPair, TetrominoType,
+};
+
+const ROUND_BREAK: Duration = Duration::from_millis(500);
+
+// Series in wells two O high without countdown, so that two hard drops top out the well
+fn series(best_of: usize) -> MatchSeries {
+    let settings = PlayerSettings::with_rules(4, 4, RuleSet::guideline());
+    let mut pair = TetrisPair::with_settings(&settings, &settings);
+    pair.set_shared_seed(Some(1));
+    for side in [PlayerSide::Player, PlayerSide::Opponent] {
+        pair.get_mut(side)
+            .set_fixed_queue(Some(vec![TetrominoType::O; 10]));
+    }
+    pair.set_countdown(0, Duration::ZERO);
+    let mut series = MatchSeries::new(pair, best_of);
+    series.set_round_break(ROUND_BREAK);
+    series
+}
+
+// Hard drop tetrominos of the sides until the round is over
+fn top_out(series: &mut MatchSeries, sides: &[PlayerSide]) {
+    for _ in 0..100 {
+        for &side in sides {
+            series.add_player_action(side, Action::Drop);
+        }
+        series.step();
+        if series.phase() != MatchPhase::Playing {
+            return;
+        }
+    }
+    panic!("round isn't over");
+}
+
+fn next_round(series: &mut MatchSeries) {
+    series.advance(ROUND_BREAK);
+    assert_eq!(series.phase(), MatchPhase::Playing);
+}
+
+#[test]
+fn series_is_won_by_majority_of_rounds() {
+    let mut series = series(3);
+    assert_eq!(series.wins_needed(), 2);
+
+    top_out(&mut series, &[PlayerSide::Player]);
+    assert_eq!(series.phase(), MatchPhase::RoundOver);
+    assert_eq!(
+        series.last_round(),
+        Some(RoundOutcome::Winner(PlayerSide::Opponent))
+    );
+    assert_eq!(series.rounds()[0].seed, Some(1));
+    next_round(&mut series);
+    assert_eq!(series.round(), 2);
+
+    top_out(&mut series, &[PlayerSide::Opponent]);
+    next_round(&mut series);
+    assert_eq!(series.round(), 3);
+    assert_eq!(series.wins(PlayerSide::Player), 1);
+    assert_eq!(series.wins(PlayerSide::Opponent), 1);
+    assert_eq!(series.winner(), None);
+
+    top_out(&mut series, &[PlayerSide::Opponent]);
+    assert!(series.is_match_over());
+    assert_eq!(series.winner(), Some(PlayerSide::Player));
+    assert_eq!(series.rounds().len(), 3);
+    // Nothing happens until rematch
+    series.advance(ROUND_BREAK * 2);
+    assert!(series.is_match_over());
+    assert_eq!(series.round(), 3);
+}
+
+#[test]
+fn drawn_round_is_replayed() {
+    let mut series = series(1);
+    top_out(&mut series, &[PlayerSide::Player, PlayerSide::Opponent]);
+    assert_eq!(series.last_round(), Some(RoundOutcome::Draw));
+    assert_eq!(series.phase(), MatchPhase::RoundOver);
+    assert_eq!(series.wins(PlayerSide::Player), 0);
+    assert_eq!(series.wins(PlayerSide::Opponent), 0);
+    next_round(&mut series);
+    assert_eq!(series.round(), 1);
+    let well = series.pair().get(PlayerSide::Player).get_field();
+    assert_eq!(well, &Field::new(4, 4));
+
+    top_out(&mut series, &[PlayerSide::Opponent]);
+    assert!(series.is_match_over());
+    assert_eq!(series.rounds().len(), 2);
+}
+
+#[test]
+fn next_round_starts_after_round_break() {
+    let mut series = series(3);
+    top_out(&mut series, &[PlayerSide::Player]);
+    series.advance(ROUND_BREAK - Duration::from_millis(1));
+    assert_eq!(series.phase(), MatchPhase::RoundOver);
+    assert!(!series.add_player_action(PlayerSide::Player, Action::MoveLeft));
+    assert_eq!(series.round(), 1);
+    series.advance(Duration::from_millis(1));
+    assert_eq!(series.phase(), MatchPhase::Playing);
+    assert_eq!(series.round(), 2);
+    assert!(series.add_player_action(PlayerSide::Player, Action::MoveLeft));
+}
+
+#[test]
+fn rematch_needs_both_players() {
+    let mut series = series(1);
+    // Requests before the series is over don't count
+    series.command(PlayerSide::Player, MatchCommand::Rematch);
+    top_out(&mut series, &[PlayerSide::Player]);
+    assert!(series.is_match_over());
+
+    series.command(PlayerSide::Opponent, MatchCommand::Rematch);
+    assert!(series.is_match_over());
+    let state = series.get_state();
+    assert!(state.rematch_opponent && !state.rematch_player);
+
+    series.command(PlayerSide::Player, MatchCommand::Rematch);
+    assert_eq!(series.phase(), MatchPhase::Playing);
+    assert_eq!(series.round(), 1);
+    assert_eq!(series.wins(PlayerSide::Opponent), 0);
+    assert!(series.rounds().is_empty());
+    let state = series.get_state();
+    assert!(!state.rematch_opponent && !state.rematch_player);
+}
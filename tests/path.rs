@@ -0,0 +1,237 @@
+use gametetris_rs::{
+    Action, DropStyle, Field, Lock, PathPlanner, Placement, Rotation, RuleSet, StepResult, TSpin,
+    Tetris, Tetromino, TetrominoType,
+};
+
+const TSD: &str = "
+    ....t.....
+    ...ttt....
+    ..........
+    ..........
+    ##........
+    #...######
+    ##.#######
+";
+
+// Queue the actions at once and return how the tetromino locked.
+// Fast drop takes a few ticks to get the tetromino to the ground
+fn replay(game: &mut Tetris, actions: &[Action]) -> Lock {
+    for &action in actions {
+        assert!(game.add_action(action));
+    }
+    (0..100)
+        .flat_map(|_| game.step())
+        .find_map(|result| match result {
+            StepResult::Locked(lock) => Some(lock),
+            _ => None,
+        })
+        .expect("tetromino locked")
+}
+
+fn sorted_cells(tetromino: &Tetromino) -> Vec<(isize, isize)> {
+    let mut cells = tetromino.cells();
+    cells.sort();
+    cells
+}
+
+fn flat_field() -> Field {
+    Field::new(10, 8)
+}
+
+fn t_at_spawn() -> Tetromino {
+    Tetromino::new(TetrominoType::T, Rotation::R0, 3, 0)
+}
+
+fn sonic() -> RuleSet {
+    RuleSet {
+        drop: DropStyle::Sonic,
+        ..RuleSet::guideline()
+    }
+}
+
+#[test]
+fn replayed_paths_lock_in_every_placement() {
+    for rules in [RuleSet::guideline(), RuleSet::tgm(), RuleSet::default()] {
+        let placements = Tetris::from_ascii(TSD, rules.clone()).unwrap().placements();
+        assert!(!placements.is_empty());
+        for placement in placements {
+            let mut game = Tetris::from_ascii(TSD, rules.clone()).unwrap();
+            let path = game.path_to(&placement).expect("placement is reachable");
+            let lock = replay(&mut game, &path);
+            assert_eq!(
+                sorted_cells(&lock.tetromino),
+                sorted_cells(&placement.tetromino),
+                "{} {:?}",
+                rules.name,
+                path
+            );
+            assert_eq!((lock.lines, lock.tspin), (placement.lines, placement.tspin));
+        }
+    }
+}
+
+#[test]
+fn path_is_shortest() {
+    let planner = PathPlanner::new(&RuleSet::guideline());
+    let field = flat_field();
+    let flat = Tetromino::new(TetrominoType::T, Rotation::R0, 0, 6);
+    assert_eq!(
+        planner.find(&field, t_at_spawn(), flat),
+        Some(vec![
+            Action::MoveLeft,
+            Action::MoveLeft,
+            Action::MoveLeft,
+            Action::Drop
+        ])
+    );
+    let upside_down = Tetromino::new(TetrominoType::T, Rotation::R180, 0, 6);
+    let path = planner.find(&field, t_at_spawn(), upside_down).unwrap();
+    assert_eq!(path.len(), 6, "{:?}", path);
+    // Tetromino which would fall further can't lock there
+    let floating = Tetromino::new(TetrominoType::T, Rotation::R0, 0, 5);
+    assert_eq!(planner.find(&field, t_at_spawn(), floating), None);
+}
+
+#[test]
+fn sonic_drop_is_followed_by_move_down() {
+    let field = flat_field();
+    let flat = Tetromino::new(TetrominoType::T, Rotation::R0, 3, 6);
+    assert_eq!(
+        PathPlanner::new(&RuleSet::guideline()).find(&field, t_at_spawn(), flat),
+        Some(vec![Action::Drop])
+    );
+    assert_eq!(
+        PathPlanner::new(&sonic()).find(&field, t_at_spawn(), flat),
+        Some(vec![Action::Drop, Action::MoveDown])
+    );
+}
+
+#[test]
+fn sonic_drop_shortens_tuck_under_overhang() {
+    let field = Field::from_ascii(
+        "
+        ..........
+        ..........
+        ..........
+        ###.......
+        ..........
+        ..........
+        ",
+    )
+    .unwrap();
+    let start = Tetromino::new(TetrominoType::S, Rotation::R0, 3, 0);
+    let tucked = Tetromino::new(TetrominoType::S, Rotation::R0, 0, 4);
+
+    // Hard drop locks at once, the tetromino is soft dropped instead
+    let hard = PathPlanner::new(&RuleSet::guideline())
+        .find(&field, start, tucked)
+        .unwrap();
+    assert_eq!(hard.iter().filter(|&&a| a == Action::MoveDown).count(), 4);
+    assert_eq!(hard.iter().filter(|&&a| a == Action::MoveLeft).count(), 3);
+    assert_eq!(hard.last(), Some(&Action::Drop));
+
+    let sonic = PathPlanner::new(&sonic())
+        .find(&field, start, tucked)
+        .unwrap();
+    assert_eq!(
+        sonic,
+        [
+            Action::Drop,
+            Action::MoveLeft,
+            Action::MoveLeft,
+            Action::MoveLeft,
+            Action::MoveDown
+        ]
+    );
+}
+
+#[test]
+fn t_spin_path_ends_with_rotation() {
+    let game = Tetris::from_ascii(TSD, RuleSet::guideline()).unwrap();
+    let tsd = game
+        .placements()
+        .into_iter()
+        .find(|placement| placement.tspin == TSpin::Full && placement.lines == 2)
+        .expect("TSD slot");
+    let path = game.path_to(&tsd).unwrap();
+    assert_eq!(path.last(), Some(&Action::Drop));
+    assert!(matches!(
+        path[path.len() - 2],
+        Action::RotateLeft | Action::RotateRight
+    ));
+}
+
+#[test]
+fn t_spin_placement_needs_rotation_even_if_reached_by_drop() {
+    let field = Field::from_ascii(
+        "
+        ..........
+        ..........
+        ..........
+        ..........
+        .#########
+        ",
+    )
+    .unwrap();
+    let planner = PathPlanner::new(&RuleSet::guideline());
+    let wall = Tetromino::new(TetrominoType::T, Rotation::R90, 0, 2);
+    let placement = |tspin| Placement {
+        tetromino: wall,
+        tspin,
+        lines: 1,
+    };
+    let start = Tetromino::new(TetrominoType::T, Rotation::R0, 1, 0);
+
+    let dropped = planner
+        .find_placement(&field, start, &placement(TSpin::None))
+        .unwrap();
+    assert_eq!(dropped.last(), Some(&Action::Drop));
+    assert!(!matches!(
+        dropped[dropped.len() - 2],
+        Action::RotateLeft | Action::RotateRight
+    ));
+
+    let spun = planner
+        .find_placement(&field, start, &placement(TSpin::Mini))
+        .unwrap();
+    assert!(spun.len() > dropped.len());
+    assert!(matches!(
+        spun[spun.len() - 2],
+        Action::RotateLeft | Action::RotateRight
+    ));
+    assert_eq!(spun.last(), Some(&Action::Drop));
+}
+
+#[test]
+fn fall_every_limits_moves_before_falling() {
+    // The O has to cross the column before falling below its top
+    let field = Field::from_ascii(
+        "
+        ..........
+        ..........
+        ..#.......
+        ..#.......
+        ..#.......
+        ..#.......
+        ..#.......
+        ..#.......
+        ",
+    )
+    .unwrap();
+    let start = Tetromino::new(TetrominoType::O, Rotation::R0, 4, 0);
+    let target = Tetromino::new(TetrominoType::O, Rotation::R0, 0, 6);
+    let planner = PathPlanner::new(&RuleSet::guideline());
+    let path = |fall_every| {
+        planner
+            .with_fall_every(fall_every)
+            .find(&field, start, target)
+    };
+
+    assert!(path(None).is_some());
+    // Falls onto the column after the second move, then slides off it
+    assert!(path(Some(2)).is_some());
+    // Falls a row after the first move and hits the column
+    assert_eq!(path(Some(1)), None);
+    // Falls to the ground at once
+    assert_eq!(path(Some(0)), None);
+}
@@ -0,0 +1,156 @@
+use gametetris_rs::{
+    Field, Placement, Rotation, RotationSystem, RuleSet, TSpin, Tetris, Tetromino, TetrominoType,
+};
+
+fn find_all(ascii: &str, tetromino_type: TetrominoType) -> Vec<Placement> {
+    let field = Field::from_ascii(ascii).unwrap();
+    Placement::find_all(&field, tetromino_type, RotationSystem::Srs)
+}
+
+// The placement which fills the given cells
+fn filling(placements: &[Placement], cells: &[(isize, isize)]) -> Placement {
+    let matching: Vec<&Placement> = placements
+        .iter()
+        .filter(|p| {
+            let mut filled = p.tetromino.cells();
+            filled.sort();
+            filled == cells
+        })
+        .collect();
+    assert_eq!(matching.len(), 1, "one placement fills {:?}", cells);
+    *matching[0]
+}
+
+#[test]
+fn flat_board_counts() {
+    let empty = Field::new(10, 20);
+    let count = |t| Placement::find_all(&empty, t, RotationSystem::Srs).len();
+    // 8 + 9 + 8 + 9 for the four rotations
+    assert_eq!(count(TetrominoType::T), 34);
+    assert_eq!(count(TetrominoType::L), 34);
+    assert_eq!(count(TetrominoType::J), 34);
+    // Both horizontal and both vertical rotations leave the same boards
+    assert_eq!(count(TetrominoType::I), 17);
+    assert_eq!(count(TetrominoType::S), 17);
+    assert_eq!(count(TetrominoType::Z), 17);
+    assert_eq!(count(TetrominoType::O), 9);
+}
+
+#[test]
+fn placements_are_listed_once_per_board() {
+    let placements = find_all("....\n....\n....\n....", TetrominoType::S);
+    let mut boards: Vec<Vec<(isize, isize)>> = placements
+        .iter()
+        .map(|p| {
+            let mut cells = p.tetromino.cells();
+            cells.sort();
+            cells
+        })
+        .collect();
+    boards.sort();
+    boards.dedup();
+    assert_eq!(boards.len(), placements.len());
+    assert_eq!(placements.len(), 5);
+    assert!(placements
+        .iter()
+        .all(|p| p.tspin == TSpin::None && p.lines == 0));
+}
+
+#[test]
+fn tetrominos_slide_under_overhang() {
+    let overhang = "
+        ..........
+        ..........
+        ..........
+        ###.......
+        ..........
+        ..........
+    ";
+    // Soft dropped to the floor and moved left under the overhang
+    let s = find_all(overhang, TetrominoType::S);
+    let tucked = filling(&s, &[(0, 5), (1, 4), (1, 5), (2, 4)]);
+    assert_eq!(tucked.tetromino.rotation(), Rotation::R0);
+    assert_eq!(tucked.tspin, TSpin::None);
+    let z = find_all(overhang, TetrominoType::Z);
+    filling(&z, &[(0, 4), (1, 4), (1, 5), (2, 5)]);
+}
+
+#[test]
+fn tsd_slot_is_reached_by_rotation() {
+    let placements = find_all(
+        "
+        ..........
+        ..........
+        ..........
+        ##........
+        #...######
+        ##.#######
+        ",
+        TetrominoType::T,
+    );
+    let tsd = filling(&placements, &[(1, 4), (2, 4), (2, 5), (3, 4)]);
+    assert_eq!(tsd.tetromino.rotation(), Rotation::R180);
+    assert_eq!(tsd.tspin, TSpin::Full);
+    assert_eq!(tsd.lines, 2);
+}
+
+#[test]
+fn t_spin_is_kept_when_placement_is_also_reached_by_drop() {
+    // A T pointing right dropped along the left wall, or later rotated in from the floor
+    let field = Field::from_ascii(
+        "
+        ..........
+        ..........
+        ..........
+        ..........
+        .#########
+        ",
+    )
+    .unwrap();
+    let above = Tetromino::new(TetrominoType::T, Rotation::R90, 0, 0);
+    let placements = Placement::find_from(&field, above, RotationSystem::Srs);
+    let wall = filling(&placements, &[(0, 2), (0, 3), (0, 4), (1, 3)]);
+    assert_eq!(wall.tspin, TSpin::Mini);
+    assert_eq!(wall.lines, 1);
+    assert_eq!(
+        placements.iter().filter(|p| p.tspin != TSpin::None).count(),
+        1
+    );
+}
+
+#[test]
+fn placements_start_from_current_tetromino() {
+    // Below the overhang the T can't get back on top of it
+    let game = Tetris::from_ascii(
+        "
+        ..........
+        ..........
+        ###.......
+        ....t.....
+        ...ttt....
+        ..........
+        ",
+        RuleSet::guideline(),
+    )
+    .unwrap();
+    let placements = game.placements();
+    assert_eq!(
+        placements,
+        Placement::find_from(
+            game.get_field(),
+            game.get_current().unwrap(),
+            RotationSystem::Srs
+        )
+    );
+    assert!(!placements.is_empty());
+    assert!(placements
+        .iter()
+        .all(|p| p.tetromino.cells().iter().all(|&(_, y)| y >= 3)));
+    // Still under the overhang after moving left
+    filling(&placements, &[(0, 5), (1, 4), (1, 5), (2, 5)]);
+}
+
+#[test]
+fn no_placements_when_tetromino_can_not_spawn() {
+    assert!(find_all("....", TetrominoType::T).is_empty());
+}
@@ -0,0 +1,76 @@
+use gametetris_rs::{Puzzle, PuzzleGoal, TetrominoType};
+
+const TSD: &str = "
+    name: First T-spin
+    goal: tspin-double
+    pieces: T I
+    rows: 6
+    ; Slot on the left
+    board:
+    G.........
+    ...GGGGGGG
+    G.GGGGGGGG
+";
+
+#[test]
+fn puzzle_from_text() {
+    let puzzle = Puzzle::from_text(TSD).unwrap();
+    assert_eq!(puzzle.name, "First T-spin");
+    assert_eq!(puzzle.goal, PuzzleGoal::TSpin(2));
+    assert_eq!(puzzle.pieces, [TetrominoType::T, TetrominoType::I]);
+    assert_eq!(puzzle.rows, 6);
+    assert_eq!(puzzle.board, ["G.........", "...GGGGGGG", "G.GGGGGGGG"]);
+}
+
+#[test]
+fn first_board_row_may_follow_the_key() {
+    let text = "goal: clear-lines 1\npieces: I\nboard: G.........\n.GGGGGGGGG";
+    let puzzle = Puzzle::from_text(text).unwrap();
+    assert_eq!(puzzle.board, ["G.........", ".GGGGGGGGG"]);
+}
+
+#[test]
+fn puzzle_text_errors() {
+    assert_eq!(
+        Puzzle::from_text("pieces: T\nboard:\nG.........").unwrap_err(),
+        "goal is missing"
+    );
+    assert_eq!(
+        Puzzle::from_text("goal: perfect-clear\npieces: T\nfumen: v115@vhAAgH\nboard:\nG...")
+            .unwrap_err(),
+        "both board and fumen are given"
+    );
+    assert_eq!(
+        Puzzle::from_text("goal: perfect-clear\nboard:\nG...").unwrap_err(),
+        "pieces are missing"
+    );
+    assert_eq!(
+        Puzzle::from_text("goal: win\npieces: T").unwrap_err(),
+        "unknown goal: win"
+    );
+    assert_eq!(
+        Puzzle::from_text("goal: perfect-clear\npieces: T X").unwrap_err(),
+        "unknown tetromino: X"
+    );
+}
+
+#[test]
+fn pack_is_split_at_separator_lines() {
+    let text = format!(
+        "{}---\ngoal: perfect-clear\npieces: O\nboard:\n..GG\n..GG\n---\n",
+        TSD
+    );
+    let pack = Puzzle::pack_from_text(&text).unwrap();
+    assert_eq!(pack.len(), 2);
+    assert_eq!(pack[0], Puzzle::from_text(TSD).unwrap());
+    assert_eq!(pack[1].goal, PuzzleGoal::PerfectClear);
+    assert_eq!(pack[1].board, ["..GG", "..GG"]);
+    // Error of any puzzle fails the pack
+    assert!(Puzzle::pack_from_text(&format!("{}---\npieces: O", TSD)).is_err());
+}
+
+#[test]
+fn puzzle_round_trips_through_json() {
+    let puzzle = Puzzle::from_text(TSD).unwrap();
+    assert_eq!(Puzzle::from_json(&puzzle.to_json()).unwrap(), puzzle);
+}
@@ -0,0 +1,23 @@
+use gametetris_rs::{BotDifficulty, RuleSet};
+
+#[test]
+fn rules_are_loaded_by_preset_name_or_file() {
+    assert_eq!(RuleSet::load("NES").unwrap(), RuleSet::nes());
+    let path = std::env::temp_dir().join(format!("gametetris-rules-{}.json", std::process::id()));
+    std::fs::write(&path, RuleSet::tgm().to_json()).unwrap();
+    let rules = RuleSet::load(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(rules.unwrap(), RuleSet::tgm());
+    let err = RuleSet::load("no-such-rules").unwrap_err();
+    assert!(err.contains("guideline"), "{}", err);
+}
+
+#[test]
+fn difficulty_is_loaded_by_preset_name_or_file() {
+    assert!(BotDifficulty::load("hard").is_ok());
+    let path = std::env::temp_dir().join(format!("gametetris-level-{}.json", std::process::id()));
+    std::fs::write(&path, "not json").unwrap();
+    let difficulty = BotDifficulty::load(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert!(difficulty.unwrap_err().starts_with("invalid level file"));
+}
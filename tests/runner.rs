@@ -0,0 +1,116 @@
+use std::{
+    sync::{atomic::AtomicBool, mpsc},
+    time::Duration,
+};
+
+use gametetris_rs::{FakeClock, Game, GameRunner, RunnerHandle, TICK};
+
+// Counts ticks and keeps the inputs, the number of ticks is published every tick
+#[derive(Default)]
+struct Counter {
+    ticks: usize,
+    inputs: Vec<u32>,
+}
+
+impl Game for Counter {
+    type Input = u32;
+    type State = usize;
+    fn handle_input(&mut self, input: u32) {
+        self.inputs.push(input);
+    }
+    fn advance(&mut self, elapsed: Duration) -> bool {
+        assert_eq!(elapsed, TICK);
+        self.ticks += 1;
+        true
+    }
+    fn state(&self) -> usize {
+        self.ticks
+    }
+}
+
+// Runner on its own thread with the first tick done at time zero
+fn start(
+    clock: &FakeClock,
+    max_catch_up: usize,
+    inputs: mpsc::Receiver<u32>,
+) -> (RunnerHandle<Counter>, mpsc::Receiver<usize>) {
+    let (sink, states) = mpsc::channel();
+    let runner = GameRunner::new(Counter::default())
+        .with_clock(clock.clone())
+        .with_max_catch_up(max_catch_up)
+        .with_input(inputs)
+        .with_sink(sink)
+        .spawn();
+    assert_eq!(states.recv().unwrap(), 1);
+    (runner, states)
+}
+
+// States published until the runner waits for the clock
+fn published(states: &mpsc::Receiver<usize>) -> Vec<usize> {
+    let mut published = Vec::new();
+    while let Ok(state) = states.recv_timeout(Duration::from_millis(200)) {
+        published.push(state);
+    }
+    published
+}
+
+#[test]
+fn missed_ticks_are_run_back_to_back() {
+    let clock = FakeClock::new();
+    let (_inputs, receiver) = mpsc::channel();
+    let (runner, states) = start(&clock, 10, receiver);
+    clock.advance(TICK * 3);
+    assert_eq!(published(&states), [2, 3, 4]);
+    assert_eq!(runner.stop_and_join().ticks, 4);
+}
+
+#[test]
+fn catch_up_is_capped_at_max_catch_up() {
+    let clock = FakeClock::new();
+    let (_inputs, receiver) = mpsc::channel();
+    let (runner, states) = start(&clock, 5, receiver);
+    clock.advance(TICK * 25);
+    assert_eq!(published(&states), [2, 3, 4, 5, 6]);
+    runner.stop_and_join();
+}
+
+#[test]
+fn backlog_is_dropped_after_stall() {
+    let clock = FakeClock::new();
+    let (_inputs, receiver) = mpsc::channel();
+    let (runner, states) = start(&clock, 5, receiver);
+    clock.advance(TICK * 25);
+    assert_eq!(published(&states).len(), 5);
+    // The next tick is scheduled a tick after the stall, the rest of it isn't run
+    clock.advance(TICK / 2);
+    assert!(published(&states).is_empty());
+    clock.advance(TICK / 2);
+    assert_eq!(published(&states), [7]);
+    runner.stop_and_join();
+}
+
+#[test]
+fn runner_stops_when_sink_disconnects() {
+    let (sink, states) = mpsc::channel();
+    drop(states);
+    let stop = AtomicBool::new(false);
+    let game = GameRunner::new(Counter::default())
+        .with_clock(FakeClock::new())
+        .with_sink(sink)
+        .run(&stop);
+    assert_eq!(game.ticks, 1);
+}
+
+#[test]
+fn stop_and_join_returns_the_game() {
+    let clock = FakeClock::new();
+    let (inputs, receiver) = mpsc::channel();
+    let (runner, states) = start(&clock, 10, receiver);
+    inputs.send(7).unwrap();
+    inputs.send(8).unwrap();
+    clock.advance(TICK);
+    assert_eq!(states.recv().unwrap(), 2);
+    let game = runner.stop_and_join();
+    assert_eq!(game.ticks, 2);
+    assert_eq!(game.inputs, [7, 8]);
+}
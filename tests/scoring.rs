@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use gametetris_rs::{Action, Lock, Puzzle, RuleSet, StepResult, TSpin};
+
+// Soft drop the T next to the slot under the overhang, rotate it in and hard drop it.
+// Returns the lock and points given for it
+fn spin_t(board: &str) -> (Lock, usize) {
+    let text = format!("goal: clear-lines 1\npieces: T\nboard:\n{}", board);
+    let puzzle = Puzzle::from_text(&text).unwrap();
+    let mut game = puzzle.create_tetris(RuleSet::guideline()).unwrap();
+    game.advance(Duration::from_millis(1));
+    game.add_action(Action::RotateRight);
+    for _ in 0..3 {
+        game.add_action(Action::MoveLeft);
+    }
+    for _ in 0..game.rows() {
+        game.add_action(Action::MoveDown);
+    }
+    game.advance(Duration::from_millis(1));
+    let before = game.score().score;
+    game.add_action(Action::RotateRight);
+    game.add_action(Action::Drop);
+    let results = game.advance(Duration::from_millis(1));
+    let lock = results
+        .into_iter()
+        .find_map(|result| match result {
+            StepResult::Locked(lock) => Some(lock),
+            _ => None,
+        })
+        .expect("tetromino locked");
+    let points = game.score().score - before;
+    (lock, points)
+}
+
+#[test]
+fn t_spin_double_scores_more_than_double() {
+    let (lock, points) = spin_t(
+        "\
+G.........
+...GGGGGGG
+G.GGGGGGGG
+",
+    );
+    assert_eq!((lock.lines, lock.tspin), (2, TSpin::Full));
+    assert_eq!(points, 1200);
+}
+
+#[test]
+fn t_spin_without_lines_scores() {
+    let (lock, points) = spin_t(
+        "\
+G.........
+...GGGGG.G
+G.GGGGGG.G
+",
+    );
+    assert_eq!((lock.lines, lock.tspin), (0, TSpin::Full));
+    assert_eq!(points, 400);
+}
@@ -0,0 +1,259 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use gametetris_rs::{
+    Action, Bot, BotMove, Rotation, RuleSet, StepResult, TSpin, TbpBot, TbpBotMessage,
+    TbpFrontendMessage, TbpLocation, TbpMove, TbpSpin, TbpStart, Tetris, Tetromino,
+};
+
+// Shell script speaking TBP: `suggest` runs for each suggest request and `quit` for quit,
+// the answers to the other messages are fixed. Received messages are logged, see `received`
+fn stub_bot(name: &str, suggest: &str, quit: &str) -> (TbpBot, PathBuf) {
+    let path = std::env::temp_dir().join(format!("gametetris-{}-{}.sh", name, std::process::id()));
+    let script = format!(
+        r#"echo '{{"type":"info","name":"{}","version":"1","author":"test","features":[]}}'
+suggested=0
+while read -r line; do
+    printf '%s\n' "$line" >> '{}'
+    case "$line" in
+        *'"rules"'*) echo '{{"type":"ready"}}' ;;
+        *'"suggest"'*) suggested=$((suggested + 1)); {} ;;
+        *'"quit"'*) {} ;;
+    esac
+done
+"#,
+        name,
+        log_path(&path).display(),
+        suggest,
+        quit
+    );
+    fs::write(&path, script).unwrap();
+    let mut command = Command::new("sh");
+    command.arg(&path);
+    (TbpBot::spawn(command).unwrap(), path)
+}
+
+// Heuristic bot of `tbp_bot` served by `TbpServer`, the messages it gets are logged
+fn server_bot(name: &str) -> (TbpBot, PathBuf) {
+    let path = std::env::temp_dir().join(format!("gametetris-{}-{}", name, std::process::id()));
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(format!(
+        "tee '{}' | '{}'",
+        log_path(&path).display(),
+        env!("CARGO_BIN_EXE_tbp_bot")
+    ));
+    let bot = TbpBot::spawn(command).unwrap();
+    (bot.with_timeout(Duration::from_secs(10)), path)
+}
+
+fn log_path(path: &Path) -> PathBuf {
+    path.with_extension("log")
+}
+
+// Messages the bot got so far, the log and the script are removed
+fn received(path: &Path) -> Vec<TbpFrontendMessage> {
+    let log = fs::read_to_string(log_path(path)).unwrap_or_default();
+    let _ = fs::remove_file(log_path(path));
+    let _ = fs::remove_file(path);
+    log.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+// Message types with runs of new pieces counted as one
+fn message_types(messages: &[TbpFrontendMessage]) -> Vec<&'static str> {
+    let mut types: Vec<&'static str> = messages
+        .iter()
+        .map(|message| match message {
+            TbpFrontendMessage::Rules => "rules",
+            TbpFrontendMessage::Start(_) => "start",
+            TbpFrontendMessage::Stop => "stop",
+            TbpFrontendMessage::Suggest => "suggest",
+            TbpFrontendMessage::Play { .. } => "play",
+            TbpFrontendMessage::NewPiece { .. } => "new_piece",
+            TbpFrontendMessage::Quit => "quit",
+        })
+        .collect();
+    types.dedup_by(|a, b| *a == "new_piece" && *b == "new_piece");
+    types
+}
+
+// Play the move the way `Bot` users do: hold first, then queue the path at once
+fn play(game: &mut Tetris, bot_move: BotMove) {
+    if bot_move.hold {
+        assert!(game.add_action(Action::Hold));
+        game.step();
+    }
+    for action in game
+        .path_to(&bot_move.placement)
+        .expect("move is reachable")
+    {
+        assert!(game.add_action(action));
+    }
+    let locked = (0..100)
+        .flat_map(|_| game.step())
+        .any(|result| matches!(result, StepResult::Locked(_)));
+    assert!(locked);
+    while game.get_current().is_none() {
+        game.step();
+    }
+}
+
+fn game() -> Tetris {
+    let mut game = Tetris::with_rules(10, 20, RuleSet::guideline());
+    game.set_seed(1);
+    game.place_next_tetromino();
+    game
+}
+
+// Current tetromino dropped straight down after moving it by `dx`
+fn drop_at(game: &Tetris, dx: isize) -> Tetromino {
+    dropped(game, game.get_current().unwrap().moved(dx, 0, Rotation::R0))
+}
+
+fn dropped(game: &Tetris, mut tetromino: Tetromino) -> Tetromino {
+    while !tetromino
+        .moved(0, 1, Rotation::R0)
+        .intersects(game.get_field())
+    {
+        tetromino = tetromino.moved(0, 1, Rotation::R0);
+    }
+    tetromino
+}
+
+fn suggestion(game: &Tetris, tetromino: &Tetromino, spin: TbpSpin) -> String {
+    let location = TbpLocation::from_tetromino(tetromino, game.rows());
+    let message = TbpBotMessage::Suggestion {
+        moves: vec![TbpMove { location, spin }],
+    };
+    format!("echo '{}'", serde_json::to_string(&message).unwrap())
+}
+
+fn placed(bot_move: Option<BotMove>) -> Tetromino {
+    bot_move.expect("suggestion is played").placement.tetromino
+}
+
+#[test]
+fn late_suggestion_is_not_taken_for_the_next_one() {
+    let game = game();
+    let (late, fresh) = (drop_at(&game, -2), drop_at(&game, 2));
+    // The first suggestion comes after the timeout
+    let suggest = format!(
+        r#"if [ $suggested = 1 ]; then sleep 0.5; {}; else {}; fi"#,
+        suggestion(&game, &late, TbpSpin::None),
+        suggestion(&game, &fresh, TbpSpin::None)
+    );
+    let (bot, path) = stub_bot("late", &suggest, "exit 0");
+    let mut bot = bot.with_timeout(Duration::from_millis(300));
+    assert!(bot.choose(&game).is_none());
+    let chosen = placed(bot.choose(&game));
+    drop(bot);
+    received(&path);
+    assert_eq!(chosen, fresh);
+}
+
+#[test]
+fn claimed_spin_is_checked() {
+    let game = game();
+    let flat = drop_at(&game, 0);
+    let (mut bot, path) = stub_bot("spin", &suggestion(&game, &flat, TbpSpin::Full), "exit 0");
+    let bot_move = bot.choose(&game).expect("suggestion is played");
+    drop(bot);
+    received(&path);
+    assert_eq!(bot_move.placement.tetromino, flat);
+    assert_eq!(bot_move.placement.tspin, TSpin::None);
+}
+
+#[test]
+fn bot_which_does_not_quit_is_killed() {
+    let (bot, path) = stub_bot("stuck", ":", ":");
+    let started = Instant::now();
+    drop(bot);
+    received(&path);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn bot_is_started_again_when_hold_is_not_allowed() {
+    let rules = RuleSet {
+        hold: false,
+        ..RuleSet::guideline()
+    };
+    let mut game = Tetris::with_rules(10, 20, rules);
+    game.set_seed(1);
+    game.place_next_tetromino();
+    let current = drop_at(&game, 0);
+    let next = *game.get_next().unwrap();
+    let held = dropped(&game, Tetromino::new(next, Rotation::R0, 0, 0));
+    // The first suggestion holds, the one for the current tetromino alone doesn't
+    let suggest = format!(
+        r#"if [ $suggested = 1 ]; then {}; else {}; fi"#,
+        suggestion(&game, &held, TbpSpin::None),
+        suggestion(&game, &current, TbpSpin::None)
+    );
+    let (mut bot, path) = stub_bot("hold", &suggest, "exit 0");
+    let bot_move = bot.choose(&game).expect("suggestion is played");
+    drop(bot);
+    let messages = received(&path);
+    assert!(!bot_move.hold);
+    assert_eq!(bot_move.placement.tetromino, current);
+    assert_eq!(
+        message_types(&messages),
+        ["rules", "start", "suggest", "stop", "start", "suggest", "play", "quit"]
+    );
+    let TbpFrontendMessage::Start(start) = &messages[4] else {
+        panic!("start expected");
+    };
+    assert_eq!(start.queue, [current.tetromino_type()]);
+    assert_eq!(start.hold, None);
+}
+
+#[test]
+fn server_is_told_new_pieces_until_garbage_comes() {
+    let mut game = game();
+    let (mut bot, path) = server_bot("server");
+    assert_eq!(bot.name(), "gametetris heuristic");
+    for _ in 0..4 {
+        let bot_move = bot.choose(&game).expect("bot plays");
+        play(&mut game, bot_move);
+    }
+    game.add_action(Action::BottomRefill);
+    game.step();
+    let bot_move = bot.choose(&game).expect("bot plays after garbage");
+    play(&mut game, bot_move);
+    drop(bot);
+
+    let messages = received(&path);
+    #[rustfmt::skip]
+    assert_eq!(
+        message_types(&messages),
+        [
+            "rules",
+            "start", "suggest", "play",
+            "new_piece", "suggest", "play",
+            "new_piece", "suggest", "play",
+            "new_piece", "suggest", "play",
+            "stop", "start", "suggest", "play",
+            "quit",
+        ]
+    );
+    let starts: Vec<&TbpStart> = messages
+        .iter()
+        .filter_map(|message| match message {
+            TbpFrontendMessage::Start(start) => Some(start),
+            _ => None,
+        })
+        .collect();
+    // Current tetromino and the preview
+    assert_eq!(starts[0].queue.len(), 6);
+    assert!(starts[0].board.iter().flatten().all(Option::is_none));
+    // Garbage row with holes under the stack
+    let bottom = &starts[1].board[0];
+    assert!(bottom.contains(&None) && bottom.contains(&Some('G')));
+    assert!(bottom.iter().flatten().all(|&cell| cell == 'G'));
+    assert!(starts[1].board[1].iter().flatten().any(|&cell| cell != 'G'));
+}
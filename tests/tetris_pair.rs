@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use gametetris_rs::{
+    Action, PlayerSettings, PlayerSide, RuleSet, StepResult, TetrisPair, TetrominoType,
+};
+
+// Pair of 4 wide wells, where each flat I clears a line, started without countdown
+fn started_pair(player: &PlayerSettings, opponent: &PlayerSettings) -> TetrisPair {
+    let mut pair = TetrisPair::with_settings(player, opponent);
+    for side in [PlayerSide::Player, PlayerSide::Opponent] {
+        pair.get_mut(side)
+            .set_fixed_queue(Some(vec![TetrominoType::I; 20]));
+    }
+    pair.set_shared_seed(Some(1));
+    pair
+}
+
+// Hard drop the tetromino of the side and step until it clears a line
+fn clear_line(pair: &mut TetrisPair, side: PlayerSide) {
+    while pair.get(side).get_current().is_none() {
+        pair.step();
+    }
+    assert!(pair.add_player_action(side, Action::Drop));
+    for _ in 0..100 {
+        let (player, opponent) = pair.step();
+        let results = match side {
+            PlayerSide::Player => player,
+            PlayerSide::Opponent => opponent,
+        };
+        if results.contains(&StepResult::LinesRemoved(1)) {
+            return;
+        }
+    }
+    panic!("no line cleared");
+}
+
+// Garbage rows of the side once the queued refills are applied
+fn garbage_rows(pair: &mut TetrisPair, side: PlayerSide) -> usize {
+    pair.step();
+    pair.get(side).get_field().garbage_lines()
+}
+
+#[test]
+fn garbage_multiplier_per_zero_lines_counts_per_line() {
+    let settings = PlayerSettings::with_rules(4, 8, RuleSet::guideline());
+    let player = PlayerSettings {
+        garbage_multiplier: (2, 0),
+        ..settings.clone()
+    };
+    let mut pair = started_pair(&player, &settings);
+    clear_line(&mut pair, PlayerSide::Opponent);
+    assert_eq!(garbage_rows(&mut pair, PlayerSide::Player), 2);
+
+    pair.set_player_garbage_multiplier(PlayerSide::Opponent, 1, 0);
+    clear_line(&mut pair, PlayerSide::Player);
+    assert_eq!(garbage_rows(&mut pair, PlayerSide::Opponent), 1);
+}
+
+// Tetrominos each side got, the player drops each one at once and the opponent
+// every `opponent_every` steps
+fn sequences(
+    pair: &mut TetrisPair,
+    pieces: usize,
+    opponent_every: usize,
+) -> [Vec<TetrominoType>; 2] {
+    let mut sequences = [Vec::new(), Vec::new()];
+    let sides = [PlayerSide::Player, PlayerSide::Opponent];
+    for step in 0..pieces * opponent_every * 2 {
+        for (side, sequence) in sides.iter().zip(&mut sequences) {
+            let tetris = pair.get(*side);
+            let Some(current) = tetris.get_current() else {
+                continue;
+            };
+            if sequence.len() == tetris.score().pieces && sequence.len() < pieces {
+                sequence.push(current.tetromino_type());
+            }
+            let every = match side {
+                PlayerSide::Player => 1,
+                PlayerSide::Opponent => opponent_every,
+            };
+            if step % every == 0 && tetris.score().pieces + 1 < pieces {
+                pair.add_player_action(*side, Action::Drop);
+            }
+        }
+        pair.step();
+    }
+    sequences
+}
+
+#[test]
+fn shared_seed_gives_the_same_sequence_at_any_pace() {
+    let mut pair = TetrisPair::with_settings(
+        &PlayerSettings::with_rules(10, 20, RuleSet::guideline()),
+        &PlayerSettings::with_rules(10, 20, RuleSet::guideline()),
+    );
+    pair.set_shared_seed(Some(7));
+    let [player, opponent] = sequences(&mut pair, 7, 20);
+    assert_eq!(player.len(), 7);
+    assert_eq!(player, opponent);
+
+    // The next game gets another shared seed
+    pair.reset();
+    assert_ne!(pair.shared_seed(), Some(7));
+    let [player_next, opponent_next] = sequences(&mut pair, 7, 5);
+    assert_eq!(player_next, opponent_next);
+    assert_ne!(player_next, player);
+}
+
+#[test]
+fn pause_holds_until_both_players_resume() {
+    let settings = PlayerSettings::with_rules(10, 20, RuleSet::guideline());
+    let mut pair = TetrisPair::with_settings(&settings, &settings);
+    pair.set_countdown(1, Duration::from_millis(100));
+    pair.start_countdown();
+    while pair.get(PlayerSide::Player).get_current().is_none() {
+        pair.step();
+    }
+    let falling = *pair.get(PlayerSide::Player).get_current();
+
+    pair.pause(PlayerSide::Player);
+    pair.pause(PlayerSide::Opponent);
+    pair.advance(Duration::from_secs(5));
+    assert_eq!(*pair.get(PlayerSide::Player).get_current(), falling);
+    assert!(!pair.add_player_action(PlayerSide::Opponent, Action::MoveLeft));
+
+    pair.resume(PlayerSide::Player);
+    assert!(pair.is_paused());
+    assert!(!pair.is_paused_by(PlayerSide::Player));
+    assert!(pair.is_paused_by(PlayerSide::Opponent));
+    pair.advance(Duration::from_secs(5));
+    assert_eq!(*pair.get(PlayerSide::Player).get_current(), falling);
+
+    // Both games continue after the countdown
+    pair.resume(PlayerSide::Opponent);
+    assert!(!pair.is_paused());
+    for side in [PlayerSide::Player, PlayerSide::Opponent] {
+        assert_eq!(pair.get(side).countdown(), Some(1));
+    }
+    pair.advance(Duration::from_millis(100));
+    assert_eq!(pair.get(PlayerSide::Player).countdown(), None);
+    pair.advance(Duration::from_secs(5));
+    assert_ne!(*pair.get(PlayerSide::Player).get_current(), falling);
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use gametetris_rs::{Bot, BotDifficulty, HeuristicBot, PlayerSettings, Tournament};
+
+fn paced_bot(seed: u64) -> Box<dyn Bot> {
+    Box::new(
+        HeuristicBot::default()
+            .with_difficulty(&BotDifficulty::beginner())
+            .with_seed(seed),
+    )
+}
+
+fn tournament() -> Tournament {
+    Tournament::new(PlayerSettings::new(10, 20))
+        .with_games(2, 1)
+        .with_max_time(Duration::from_secs(20))
+        .with_threads(2)
+}
+
+#[test]
+fn games_with_paced_bots_are_replayed_the_same() {
+    let run = || {
+        tournament()
+            .with_bot("first, paced", paced_bot)
+            .unwrap()
+            .with_bot("second \"paced\"", paced_bot)
+            .unwrap()
+            .run()
+            .games_csv()
+    };
+    let games = run();
+    assert_eq!(run(), games);
+    let mut lines = games.lines().skip(1);
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("\"first, paced\",\"second \"\"paced\"\"\",1,"));
+}
+
+#[test]
+fn bot_names_must_differ() {
+    let err = tournament()
+        .with_bot("bot", paced_bot)
+        .unwrap()
+        .with_tbp("bot", "true", &[])
+        .err()
+        .expect("duplicate name is rejected");
+    assert_eq!(err, "bot bot is registered twice");
+}